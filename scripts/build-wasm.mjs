@@ -1371,9 +1371,37 @@ panic = "abort"
 
     console.log("Building wasm (cargo build)...");
 
-    // Determine SUI and SUI_MOVE versions for the binary
+    // Versions build_info reports. build.rs fails without them: standalone, Cargo.lock only
+    // has the compiler crates, whose versions are placeholders. `sui-move` shares the
+    // workspace version of the checked-out tag.
     const suiVersion = SUI_VERSION_TAG;
-    const suiMoveVersion = "2024.beta"; // approximated or extracted
+    const suiMoveVersion = SUI_VERSION_TAG.replace(/^v/, "");
+
+    // `--native`: check the crate for the host target instead, without the wasm exports,
+    // and build the `native_compile` example `test:native` cross-checks wasm output with.
+    if (process.argv.includes("--native")) {
+      const nativeEnv = {
+        ...process.env,
+        SUI_VERSION: suiVersion,
+        SUI_MOVE_VERSION: suiMoveVersion,
+      };
+      const featureArgs = ["--no-default-features", "--features", "testing"];
+      await run(
+        "cargo",
+        ["clippy", "--all-targets", ...featureArgs, "--", "-D", "warnings"],
+        { cwd: crateDir, env: nativeEnv }
+      );
+      await run("cargo", ["test", ...featureArgs], {
+        cwd: crateDir,
+        env: nativeEnv,
+      });
+      await run(
+        "cargo",
+        ["build", "--release", "--example", "native_compile", ...featureArgs],
+        { cwd: crateDir, env: nativeEnv }
+      );
+      return;
+    }
 
     const releaseEnv = {
       ...process.env,
@@ -1413,17 +1441,17 @@ panic = "abort"
 
     // 4.5 Build Steps (Lite & Full)
     const buildProfiles = [
-      { name: "lite", features: [], outDir: path.join(distDir, "lite") },
+      { name: "lite", features: ["wasm"], outDir: path.join(distDir, "lite") },
       {
         name: "full",
-        features: ["testing"],
+        features: ["wasm", "testing", "coverage"],
         outDir: path.join(distDir, "full"),
       },
     ];
 
     for (const profile of buildProfiles) {
       console.log(
-        `\nBuilding '${profile.name}' WASM (Features: ${profile.features.join(", ") || "none"})...`
+        `\nBuilding '${profile.name}' WASM (Features: ${profile.features.join(", ")})...`
       );
 
       const buildArgs = [
@@ -1433,12 +1461,11 @@ panic = "abort"
         "--target",
         "wasm32-unknown-unknown",
       ];
-      if (profile.features.length > 0) {
-        buildArgs.push("--features", profile.features.join(","));
-      } else {
-        // Explicitly disable default features if any (though we set default = [])
-        buildArgs.push("--no-default-features");
-      }
+      buildArgs.push(
+        "--no-default-features",
+        "--features",
+        profile.features.join(",")
+      );
 
       // Select environment based on profile name
       const env = profile.name === "lite" ? liteEnv : fullEnv;
@@ -26,11 +26,11 @@ use colored::*;
 
 use move_binary_format::{
     binary_config::BinaryConfig,
-    errors::{Location, VMResult},
-    file_format::CompiledModule,
+    errors::{Location, VMError, VMResult},
+    file_format::{Bytecode, CompiledModule, FunctionDefinitionIndex},
 };
 use move_bytecode_utils::Modules;
-use move_command_line_common::error_bitset::ErrorBitset;
+use move_command_line_common::{error_bitset::ErrorBitset, files::FileHash};
 use move_compiler::{
     compiled_unit::NamedCompiledModule,
     unit_test::{ExpectedFailure, ModuleTestPlan, MoveErrorType, TestArgument, TestCase, TestPlan},
@@ -44,8 +44,9 @@ use move_core_types::{
     u256::U256,
     vm_status::StatusCode,
 };
-// PATCH: Removed MoveTraceBuilder usage to avoid panic in Wasm
+// PATCH: Traces are not saved to disk in Wasm; they only feed the coverage map below.
 // use move_trace_format::format::{MoveTraceBuilder, TRACE_FILE_EXTENSION};
+use move_trace_format::format::{MoveTrace, MoveTraceBuilder, TraceEvent};
 
 use move_vm_runtime::{move_vm::MoveVM, native_functions::NativeFunctionTable};
 use move_vm_test_utils::{
@@ -55,7 +56,12 @@ use move_vm_test_utils::{
 use rand::{Rng, SeedableRng, rngs::StdRng};
 use rayon::prelude::*;
 use regex::Regex;
-use std::{collections::BTreeMap, io::Write, marker::Send, sync::Mutex};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+    marker::Send,
+    sync::Mutex,
+};
 
 // Mock Instant for Wasm
 #[derive(Clone, Copy)]
@@ -80,6 +86,195 @@ pub struct SharedTestingConfig {
     trace_location: Option<String>,
 }
 
+/// PATCHED: Per-execution record exported to the Wasm host.
+///
+/// `TestStatistics` keeps its per-test data private, so the runner also records the
+/// outcome and gas of every execution here. Gas is measured before abort handling, so
+/// tests that abort (including those reported with a stack trace) still carry the gas
+/// used up to the abort point.
+#[derive(Clone, Debug)]
+pub struct TestRunRecord {
+    pub module: String,
+    pub function: String,
+    pub passed: bool,
+    pub aborted: bool,
+    pub gas_used: u64,
+    /// Where and how execution stopped, when it did not return normally.
+    pub abort: Option<TestAbortRecord>,
+}
+
+/// PATCHED: Abort details with source locations resolved through the compiled units'
+/// source maps, innermost frame first.
+#[derive(Clone, Debug)]
+pub struct TestAbortRecord {
+    pub status: StatusCode,
+    pub abort_code: Option<u64>,
+    /// Set when the abort was raised by a native function: its module and name.
+    pub native: Option<(String, String)>,
+    /// Module the abort code belongs to, for decoding clever errors.
+    pub location: Option<ModuleId>,
+    pub frames: Vec<TestFrameRecord>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestFrameRecord {
+    pub module: String,
+    pub function: String,
+    pub code_offset: u16,
+    /// Hash of the source file the frame's code came from, if the source map knows it.
+    pub file_hash: Option<FileHash>,
+    /// Byte offset of the frame's code within that file.
+    pub start: Option<u32>,
+}
+
+fn frame_record(
+    test_info: &BTreeMap<ModuleId, NamedCompiledModule>,
+    module_id: &ModuleId,
+    fdef_idx: FunctionDefinitionIndex,
+    code_offset: u16,
+) -> TestFrameRecord {
+    let named = test_info.get(module_id);
+    let function = named
+        .map(|named| {
+            let module = &named.module;
+            let handle = module.function_handle_at(module.function_def_at(fdef_idx).function);
+            module.identifier_at(handle.name).to_string()
+        })
+        .unwrap_or_else(|| format!("<function #{}>", fdef_idx.0));
+    let loc = named.and_then(|named| named.source_map.get_code_location(fdef_idx, code_offset).ok());
+    TestFrameRecord {
+        module: format_module_id(test_info, module_id),
+        function,
+        code_offset,
+        file_hash: loc.map(|loc| loc.file_hash()),
+        start: loc.map(|loc| loc.start()),
+    }
+}
+
+/// The native function called at `code_offset`, if that instruction calls one.
+///
+/// Native aborts are reported at the calling instruction, not inside the native.
+fn native_callee(
+    test_info: &BTreeMap<ModuleId, NamedCompiledModule>,
+    module_id: &ModuleId,
+    fdef_idx: FunctionDefinitionIndex,
+    code_offset: u16,
+) -> Option<(String, String)> {
+    let module = &test_info.get(module_id)?.module;
+    let code = module.function_def_at(fdef_idx).code.as_ref()?;
+    let handle_idx = match code.code.get(code_offset as usize)? {
+        Bytecode::Call(idx) => *idx,
+        Bytecode::CallGeneric(idx) => module.function_instantiation_at(*idx).handle,
+        _ => return None,
+    };
+    let handle = module.function_handle_at(handle_idx);
+    let callee_id = module.module_id_for_handle(module.module_handle_at(handle.module));
+    let name = module.identifier_at(handle.name);
+    let callee = &test_info.get(&callee_id)?.module;
+    let is_native = callee.function_defs().iter().any(|def| {
+        def.is_native() && callee.identifier_at(callee.function_handle_at(def.function).name) == name
+    });
+    is_native.then(|| (format_module_id(test_info, &callee_id), name.to_string()))
+}
+
+fn abort_record(
+    err: &VMError,
+    test_info: &BTreeMap<ModuleId, NamedCompiledModule>,
+) -> TestAbortRecord {
+    let mut frames = vec![];
+    let mut native = None;
+    if let (Location::Module(module_id), Some((fdef_idx, code_offset))) =
+        (err.location(), err.offsets().first())
+    {
+        frames.push(frame_record(test_info, module_id, *fdef_idx, *code_offset));
+        if err.major_status() == StatusCode::ABORTED {
+            native = native_callee(test_info, module_id, *fdef_idx, *code_offset);
+        }
+    }
+    // Caller frames are only kept when `report_stacktrace_on_abort` is set.
+    if let Some(state) = err.exec_state() {
+        for (module_id, fdef_idx, code_offset) in state.stack_trace().iter().rev() {
+            let Some(module_id) = module_id else { continue };
+            let frame = frame_record(test_info, module_id, *fdef_idx, *code_offset);
+            let duplicate = frames.last().is_some_and(|last: &TestFrameRecord| {
+                last.module == frame.module
+                    && last.function == frame.function
+                    && last.code_offset == frame.code_offset
+            });
+            if !duplicate {
+                frames.push(frame);
+            }
+        }
+    }
+    TestAbortRecord {
+        status: err.major_status(),
+        abort_code: err.sub_status().filter(|_| err.major_status() == StatusCode::ABORTED),
+        native,
+        location: match err.location() {
+            Location::Module(module_id) => Some(module_id.clone()),
+            Location::Undefined => None,
+        },
+        frames,
+    }
+}
+
+static TEST_RUN_RECORDS: Mutex<Vec<TestRunRecord>> = Mutex::new(Vec::new());
+
+/// Drain the records collected since the last call.
+pub fn take_test_run_records() -> Vec<TestRunRecord> {
+    std::mem::take(&mut *TEST_RUN_RECORDS.lock().unwrap())
+}
+
+fn record_test_run(
+    output: &TestOutput<impl Write>,
+    function_name: &str,
+    passed: bool,
+    gas_used: u64,
+    abort: &Option<TestAbortRecord>,
+) {
+    TEST_RUN_RECORDS.lock().unwrap().push(TestRunRecord {
+        module: format_module_id(output.test_info, &output.test_plan.module_id),
+        function: function_name.to_string(),
+        passed,
+        aborted: abort.is_some(),
+        gas_used,
+        abort: abort.clone(),
+    });
+}
+
+/// PATCHED: Code offsets executed per (module, function), gathered from the execution
+/// traces of every test when tracing is requested (`trace_location` is set).
+pub type TestCoverage = BTreeMap<(ModuleId, String), BTreeSet<u16>>;
+
+static TEST_COVERAGE: Mutex<TestCoverage> = Mutex::new(BTreeMap::new());
+
+/// Drain the coverage collected since the last call.
+pub fn take_test_coverage() -> TestCoverage {
+    std::mem::take(&mut *TEST_COVERAGE.lock().unwrap())
+}
+
+fn record_coverage(trace: &MoveTrace) {
+    let mut coverage = TEST_COVERAGE.lock().unwrap();
+    // Native frames execute no bytecode; keep a placeholder so closes stay balanced.
+    let mut frames: Vec<Option<(ModuleId, String)>> = Vec::new();
+    for event in &trace.events {
+        match event {
+            TraceEvent::OpenFrame { frame, .. } => frames.push(
+                (!frame.is_native).then(|| (frame.module.clone(), frame.function_name.clone())),
+            ),
+            TraceEvent::CloseFrame { .. } => {
+                frames.pop();
+            }
+            TraceEvent::Instruction { pc, .. } => {
+                if let Some(Some(key)) = frames.last() {
+                    coverage.entry(key.clone()).or_default().insert(*pc);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 pub struct TestRunner {
     num_threads: usize,
     testing_config: SharedTestingConfig,
@@ -302,17 +497,10 @@ impl SharedTestingConfig {
         let extensions = extensions::new_extensions();
         debug_print("DEBUG: MoveVM created");
 
-        // PATCHED: Removed MoveTraceBuilder usage
-        debug_print("DEBUG: MoveTraceBuilder DISABLED for Wasm to avoid panic");
-        // let mut move_tracer = MoveTraceBuilder::new();
-        let tracer = None; // Always None for now
-        /*
-        if self.trace_location.is_some() {
-            Some(&mut move_tracer)
-        } else {
-            None
-        };
-        */
+        // PATCHED: Only build a tracer when tracing was requested (coverage); it is
+        // never created otherwise.
+        let mut move_tracer = self.trace_location.as_ref().map(|_| MoveTraceBuilder::new());
+        let tracer = move_tracer.as_mut();
 
         debug_print("DEBUG: Creating session");
         let mut session =
@@ -344,15 +532,11 @@ impl SharedTestingConfig {
         {
             err.remove_exec_state();
         }
-        // PATCHED: No trace collection
+        // PATCHED: Fold the trace into the coverage map instead of keeping it for saving.
+        if let Some(move_tracer) = move_tracer {
+            record_coverage(&move_tracer.into_trace());
+        }
         let trace = None;
-        /*
-        let trace = if self.trace_location.is_some() {
-            Some(move_tracer.into_trace())
-        } else {
-            None
-        };
-        */
 
         let test_run_info = TestRunInfo::new(
             now.elapsed(),
@@ -507,6 +691,12 @@ impl SharedTestingConfig {
         }
         */
 
+        let gas_used = test_run_info.instructions_executed;
+        let abort = exec_result
+            .as_ref()
+            .err()
+            .map(|err| abort_record(err, global_test_context));
+
         match exec_result {
             Err(err) => {
                 let sub_status = err.sub_status().and_then(|status| {
@@ -519,6 +709,7 @@ impl SharedTestingConfig {
                         if is_last_execution_of_test {
                             output.pass(function_name);
                         }
+                        record_test_run(output, function_name, true, gas_used, &abort);
                         stats.test_success(function_name.to_string(), test_run_info, test_plan)
                     }
                     Some(ExpectedFailure::ExpectedWithError(expected_err))
@@ -527,6 +718,7 @@ impl SharedTestingConfig {
                         if is_last_execution_of_test {
                             output.pass(function_name);
                         }
+                        record_test_run(output, function_name, true, gas_used, &abort);
                         stats.test_success(function_name.to_string(), test_run_info, test_plan)
                     }
                     Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(code))
@@ -537,11 +729,13 @@ impl SharedTestingConfig {
                         if is_last_execution_of_test {
                             output.pass(function_name);
                         }
+                        record_test_run(output, function_name, true, gas_used, &abort);
                         stats.test_success(function_name.to_string(), test_run_info, test_plan)
                     }
                     // incorrect cases
                     Some(ExpectedFailure::ExpectedWithError(expected_err)) => {
                         output.fail(function_name);
+                        record_test_run(output, function_name, false, gas_used, &abort);
                         stats.test_failure(
                             function_name.to_string(),
                             TestFailure::new(
@@ -555,6 +749,7 @@ impl SharedTestingConfig {
                     }
                     Some(ExpectedFailure::ExpectedWithCodeDEPRECATED(expected_code)) => {
                         output.fail(function_name);
+                        record_test_run(output, function_name, false, gas_used, &abort);
                         stats.test_failure(
                             function_name.to_string(),
                             TestFailure::new(
@@ -572,6 +767,7 @@ impl SharedTestingConfig {
                     None if err.major_status() == StatusCode::OUT_OF_GAS => {
                         // Ran out of ticks, report a test timeout and log a test failure
                         output.timeout(function_name);
+                        record_test_run(output, function_name, false, gas_used, &abort);
                         stats.test_failure(
                             function_name.to_string(),
                             TestFailure::new(
@@ -585,6 +781,7 @@ impl SharedTestingConfig {
                     }
                     None => {
                         output.fail(function_name);
+                        record_test_run(output, function_name, false, gas_used, &abort);
                         stats.test_failure(
                             function_name.to_string(),
                             TestFailure::new(
@@ -602,6 +799,7 @@ impl SharedTestingConfig {
                 // Expected the test to fail, but it executed
                 if test_info.expected_failure.is_some() {
                     output.fail(function_name);
+                    record_test_run(output, function_name, false, gas_used, &abort);
                     stats.test_failure(
                         function_name.to_string(),
                         TestFailure::new(FailureReason::no_error(), test_run_info, None, prng_seed),
@@ -612,6 +810,7 @@ impl SharedTestingConfig {
                     if is_last_execution_of_test {
                         output.pass(function_name);
                     }
+                    record_test_run(output, function_name, true, gas_used, &abort);
                     stats.test_success(function_name.to_string(), test_run_info, test_plan)
                 }
             }
@@ -9,13 +9,15 @@ import { ResolvedGraph } from "./resolvedGraph.js";
 
 interface PackageConfig {
   edition: string;
-  flavor: "sui" | "move";
+  flavor: "sui" | "core";
 }
 
 export interface PackageGroupedFormat {
   name: string;
   files: Record<string, string>;
   edition?: string;
+  /** Compiler flavor; "core" compiles as plain Move without Sui checks (defaults to "sui") */
+  flavor?: "sui" | "core";
   addressMapping?: Record<string, string>;
   /** Dependency ID for output (prefer latest published ID) */
   publishedIdForOutput?: string;
@@ -36,6 +38,10 @@ export interface PackageGroupedFormat {
   };
   /** Maps Move.toml deps key (alias) → resolved package name */
   depAliasToPackageName?: Record<string, string>;
+  /** "dev" for packages only reachable through [dev-dependencies] (defaults to "normal") */
+  scope?: "normal" | "dev";
+  /** Build modes the package belongs to (see `mode`); empty or absent means all */
+  modes?: string[];
 }
 
 type ModuleFormat = "Source" | "Bytecode";
@@ -255,7 +261,7 @@ export class CompilationDependencies {
         addressMapping,
         compilerConfig: {
           edition: effectiveEdition,
-          flavor: "sui",
+          flavor: pkg.manifest.flavor === "core" ? "core" : "sui",
         },
         moduleFormat: sourcePaths.length > 0 ? "Source" : "Bytecode",
         edition: effectiveEdition,
@@ -324,7 +330,7 @@ export class CompilationDependencies {
         addressMapping,
         compilerConfig: {
           edition: effectiveEdition,
-          flavor: "sui",
+          flavor: pkg.manifest.flavor === "core" ? "core" : "sui",
         },
         moduleFormat: sourcePaths.length > 0 ? "Source" : "Bytecode",
         edition: effectiveEdition,
@@ -414,6 +420,7 @@ export class CompilationDependencies {
         name: dep.name,
         files: groupedFiles,
         edition: dep.edition,
+        flavor: dep.compilerConfig.flavor,
         addressMapping: dep.addressMapping,
         publishedIdForOutput: dep.publishedIdForOutput,
         source: dep.source,
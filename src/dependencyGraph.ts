@@ -54,6 +54,8 @@ export interface PackageManifest {
   name: string;
   version?: string;
   edition?: string;
+  /** Compiler flavor from `[package] flavor` ("sui" or "core") */
+  flavor?: string;
   publishedAt?: string;
   originalId?: string; // Original published ID (first version)
   latestPublishedId?: string; // Latest published ID from Move.lock (for logging/output)
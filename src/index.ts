@@ -21,6 +21,17 @@ export type BuildProgressEvent =
 /** Callback function for receiving build progress events */
 export type OnProgressCallback = (event: BuildProgressEvent) => void;
 
+/** Log event emitted by the wasm compiler during a build */
+export interface BuildLogEvent {
+  level: "info" | "warn" | "error";
+  /** Compiler phase (setup, parsing, compiling, verifying, tree-shaking, serializing) */
+  phase: string;
+  message: string;
+}
+
+/** Callback function for receiving compiler log events */
+export type OnLogCallback = (event: BuildLogEvent) => void;
+
 export interface ResolvedDependencies {
   /** JSON string of resolved files for the root package */
   files: string;
@@ -47,23 +58,262 @@ export interface BuildInput {
   resolvedDependencies?: ResolvedDependencies;
   /** Use this option to silence warnings. */
   silenceWarnings?: boolean;
+  /** Leave compiler notes (e.g. deprecation guidance) out of `notes`. */
+  silenceNotes?: boolean;
+  /** Warning codes (e.g. "W09001") or messages (e.g. "unused variable") to drop. */
+  suppressWarnings?: string[];
+  /** On compiler errors, also report the diagnostics per file in `errorsByFile`. */
+  perModuleErrors?: boolean;
+  /** Fail the build when it produces warnings (silenced warnings do not count). */
+  warningsAsErrors?: boolean;
   /** Use this option to enable test mode (includes #[test_only] modules). */
   testMode?: boolean;
+  /**
+   * In test mode, allow root code outside tests to use dependencies' #[test_only] functions and types
+   * (rejected by default, since it does not exist on-chain).
+   */
+  keepTestOnlyDeps?: boolean;
+  /** Include [dev-dependencies] without enabling test mode. */
+  devMode?: boolean;
+  /** Compile root tests/ files (defaults to testMode). */
+  includeTests?: boolean;
+  /** Compile root examples/ files (defaults to testMode). */
+  includeExamples?: boolean;
+  /** Protocol version to verify against (defaults to the latest bundled version). */
+  protocolVersion?: number;
+  /** Chain whose protocol config applies during verification and tests. */
+  chain?: "mainnet" | "testnet" | "unknown";
+  /** Run the verifier with validator signing limits (defaults to true outside test mode). */
+  meteredVerification?: boolean;
+  /** Publish size/module-count limit checks (defaults to "error" outside test mode). */
+  packageLimits?: "error" | "warn" | "off";
   /** Use this option to specify lint level (e.g. "all", "none"). */
   lintFlag?: string;
+  /** Fall back to defaults when a Move.toml fails to parse instead of failing the build. */
+  ignoreManifestErrors?: boolean;
+  /**
+   * Assume std = 0x1 / sui = 0x2 when they are not defined (default: true). Turn off for
+   * frameworks published elsewhere; an unbound std/sui/bridge/deepbook address then fails
+   * compilation with a note naming the dependency that should define it.
+   */
+  implicitFrameworkAddresses?: boolean;
+  /** Fail instead of warning when a dependency's [addresses] self-address and its Move.lock original ID differ. */
+  strictPublishedAt?: boolean;
+  /** Warn instead of failing when the root uses a dependency with no published address. */
+  allowUnpublishedDependencies?: boolean;
+  /** Add `interface` (structs, enums and callable functions of each root module) to the output. */
+  emitInterface?: boolean;
+  /** Add `entryPoints`: public/entry functions with parameter kinds and BCS layouts. */
+  emitEntryPoints?: boolean;
+  /** Add `friends`: each module's declared friend modules. */
+  withFriends?: boolean;
+  /** Add `hints`: unused `use`s/constants and unreachable private functions with spans. */
+  emitHints?: boolean;
+  /** Add `constants`: each root module's constant pool with names and decoded values. */
+  emitConstants?: boolean;
+  /** Add `debugBundle`: each root module's source map and compiled source, for trace debuggers. */
+  emitDebugBundle?: boolean;
+  /** Active build mode: files (`fileModes`) and dependencies (`modes`) of other modes are left out. */
+  mode?: string;
+  /** Fail when a used published dependency has no output address (see `treeShakeWarnings`). */
+  strictTreeShaking?: boolean;
+  /**
+   * Lock environment (e.g. "mainnet" or a chain ID) whose publication sets the addresses of
+   * dependencies that have no `addressMapping`: `[published.<env>]` of their Published.toml or
+   * Move.lock, `[env.<env>]` of a legacy Move.lock, or the root Move.lock's `[pinned.<env>.<name>]`.
+   */
+  lockEnvironment?: string;
+  /**
+   * Move.lock (version 3 or 4) to hold the resolved dependencies to: a package missing on
+   * either side, or a different git source, revision, address or published ID, fails the build.
+   */
+  moveLock?: string;
+  /** `[pinned.<env>]` table of a version 4 `moveLock` to check against (needed when it pins several). */
+  moveLockEnvironment?: string;
+  /** Only log `moveLock` mismatches instead of failing. */
+  ignoreLockMismatch?: boolean;
+  /** File key -> modes it belongs to; unlisted files are part of every mode. */
+  fileModes?: Record<string, string[]>;
+  /** Fail the build unless the package digest equals this hex string (hex of `digest`). */
+  expectedDigest?: string;
+  /** Dependency package name -> edition, overriding its Move.toml (e.g. mislabeled legacy sources). */
+  editionOverrides?: Record<string, string>;
+  /** Gas prices (MIST per unit) to estimate the publish cost with; adds `costEstimate`. */
+  estimatePublishCost?: { storagePrice: number; computationPrice: number };
+  /** Binary format version to serialize modules at (default: newest the protocol version accepts; newer is an error). */
+  bytecodeVersion?: number;
+  /** Emit root modules "as if published" at this address (root must be compiled at 0x0). */
+  substituteRootAddress?: string;
+  /**
+   * Keep the root [addresses] declaration order: the builder's "Invalid address" errors
+   * follow it, and buildInfo.declaredAddressOrder reports it. Compiler diagnostics stay
+   * alphabetical.
+   */
+  preserveAddressOrder?: boolean;
+  /** Tests only: dependency packages (by name) whose tests also run. */
+  includePackages?: string[];
+  /** Tests only: transaction sender address (defaults to 0x0). */
+  sender?: string;
+  /** Tests only: epoch seen by tx_context::epoch (defaults to 0). */
+  epoch?: number;
+  /** Tests only: epoch start timestamp in ms (defaults to 0). */
+  epochTimestampMs?: number;
+  /** Tests only: seed for the transaction digest (fresh object IDs) and random tests. */
+  rngSeed?: number;
+  /** Tests only: report which root module instructions the tests executed. */
+  withCoverage?: boolean;
+  /** Tests only: gas budget of each test (defaults to 1,000,000, as the CLI). */
+  gasLimit?: number;
+  /**
+   * Tests only: cost table for execution. "unitTest" (default) gives stable figures;
+   * "protocol" uses the selected protocol version's gas model, matching production.
+   */
+  gasSchedule?: "unitTest" | "protocol";
+  /** Largest accepted input file in bytes (default 8 MiB). */
+  maxFileBytes?: number;
+  /** Largest accepted total size of all input files in bytes (default 256 MiB). */
+  maxTotalBytes?: number;
   /** Use this option to strip metadata from the output (e.g. for mainnet dep matching). */
   stripMetadata?: boolean;
   /** Optional progress callback for build events */
   onProgress?: OnProgressCallback;
+  /** Optional callback receiving compiler log events instead of the console */
+  onLog?: OnLogCallback;
+}
+
+export interface TypeParameterInterface {
+  /** `T0`, `T1`, ... (bytecode keeps no parameter names). */
+  name: string;
+  constraints: string[];
+  isPhantom: boolean;
+}
+
+export interface ModuleInterface {
+  /** Named address bound to the module's address, or its hex literal. */
+  address: string;
+  name: string;
+  structs: {
+    name: string;
+    abilities: string[];
+    typeParameters: TypeParameterInterface[];
+    fields: { name: string; type: string }[];
+  }[];
+  /** Enums, with their variants in declaration order. */
+  enums: {
+    name: string;
+    abilities: string[];
+    typeParameters: TypeParameterInterface[];
+    variants: { name: string; fields: { name: string; type: string }[] }[];
+  }[];
+  /** `public`, `public(package)` ("package") and `entry` functions. */
+  functions: {
+    name: string;
+    visibility: "public" | "package" | "private";
+    isEntry: boolean;
+    typeParameters: TypeParameterInterface[];
+    parameters: string[];
+    return: string[];
+  }[];
+}
+
+export interface EntryPointParameter {
+  /** Type with hex addresses, e.g. `&mut 0x2::coin::Coin<0x2::sui::SUI>`. */
+  type: string;
+  /** How a transaction passes it; `txContext` parameters are omitted by callers. */
+  kind: "pure" | "object" | "txContext" | "typeParameter" | "other";
+  /** Set for reference parameters. */
+  reference?: "immutable" | "mutable";
+  /** For `pure`: annotated Move type layout (JSON) to BCS-encode the value with. */
+  layout?: unknown;
+}
+
+export interface EntryPoint {
+  /** `0x...::module::function`. */
+  fullName: string;
+  visibility: "public" | "package" | "private";
+  isEntry: boolean;
+  /** Ability constraints per type parameter. */
+  typeParameters: string[][];
+  parameters: EntryPointParameter[];
+  return: string[];
+}
+
+/** Compiler diagnostics behind a build or test result. */
+export interface DiagnosticCounts {
+  /** Warnings shown in the output (the warnings text, or the failure report). */
+  warnings: number;
+  /** Errors in the failure report; 0 on success. */
+  errors: number;
+  /** Warnings hidden by silenceWarnings or suppressWarnings. */
+  suppressedWarnings: number;
 }
 
 export interface BuildSuccess {
   /** Base64-encoded bytecode modules. */
   modules: string[];
+  /** Module names, index-aligned with `modules`. */
+  moduleNames?: string[];
+  /** Serialized module sizes in bytes, index-aligned with `modules`. */
+  moduleSizes?: number[];
+  /** Binary format version each module was serialized at, index-aligned with `modules`. */
+  moduleVersions?: number[];
+  /** Number of modules. */
+  moduleCount?: number;
+  /** Raw bytecode size of all modules in bytes (sum of `moduleSizes`). */
+  totalBytecodeBytes?: number;
+  /** With `emitInterface`: each module's public API, index-aligned with `modules`. */
+  interface?: ModuleInterface[];
+  /** With `emitEntryPoints`: callable root functions for transaction building. */
+  entryPoints?: EntryPoint[];
+  /** With `withFriends`: module name -> friend module IDs (`0x...::module`). */
+  friends?: Record<string, string[]>;
+  /** Used published dependencies with no output address; they are missing from `dependencies`. */
+  treeShakeWarnings?: string[];
+  /** With `emitHints`: clean-up hints, each with the file key and byte range to remove. */
+  hints?: {
+    kind: "unusedUse" | "unusedConstant" | "unreachableFunction";
+    file: string;
+    start: number;
+    end: number;
+    text: string;
+    message: string;
+  }[];
+  /**
+   * With `emitConstants`: module name -> constants in pool order. Integers are decimal
+   * strings, `vector<u8>` is `{ hex, utf8 }`; `bcs` holds the raw bytes as hex when a value
+   * cannot be decoded. `name` comes from the source map, or is `const_<index>` for
+   * compiler-generated entries.
+   */
+  constants?: Record<
+    string,
+    {
+      index: number;
+      name: string;
+      type: string;
+      value: unknown;
+      bcs?: string;
+      valueBytesB64: string;
+    }[]
+  >;
+  /**
+   * With `emitDebugBundle`: per root module, the base64 `.mvsm` source map and the source
+   * text it indexes, under the same file key diagnostics use.
+   */
+  debugBundle?: { module: string; sourceMap: string; file: string; source: string }[];
   /** Hex-encoded dependency IDs. */
   dependencies: string[];
   /** Blake2b-256 package digest as byte array (matches Sui CLI JSON). */
   digest: number[];
+  /** Compilation (original) address -> output (published) address, for dependencies and a published root. */
+  addressMapping?: Record<string, string>;
+  /** With `estimatePublishCost`: estimated publish cost in MIST. */
+  costEstimate?: {
+    storageBytes: number;
+    storageCost: number;
+    computationUnits: number;
+    computationCost: number;
+    totalCost: number;
+  };
   /** Move.lock V4 content (TOML string) */
   moveLock: string;
   /** Build environment used */
@@ -72,10 +322,43 @@ export interface BuildSuccess {
   publishedToml?: string;
   /** Compiler warnings (if any) */
   warnings?: string;
+  /** Declared dependency IDs not referenced by the root package (informational; they stay in `dependencies`) */
+  prunedDependencies?: string[];
+  /** Root `[dependencies]` package names the root package never reaches */
+  unusedDependencies?: string[];
+  /** In test mode: dependency package names referenced only from test code */
+  testOnlyDependencies?: string[];
+  /** Adjustments made on the caller's behalf (e.g. an implicit std/sui address) */
+  notes?: string[];
+  /** Build metadata for publication and source-verification tooling */
+  buildInfo?: BuildInfo;
+}
+
+export interface BuildInfo {
+  packageName: string;
+  edition: string;
+  flavor: string;
+  /** Named addresses the root package was compiled with (name -> address) */
+  addressAliasInstantiation: Record<string, string>;
+  /** Root [addresses] names in declaration order (with preserveAddressOrder) */
+  declaredAddressOrder?: string[];
+  buildFlags: {
+    testMode: boolean;
+    devMode: boolean;
+    silenceWarnings: boolean;
+  };
+  suiVersion: string;
+  suiMoveVersion: string;
+  diagnostics?: DiagnosticCounts;
 }
 
 export interface BuildFailure {
   error: string;
+  /** With `perModuleErrors` and compiler errors: file key -> that file's diagnostics. */
+  errorsByFile?: Record<string, string>;
+  /** Dependencies with parse errors: the edition each was parsed with and how to override it. */
+  editionHints?: string[];
+  diagnostics?: DiagnosticCounts;
 }
 
 import {
@@ -154,6 +437,16 @@ function ensureCompileResult(result: unknown): {
   throw new Error("Unexpected compile result shape from wasm");
 }
 
+/** Diagnostic counts of a wasm compile or test result, if the build exposes them. */
+function diagnosticCounts(raw: any): DiagnosticCounts | undefined {
+  if (typeof raw?.warningCount !== "number") return undefined;
+  return {
+    warnings: raw.warningCount,
+    errors: raw.errorCount,
+    suppressedWarnings: raw.suppressedWarningCount,
+  };
+}
+
 function parseCompileResult(
   output: string,
   nameMap?: Map<string, string>,
@@ -176,9 +469,28 @@ function parseCompileResult(
   try {
     const parsed = JSON.parse(output) as {
       modules?: string[];
+      moduleNames?: string[];
+      moduleSizes?: number[];
+      moduleVersions?: number[];
+      moduleCount?: number;
+      totalBytecodeBytes?: number;
+      interface?: ModuleInterface[];
+      entryPoints?: EntryPoint[];
+      friends?: Record<string, string[]>;
+      hints?: BuildSuccess["hints"];
+      constants?: BuildSuccess["constants"];
+      debugBundle?: BuildSuccess["debugBundle"];
+      treeShakeWarnings?: string[];
       dependencies?: string[];
       digest?: number[] | string;
+      addressMapping?: Record<string, string>;
+      costEstimate?: BuildSuccess["costEstimate"];
       warnings?: string;
+      prunedDependencies?: string[];
+      unusedDependencies?: string[];
+      testOnlyDependencies?: string[];
+      notes?: string[];
+      buildInfo?: BuildInfo;
     };
     if (!parsed.modules || !parsed.dependencies || !parsed.digest) {
       throw new Error("missing fields in compiler output");
@@ -212,12 +524,31 @@ function parseCompileResult(
 
     return {
       modules: parsed.modules,
+      moduleNames: parsed.moduleNames,
+      moduleSizes: parsed.moduleSizes,
+      moduleVersions: parsed.moduleVersions,
+      moduleCount: parsed.moduleCount,
+      totalBytecodeBytes: parsed.totalBytecodeBytes,
+      interface: parsed.interface,
+      entryPoints: parsed.entryPoints,
+      friends: parsed.friends,
+      hints: parsed.hints,
+      constants: parsed.constants,
+      debugBundle: parsed.debugBundle,
+      treeShakeWarnings: parsed.treeShakeWarnings,
       // Filter out implicit system dependencies to match CLI behavior
       dependencies,
       digest: digestBytes,
+      addressMapping: parsed.addressMapping,
+      costEstimate: parsed.costEstimate,
       moveLock: moveLock || "",
       environment: environment || "mainnet",
       warnings: parsed.warnings,
+      prunedDependencies: parsed.prunedDependencies,
+      unusedDependencies: parsed.unusedDependencies,
+      testOnlyDependencies: parsed.testOnlyDependencies,
+      notes: parsed.notes,
+      buildInfo: parsed.buildInfo,
     };
   } catch (error) {
     return asFailure(error);
@@ -523,23 +854,69 @@ export async function buildMovePackage(
       resolved.dependencies, // Pass original array for compilation
       JSON.stringify({
         silenceWarnings: input.silenceWarnings,
+        silenceNotes: input.silenceNotes,
+        perModuleErrors: input.perModuleErrors,
+        suppressWarnings: input.suppressWarnings,
+        warningsAsErrors: input.warningsAsErrors,
         testMode: input.testMode,
+        keepTestOnlyDeps: input.keepTestOnlyDeps,
+        maxFileBytes: input.maxFileBytes,
+        maxTotalBytes: input.maxTotalBytes,
+        devMode: input.devMode,
+        includeTests: input.includeTests,
+        includeExamples: input.includeExamples,
+        meteredVerification: input.meteredVerification,
+        packageLimits: input.packageLimits,
+        protocolVersion: input.protocolVersion,
+        chain: input.chain,
         lintFlag: input.lintFlag,
+        ignoreManifestErrors: input.ignoreManifestErrors,
+        implicitFrameworkAddresses: input.implicitFrameworkAddresses,
+        strictPublishedAt: input.strictPublishedAt,
+        allowUnpublishedDependencies: input.allowUnpublishedDependencies,
+        emitInterface: input.emitInterface,
+        emitEntryPoints: input.emitEntryPoints,
+        withFriends: input.withFriends,
+        emitHints: input.emitHints,
+        emitConstants: input.emitConstants,
+        emitDebugBundle: input.emitDebugBundle,
+        mode: input.mode,
+        strictTreeShaking: input.strictTreeShaking,
+        lockEnvironment: input.lockEnvironment,
+        moveLock: input.moveLock,
+        moveLockEnvironment: input.moveLockEnvironment,
+        ignoreLockMismatch: input.ignoreLockMismatch,
+        fileModes: input.fileModes,
+        expectedDigest: input.expectedDigest,
+        editionOverrides: input.editionOverrides,
+        estimatePublishCost: input.estimatePublishCost,
+        bytecodeVersion: input.bytecodeVersion,
+        substituteRootAddress: input.substituteRootAddress,
+        preserveAddressOrder: input.preserveAddressOrder,
         stripMetadata: input.stripMetadata,
         ansiColor: input.ansiColor,
       }),
-      JSON.stringify(dependencyGraph) // 4th param: graph for lockfile generation
+      JSON.stringify(dependencyGraph), // 4th param: graph for lockfile generation
+      input.onLog
     );
 
     const result = ensureCompileResult(raw);
     const ok = result.success();
     const output = result.output();
+    const diagnostics = diagnosticCounts(raw);
 
     // Emit compile_complete event
     input.onProgress?.({ type: "compile_complete" });
 
     if (!ok) {
-      return asFailure(output);
+      const errorsByFile =
+        typeof raw.errorsByFile === "string"
+          ? (JSON.parse(raw.errorsByFile) as Record<string, string>)
+          : undefined;
+      const editionHints: string[] | undefined = raw.editionHints?.length
+        ? raw.editionHints
+        : undefined;
+      return { ...asFailure(output), errorsByFile, editionHints, diagnostics };
     }
 
     // Emit lockfile_generate event
@@ -618,6 +995,7 @@ export async function buildMovePackage(
           buildResult.publishedToml = migratedPublishedToml;
         }
       }
+      buildResult.diagnostics = diagnostics;
     }
 
     return buildResult;
@@ -626,11 +1004,88 @@ export async function buildMovePackage(
   }
 }
 
+export interface TestGasEntry {
+  /** Fully qualified test name (`address::module::function`). */
+  name: string;
+  passed: boolean;
+  /** The test aborted; `gasUsed` covers execution up to the abort point. */
+  aborted: boolean;
+  gasUsed: number;
+}
+
+export interface TestGasReport {
+  /** Gas used across all test executions. */
+  totalGasUsed: number;
+  /** Per-test breakdown, in execution order. */
+  tests: TestGasEntry[];
+}
+
+export interface TestFrame {
+  module: string;
+  function: string;
+  /** Input file key the frame's code came from (if known). */
+  file: string | null;
+  line: number | null;
+}
+
+export interface TestFailure {
+  /** Fully qualified test name. */
+  test: string;
+  /** Module and function where execution stopped. */
+  module: string;
+  function: string;
+  abortCode: number | null;
+  /** VM status code (e.g. "ABORTED"). */
+  status: string;
+  /** Raised by a native function, a Move abort/assertion, or another VM error. */
+  kind: "native" | "abort" | "error";
+  /** The aborting native (address::module::function) when kind is "native". */
+  nativeFunction?: string;
+  /** Decoded #[error] constant when the abort code is a clever error. */
+  cleverError?: { constant?: string; line: number; value?: string };
+  file: string | null;
+  line: number | null;
+  /** Abort stack trace, innermost frame first. */
+  frames: TestFrame[];
+}
+
+export interface FunctionCoverage {
+  /** Number of bytecode instructions in the function. */
+  instructions: number;
+  /** Code offsets executed by at least one test, ascending. */
+  executed: number[];
+}
+
+export interface TestCounts {
+  /** Tests found in the compiled packages, before includePackages filtering. */
+  discovered: number;
+  run: number;
+  passed: number;
+  /** Failed tests other than those out of gas. */
+  failed: number;
+  /** Tests that exhausted their gas budget; usually means gasLimit needs raising. */
+  outOfGas: number;
+  /** Tests skipped because their package is not in includePackages. */
+  filtered: number;
+}
+
 export interface TestSuccess {
   /** Whether all tests passed. */
   passed: boolean;
   /** Output from the test runner (stdout). */
   output: string;
+  /** Gas used per test, measured with the cost table selected by `gasSchedule`. */
+  gas?: TestGasReport;
+  /** Adjustments made on the caller's behalf (e.g. an implicit std/sui address). */
+  notes?: string[];
+  /** Failed tests that aborted, mapped back to source files. */
+  failures?: TestFailure[];
+  /** With `withCoverage`: coverage keyed by root module name, then function name. */
+  coverage?: Record<string, Record<string, FunctionCoverage>>;
+  /** Discovered, run, passed, failed, timed-out and filtered test counts. */
+  counts?: TestCounts;
+  /** Compiler diagnostics of the test build; warnings count as suppressed once it compiled. */
+  diagnostics?: DiagnosticCounts;
 }
 
 /** Compile and run tests for a Move package in memory. */
@@ -654,21 +1109,79 @@ export async function testMovePackage(
             resolved.dependencies,
             true
           )
-        : (mod as any).test(resolved.files, resolved.dependencies); // Fallback if test_with_color missing
+        : (mod as any).test(
+            resolved.files,
+            resolved.dependencies,
+            JSON.stringify({
+              protocolVersion: input.protocolVersion,
+              chain: input.chain,
+              ignoreManifestErrors: input.ignoreManifestErrors,
+              implicitFrameworkAddresses: input.implicitFrameworkAddresses,
+              includePackages: input.includePackages,
+              sender: input.sender,
+              epoch: input.epoch,
+              epochTimestampMs: input.epochTimestampMs,
+              rngSeed: input.rngSeed,
+              withCoverage: input.withCoverage,
+              gasLimit: input.gasLimit,
+              gasSchedule: input.gasSchedule,
+              maxFileBytes: input.maxFileBytes,
+              maxTotalBytes: input.maxTotalBytes,
+              editionOverrides: input.editionOverrides,
+              mode: input.mode,
+              fileModes: input.fileModes,
+              lockEnvironment: input.lockEnvironment,
+              ansiColor: input.ansiColor,
+            })
+          ); // Fallback if test_with_color missing
+
+    const counts: TestCounts | undefined =
+      typeof raw.numRun === "number"
+        ? {
+            discovered: raw.numDiscovered,
+            run: raw.numRun,
+            passed: raw.numPassed,
+            failed: raw.numFailed,
+            outOfGas: raw.numOutOfGas,
+            filtered: raw.numFiltered,
+          }
+        : undefined;
+    const diagnostics = diagnosticCounts(raw);
 
     // Check if raw result matches expected shape
     if (typeof raw.passed === "boolean" && typeof raw.output === "string") {
       return {
         passed: raw.passed,
         output: raw.output,
+        gas: raw.gas ?? undefined,
+        notes: raw.notes ?? undefined,
+        failures: raw.failures ?? undefined,
+        coverage: raw.coverage ?? undefined,
+        counts,
+        diagnostics,
       };
     }
 
     // In case wasm-bindgen getters are needed (wrapper objects)
     const passed = typeof raw.passed === "function" ? raw.passed() : raw.passed;
     const output = typeof raw.output === "function" ? raw.output() : raw.output;
+    const gas = typeof raw.gas === "function" ? raw.gas() : raw.gas;
+    const notes = typeof raw.notes === "function" ? raw.notes() : raw.notes;
+    const failures =
+      typeof raw.failures === "function" ? raw.failures() : raw.failures;
+    const coverage =
+      typeof raw.coverage === "function" ? raw.coverage() : raw.coverage;
 
-    return { passed, output };
+    return {
+      passed,
+      output,
+      gas: gas ?? undefined,
+      notes: notes ?? undefined,
+      failures: failures ?? undefined,
+      coverage: coverage ?? undefined,
+      counts,
+      diagnostics,
+    };
   } catch (error) {
     return asFailure(error);
   }
@@ -690,6 +1203,24 @@ export async function getSuiVersion(options?: {
   return mod.sui_version();
 }
 
+/** Toolchain and protocol metadata baked into the wasm. */
+export interface BuildInfo {
+  sui_move_version: string;
+  sui_version: string;
+  protocol_version: { default: number; min: number; max: number };
+  /** Edition strings accepted in Move.toml. */
+  editions: string[];
+  bytecode_version: number;
+}
+
+/** Versions, protocol range and supported editions in one call. */
+export async function getBuildInfo(options?: {
+  wasm?: string | URL;
+}): Promise<BuildInfo> {
+  const mod = await loadWasm(options?.wasm);
+  return JSON.parse(mod.build_info()) as BuildInfo;
+}
+
 /** Get the raw wasm bindings (low-level interface). */
 export async function getWasmBindings(options?: {
   wasm?: string | URL;
@@ -210,6 +210,7 @@ export function generateMoveLockV4FromJson(
             subdir: depInfo.subdir || "",
             rev: depInfo.rev || "",
             use_environment: environment,
+            modes: depInfo.modes,
           };
         } else if (depInfo?.local) {
           // Local dependency: { local = "..." }
@@ -217,7 +218,28 @@ export function generateMoveLockV4FromJson(
             name,
             local: depInfo.local,
             use_environment: environment,
+            modes: depInfo.modes,
           };
+        } else if (depInfo?.["on-chain"]) {
+          // On-chain dependency: { on-chain = true }
+          return {
+            name,
+            "on-chain": true,
+            use_environment: environment,
+            modes: depInfo.modes,
+          };
+        } else if (depInfo?.r && typeof depInfo.r === "object") {
+          // External dependency: { r.<resolver> = <data> }
+          const [resolver, external] = Object.entries(depInfo.r)[0] ?? [];
+          if (resolver) {
+            return {
+              name,
+              resolver,
+              external,
+              use_environment: environment,
+              modes: depInfo.modes,
+            };
+          }
         }
         // ORIGINAL: package_impl.rs:287-308 - compute_digest() serializes CombinedDependency to TOML
         // ORIGINAL: manifest.rs:155-170 - override_system_dep() returns { system: name, is_override: true }
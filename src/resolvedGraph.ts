@@ -58,7 +58,7 @@ export class ResolvedGraph {
 
       // Merge this package's addresses into the unified table
       for (const [name, addr] of Object.entries(pkg.manifest.addresses)) {
-        const normalized = this.normalizeAddress(addr);
+        const normalized = this.normalizeManifestAddress(addr);
 
         // Check for conflicts
         if (this.unifiedAddressTable.has(name)) {
@@ -104,6 +104,18 @@ export class ResolvedGraph {
     return "0x" + clean.padStart(64, "0");
   }
 
+  /**
+   * Normalize a Move.toml `[addresses]` value: unprefixed all-digit values are decimal,
+   * as the compiler reads them. Object IDs (`published-at`, lockfile IDs) stay hex.
+   */
+  private normalizeManifestAddress(addr: string): string {
+    const clean = addr?.trim();
+    if (clean && /^[0-9]+$/.test(clean)) {
+      return this.normalizeAddress("0x" + BigInt(clean).toString(16));
+    }
+    return this.normalizeAddress(addr);
+  }
+
   /**
    * Get the unified address table
    */
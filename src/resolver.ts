@@ -131,7 +131,7 @@ export class Resolver {
     // Sui CLI behavior: If root package address is 0x0 and original-published-id exists,
     // replace the 0x0 address with original-published-id in the addresses table
     const rootAddr = rootPackage.manifest.addresses[rootPackageName];
-    const normalizedRootAddr = this.normalizeAddress(rootAddr || "");
+    const normalizedRootAddr = this.normalizeManifestAddress(rootAddr || "");
     if (normalizedRootAddr === ZERO_ADDRESS) {
       if (rootPackage.manifest.originalId) {
         rootPackage.manifest.addresses[rootPackageName] = this.normalizeAddress(
@@ -206,7 +206,7 @@ export class Resolver {
           (key) => key.toLowerCase() === pkg.manifest.name.toLowerCase()
         );
         if (selfAddressKey && pkg.manifest.addresses[selfAddressKey]) {
-          const selfAddr = this.normalizeAddress(
+          const selfAddr = this.normalizeManifestAddress(
             pkg.manifest.addresses[selfAddressKey]
           );
           unifiedTable[pkg.manifest.name] = selfAddr;
@@ -234,7 +234,7 @@ export class Resolver {
       );
 
       if (selfAddressKey && pkg.manifest.addresses[selfAddressKey]) {
-        const selfAddr = this.normalizeAddress(
+        const selfAddr = this.normalizeManifestAddress(
           pkg.manifest.addresses[selfAddressKey]
         );
         unifiedTable[pkg.manifest.name] = selfAddr;
@@ -334,6 +334,7 @@ export class Resolver {
       name: parsed.package?.name || name,
       version: parsed.package?.version || "0.0.0",
       edition: parsed.package?.edition,
+      flavor: parsed.package?.flavor,
       publishedAt:
         publishedAtFromPublishedToml || publishedAtResult.publishedAt,
       originalId: originalIdFromPublishedToml || publishedAtResult.originalId,
@@ -358,7 +359,7 @@ export class Resolver {
     );
 
     if (selfAddressKey && manifest.addresses[selfAddressKey]) {
-      const selfAddr = this.normalizeAddress(
+      const selfAddr = this.normalizeManifestAddress(
         manifest.addresses[selfAddressKey]
       );
       // Treat explicit address as originalId if not 0x0
@@ -383,7 +384,7 @@ export class Resolver {
       manifest.addresses[manifest.name] ||
       (selfAddressKey ? manifest.addresses[selfAddressKey] : undefined);
     const normalizedCurrent = currentAddr
-      ? this.normalizeAddress(currentAddr)
+      ? this.normalizeManifestAddress(currentAddr)
       : undefined;
 
     if (normalizedPublished) {
@@ -1605,6 +1606,18 @@ export class Resolver {
     return "0x" + clean.toLowerCase().padStart(64, "0");
   }
 
+  /**
+   * Normalize a Move.toml `[addresses]` value: unprefixed all-digit values are decimal,
+   * as the compiler reads them. Object IDs (`published-at`, lockfile IDs) stay hex.
+   */
+  private normalizeManifestAddress(addr: string): string {
+    const clean = addr?.trim();
+    if (clean && /^[0-9]+$/.test(clean)) {
+      return this.normalizeAddress("0x" + BigInt(clean).toString(16));
+    }
+    return this.normalizeAddress(addr);
+  }
+
   /**
    * Check if git URL is Sui repository
    */
@@ -11,4 +11,6 @@ export function compile(
 export function sui_move_version(): string;
 export function sui_version(): string;
 export function compute_manifest_digest(depsJson: string): string;
+export function compute_manifest_digest_checked(depsJson: string): string;
+export function compute_deps_digest(resolvedGraphJson: string): string;
 export default function init(wasm?: string | URL): Promise<void>;
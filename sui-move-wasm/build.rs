@@ -42,24 +42,53 @@ fn workspace_package_version(toml_contents: &str) -> Option<String> {
     None
 }
 
+/// First of `package_names` found in any of `locks`.
+fn locked_version(locks: &[String], package_names: &[&str]) -> Option<String> {
+    package_names
+        .iter()
+        .find_map(|name| locks.iter().find_map(|lock| package_version_from_lock(lock, name)))
+}
+
 fn main() {
     let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
     let repo_root = manifest_dir.join("../..");
-    let lock_path = repo_root.join("Cargo.lock");
-    if let Ok(lock_contents) = fs::read_to_string(&lock_path) {
-        if let Some(version) = package_version_from_lock(&lock_contents, "sui-move") {
-            println!("cargo:rustc-env=SUI_MOVE_VERSION={}", version);
-        }
-        if let Some(version) = package_version_from_lock(&lock_contents, "sui") {
-            println!("cargo:rustc-env=SUI_VERSION={}", version);
+    println!("cargo:rerun-if-env-changed=SUI_VERSION");
+    println!("cargo:rerun-if-env-changed=SUI_MOVE_VERSION");
+    println!("cargo:rerun-if-env-changed=GIT_REVISION");
+
+    // Inside the Sui workspace the lock is at its root; built standalone, next to this crate.
+    let lock_paths = [repo_root.join("Cargo.lock"), manifest_dir.join("Cargo.lock")];
+    let mut locks = Vec::new();
+    for lock_path in &lock_paths {
+        println!("cargo:rerun-if-changed={}", lock_path.display());
+        if let Ok(lock_contents) = fs::read_to_string(lock_path) {
+            locks.push(lock_contents);
         }
-    } else {
+    }
+
+    // `sui-move`/`sui` are only in the lock of a full Sui workspace. Built standalone, the
+    // lock only has the compiler and sui-types crates, whose versions are placeholders, so
+    // the versions must come from the environment (scripts/build-wasm.mjs sets both).
+    let workspace_version = || {
         let toml_path = repo_root.join("Cargo.toml");
-        if let Ok(toml_contents) = fs::read_to_string(&toml_path) {
-            if let Some(version) = workspace_package_version(&toml_contents) {
-                println!("cargo:rustc-env=SUI_MOVE_VERSION={}", version);
-                println!("cargo:rustc-env=SUI_VERSION={}", version);
-            }
+        println!("cargo:rerun-if-changed={}", toml_path.display());
+        fs::read_to_string(&toml_path)
+            .ok()
+            .and_then(|contents| workspace_package_version(&contents))
+    };
+    for (env_name, locked_env_name, crate_name) in
+        [("SUI_MOVE_VERSION", "SUI_MOVE_LOCKED_VERSION", "sui-move"), ("SUI_VERSION", "SUI_LOCKED_VERSION", "sui")]
+    {
+        if std::env::var(env_name).is_ok_and(|version| !version.is_empty()) {
+            continue;
         }
+        let Some(version) = locked_version(&locks, &[crate_name]).or_else(workspace_version) else {
+            panic!(
+                "{} is not set and no Sui workspace Cargo.lock pins `{}`; build through \
+                 scripts/build-wasm.mjs or set {} to the bundled Sui version",
+                env_name, crate_name, env_name
+            );
+        };
+        println!("cargo:rustc-env={}={}", locked_env_name, version);
     }
 }
@@ -0,0 +1,19 @@
+// Compile a package natively through `sui_move_wasm::api`, for cross-checking the wasm build.
+//
+// Reads a `CompileRequest` as JSON (`{ files, dependencies, options }`) on stdin and prints
+// the `CompileResponse` as JSON, in the shape the wasm `compile` output takes.
+
+use std::io::Read;
+use sui_move_wasm::api::{compile, CompileRequest};
+
+fn main() -> Result<(), String> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    let request: CompileRequest =
+        serde_json::from_str(&input).map_err(|e| format!("Failed to parse request: {}", e))?;
+    let response = compile(&request);
+    println!("{}", serde_json::to_string(&response).map_err(|e| e.to_string())?);
+    Ok(())
+}
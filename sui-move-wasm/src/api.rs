@@ -0,0 +1,202 @@
+// Plain Rust entry points over the same pipeline as the wasm exports, for native callers
+// (a server cross-checking browser builds, a Node native addon, CLI tooling).
+//
+// The wasm exports take JSON strings because that is what crosses the JS boundary cheaply;
+// here the request and response are typed structs. Dependencies go through the same
+// deserialization (`normalizedModules` stubs, dedupe) as the wasm `compile`, and both end in
+// `compile_package`/`test_impl`, so outputs (modules, digest, dependencies) are
+// byte-identical between `wasm32-unknown-unknown` and a native target. Build with
+// `--no-default-features` to leave out the `wasm` feature and its wasm-bindgen exports;
+// build events that would go to the JS console are written to stderr instead.
+//
+// ```ignore
+// let options: CompileOptions = serde_json::from_value(serde_json::json!({ "emitInterface": true }))?;
+// let request = CompileRequest { files, dependencies: vec![], options };
+// let response = sui_move_wasm::api::compile(&request);
+// println!("{}", hex::encode(response.output.unwrap().digest()));
+// ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub use crate::{CompilationOutput, CompileOptions, DependencyScope};
+#[cfg(feature = "testing")]
+pub use crate::{FunctionCoverage, TestCounts, TestCoverageReport, TestFailureInfo, TestGasReport, TestOptions};
+
+/// A dependency package, as an entry of the wasm `compile`'s dependencies array.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Dependency {
+    /// Package name, as dependents' manifests refer to it.
+    pub name: String,
+    /// Package files by path (`dependencies/<name>/Move.toml`, `.../sources/...`).
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    /// "sui" or "core"; overrides the dependency manifest's flavor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<String>,
+    /// Named address -> address the package is compiled with.
+    #[serde(default, rename = "addressMapping", skip_serializing_if = "Option::is_none")]
+    pub address_mapping: Option<BTreeMap<String, String>>,
+    /// Address the package is linked at in the output (its latest published version).
+    #[serde(default, rename = "publishedIdForOutput", skip_serializing_if = "Option::is_none")]
+    pub published_id_for_output: Option<String>,
+    #[serde(default)]
+    pub scope: DependencyScope,
+    /// Move.toml dependency alias -> resolved package name.
+    #[serde(default, rename = "depAliasToPackageName")]
+    pub dep_alias_to_package_name: BTreeMap<String, String>,
+    /// Build modes the package belongs to; empty means every mode.
+    #[serde(default)]
+    pub modes: Vec<String>,
+    /// On-chain modules as returned by `sui_getNormalizedMoveModulesByPackage`, in place of
+    /// sources.
+    #[serde(default, rename = "normalizedModules", skip_serializing_if = "BTreeMap::is_empty")]
+    pub normalized_modules: BTreeMap<String, serde_json::Value>,
+    /// Where the files were fetched from, checked against `moveLock` pins.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<DependencySource>,
+}
+
+/// Origin of a dependency's files.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct DependencySource {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
+}
+
+/// Inputs of `compile`, shaped like the arguments of the wasm export.
+#[derive(Deserialize, Clone, Default)]
+pub struct CompileRequest {
+    /// Root package files by path (`Move.toml`, `sources/...`).
+    pub files: BTreeMap<String, String>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub options: CompileOptions,
+}
+
+/// Outcome of `compile`: the output on success, the rendered failure report otherwise.
+#[derive(Serialize)]
+pub struct CompileResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<CompilationOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// With `perModuleErrors` and compiler errors: file key -> that file's diagnostics.
+    #[serde(rename = "errorsByFile", skip_serializing_if = "Option::is_none")]
+    pub errors_by_file: Option<BTreeMap<String, String>>,
+    /// Edition notes for dependencies with parse errors, also appended to `error`.
+    #[serde(rename = "editionHints", skip_serializing_if = "Vec::is_empty")]
+    pub edition_hints: Vec<String>,
+    /// Warnings shown in the output (the `warnings` text, or the failure report).
+    #[serde(rename = "warningCount")]
+    pub warning_count: u32,
+    /// Errors in the failure report; 0 on success.
+    #[serde(rename = "errorCount")]
+    pub error_count: u32,
+    /// Warnings hidden by `silenceWarnings` or `suppressWarnings`.
+    #[serde(rename = "suppressedWarningCount")]
+    pub suppressed_warning_count: u32,
+}
+
+/// Inputs of `test`.
+#[cfg(feature = "testing")]
+#[derive(Deserialize, Clone, Default)]
+pub struct TestRequest {
+    pub files: BTreeMap<String, String>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub options: TestOptions,
+}
+
+/// Outcome of `test`, with the fields of the wasm `MoveTestResult`.
+#[cfg(feature = "testing")]
+#[derive(Serialize)]
+pub struct TestResponse {
+    pub passed: bool,
+    /// The test report as printed by the runner.
+    pub output: String,
+    pub notes: Vec<String>,
+    pub gas: TestGasReport,
+    pub failures: Vec<TestFailureInfo>,
+    /// With `withCoverage`: root module coverage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<TestCoverageReport>,
+    pub counts: TestCounts,
+}
+
+/// Turn typed dependencies into package groups through the wire format the wasm exports
+/// read, so stubs, validation and dedupe are the same.
+fn dependency_groups(dependencies: &[Dependency]) -> Result<Vec<crate::PackageGroup>, String> {
+    let groups = serde_json::to_value(dependencies)
+        .and_then(serde_json::from_value)
+        .map_err(|e| format!("Failed to parse dependencies: {}", e))?;
+    crate::dedupe_dependency_groups(groups)
+}
+
+/// Compile the root package of `request`.
+pub fn compile(request: &CompileRequest) -> CompileResponse {
+    let logger = crate::BuildLogger::new(None);
+    let graph_json = request.options.dependency_graph.clone();
+    let result = dependency_groups(&request.dependencies).map_err(crate::BuildFailure::from).and_then(|dep_packages| {
+        crate::compile_package(&request.files, dep_packages, &request.options, graph_json.as_deref(), &logger)
+    });
+    match result {
+        Ok(output) => CompileResponse {
+            success: true,
+            warning_count: output.diagnostics.warnings,
+            error_count: output.diagnostics.errors,
+            suppressed_warning_count: output.diagnostics.suppressed_warnings,
+            output: Some(output),
+            error: None,
+            errors_by_file: None,
+            edition_hints: Vec::new(),
+        },
+        Err(failure) => CompileResponse {
+            success: false,
+            output: None,
+            error: Some(failure.message),
+            errors_by_file: failure.errors_by_file,
+            edition_hints: failure.edition_hints,
+            warning_count: failure.counts.warnings,
+            error_count: failure.counts.errors,
+            suppressed_warning_count: failure.counts.suppressed_warnings,
+        },
+    }
+}
+
+/// Run the root package's Move unit tests.
+#[cfg(feature = "testing")]
+pub fn test(request: &TestRequest) -> TestResponse {
+    let result = match dependency_groups(&request.dependencies) {
+        Ok(dep_packages) => crate::test_impl(&request.files, dep_packages, &request.options),
+        Err(e) => crate::MoveTestResult::failed(e),
+    };
+    TestResponse {
+        passed: result.passed,
+        output: result.output,
+        notes: result.notes,
+        gas: result.gas,
+        failures: result.failures,
+        coverage: result.coverage,
+        counts: result.counts,
+    }
+}
+
+/// Manifest digest for Move.lock, as `compute_manifest_digest_checked` in JS.
+pub fn manifest_digest(deps_json: &str) -> Result<String, String> {
+    crate::manifest_digest(deps_json)
+}
+
+/// Dependency graph digest, as `compute_deps_digest_checked` in JS.
+pub fn deps_digest(resolved_graph_json: &str) -> Result<String, String> {
+    crate::deps_digest(resolved_graph_json)
+}
@@ -0,0 +1,63 @@
+// Incremental package input for large packages.
+//
+// `compile()` takes every source file in one `files_json` string, which JS has to build and
+// WASM has to parse. `PackageBuilder` receives files one call at a time instead and writes
+// each into the compiler's in-memory filesystem as it arrives (see `ingest.rs`), so no single
+// multi-megabyte JSON string is ever created.
+//
+// Output and errors are identical to the string API.
+
+use crate::ingest::{dependency_group, Ingestion};
+use crate::{
+    compile_targets, prepare_ingested, BuildFailure, BuildLogger, CompileOptions, LogCallback, MoveCompilerResult,
+};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Default)]
+pub struct PackageBuilder {
+    ingestion: Ingestion,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl PackageBuilder {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> PackageBuilder {
+        PackageBuilder::default()
+    }
+
+    /// Add or replace a root package file.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = addFile))]
+    pub fn add_file(&mut self, path: &str, content: &str) -> Result<(), String> {
+        self.ingestion.add_file(None, path, content)
+    }
+
+    /// Declare a dependency package. `info_json` carries the same fields as an entry of
+    /// `compile()`'s dependencies array, without `name` and `files`
+    /// (e.g. `{ "addressMapping": {...}, "edition": "2024", "scope": "dev" }`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = addDependency))]
+    pub fn add_dependency(&mut self, name: &str, info_json: Option<String>) -> Result<(), String> {
+        self.ingestion.add_dependency(dependency_group(name, info_json)?)
+    }
+
+    /// Add a file to a dependency declared with `addDependency`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = addDependencyFile))]
+    pub fn add_dependency_file(&mut self, name: &str, path: &str, content: &str) -> Result<(), String> {
+        self.ingestion.add_file(Some(name), path, content)
+    }
+
+    /// Compile the accumulated package. Consumes the builder; output has the same shape as `compile()`.
+    pub fn compile(self, options_json: Option<String>, on_log: Option<LogCallback>) -> MoveCompilerResult {
+        let options: CompileOptions = match options_json.map(|json| serde_json::from_str(&json)).transpose() {
+            Ok(options) => options.unwrap_or_default(),
+            Err(e) => return MoveCompilerResult::err(format!("Failed to parse options: {}", e)),
+        };
+        let logger = BuildLogger::new(on_log);
+        let graph_json = options.dependency_graph.clone();
+        let result = prepare_ingested(self.ingestion, &options, &logger)
+            .map_err(BuildFailure::from)
+            .and_then(|(root, targets)| compile_targets(root, targets, &options, graph_json.as_deref(), &logger, None));
+        MoveCompilerResult::compiled(result)
+    }
+}
@@ -0,0 +1,105 @@
+// Constants of the root modules with decoded values (`emitConstants`).
+//
+// Every constant pool entry is listed, in pool order, with its raw BCS bytes (base64) and a
+// value rendered for display rather than round-tripping:
+// - integers as decimal strings (u64 and wider do not fit a JS number)
+// - `bool` as a boolean, `address` as a full-length 0x-prefixed hex string
+// - `vector<u8>` as `{ hex, utf8 }`, `utf8` being null when the bytes are not valid UTF-8
+// - other vectors as arrays of their rendered elements
+// A constant whose bytes do not decode against its type is listed with `value: null` and
+// its raw BCS bytes as hex in `bcs`, so one bad entry does not hide the rest.
+//
+// Names are not part of the bytecode: they come from the source map. Pool entries without
+// a source name (created by the compiler, e.g. for folded expressions) are named after
+// their index, `const_<index>`. Constants the compiler inlined or that nothing uses may
+// have no entry.
+
+use crate::interface::TypePrinter;
+use base64::{Engine as _, engine::general_purpose};
+use move_binary_format::file_format::{Constant, SignatureToken};
+use move_compiler::compiled_unit::NamedCompiledModule;
+use move_core_types::runtime_value::MoveValue;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub(crate) struct ConstantInfo {
+    /// Position in the module's constant pool.
+    index: u16,
+    /// Source name, or `const_<index>` for entries without one.
+    name: String,
+    #[serde(rename = "type")]
+    type_: String,
+    value: Option<serde_json::Value>,
+    /// Hex-encoded BCS bytes, when `value` could not be decoded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bcs: Option<String>,
+    /// BCS bytes of the value, base64-encoded.
+    #[serde(rename = "valueBytesB64")]
+    value_bytes_b64: String,
+}
+
+fn render(value: &MoveValue, token: &SignatureToken) -> serde_json::Value {
+    match (value, token) {
+        (MoveValue::Bool(b), _) => serde_json::Value::Bool(*b),
+        (MoveValue::U8(n), _) => n.to_string().into(),
+        (MoveValue::U16(n), _) => n.to_string().into(),
+        (MoveValue::U32(n), _) => n.to_string().into(),
+        (MoveValue::U64(n), _) => n.to_string().into(),
+        (MoveValue::U128(n), _) => n.to_string().into(),
+        (MoveValue::U256(n), _) => n.to_string().into(),
+        (MoveValue::Address(address), _) => address.to_canonical_string(true).into(),
+        (MoveValue::Vector(elements), SignatureToken::Vector(inner)) if **inner == SignatureToken::U8 => {
+            let bytes: Vec<u8> = elements
+                .iter()
+                .filter_map(|element| match element {
+                    MoveValue::U8(byte) => Some(*byte),
+                    _ => None,
+                })
+                .collect();
+            serde_json::json!({
+                "hex": format!("0x{}", hex::encode(&bytes)),
+                "utf8": String::from_utf8(bytes).ok(),
+            })
+        }
+        (MoveValue::Vector(elements), SignatureToken::Vector(inner)) => {
+            elements.iter().map(|element| render(element, inner)).collect()
+        }
+        // Constants are limited to primitives and vectors of them.
+        (other, _) => format!("{:?}", other).into(),
+    }
+}
+
+fn constant_info(index: u16, name: Option<String>, constant: &Constant, printer: &TypePrinter) -> ConstantInfo {
+    let value = constant.deserialize_constant().map(|value| render(&value, &constant.type_));
+    ConstantInfo {
+        index,
+        name: name.unwrap_or_else(|| format!("const_{}", index)),
+        type_: printer.print(&constant.type_),
+        bcs: value.is_none().then(|| hex::encode(&constant.data)),
+        value,
+        value_bytes_b64: general_purpose::STANDARD.encode(&constant.data),
+    }
+}
+
+/// Constants of `module`, in constant pool order.
+pub(crate) fn module_constants(module: &NamedCompiledModule) -> Vec<ConstantInfo> {
+    let no_names = BTreeMap::new();
+    let printer = TypePrinter::new(&module.module, &no_names);
+    let names: BTreeMap<u16, String> = module
+        .source_map
+        .constant_map
+        .iter()
+        .map(|(name, idx)| (*idx, name.to_string()))
+        .collect();
+    module
+        .module
+        .constant_pool()
+        .iter()
+        .enumerate()
+        .map(|(idx, constant)| {
+            let idx = idx as u16;
+            constant_info(idx, names.get(&idx).cloned(), constant, &printer)
+        })
+        .collect()
+}
@@ -0,0 +1,33 @@
+// Per-module debug info for the Move trace debugger (`emitDebugBundle`).
+//
+// Each root module carries what the CLI writes to `build/<pkg>/debug_info/<module>.mvsm`
+// and `build/<pkg>/sources/<module>.move`: the BCS-serialized source map (base64) and the
+// source text the compiler read, under the same file key diagnostics use. A debugger front
+// end can map program counters to source positions from the bundle alone.
+
+use base64::{Engine as _, engine::general_purpose};
+use move_compiler::{compiled_unit::NamedCompiledModule, shared::files::MappedFiles};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub(crate) struct DebugBundle {
+    module: String,
+    /// Base64 of the BCS-serialized `SourceMap`, byte-identical to the CLI's `.mvsm` file.
+    #[serde(rename = "sourceMap")]
+    source_map: String,
+    /// Input file key of the defining source, as in diagnostics.
+    file: String,
+    /// Source text as compiled; source map locations are byte offsets into it.
+    source: String,
+}
+
+/// Debug bundle of `module`, or None if its defining file is not among `files`.
+pub(crate) fn debug_bundle(module: &NamedCompiledModule, files: &MappedFiles) -> Option<DebugBundle> {
+    let (file, source) = files.get(&module.source_map.definition_location.file_hash())?;
+    Some(DebugBundle {
+        module: module.name.to_string(),
+        source_map: general_purpose::STANDARD.encode(module.serialize_source_map()),
+        file: file.to_string(),
+        source: source.to_string(),
+    })
+}
@@ -0,0 +1,170 @@
+// Documentation extraction for `build_docs`.
+//
+// Doc comments (`///` and `/** */`) are taken from the parser AST, where the compiler has
+// already attached each one to the module, struct, function or constant it precedes.
+// Signatures come from the compiled root modules, printed like `emitInterface`, so
+// abilities, type parameters and types are the resolved ones rather than the source text.
+// Constants have no name in bytecode; their signature is the declaration's source text up
+// to `=`. Rendering (Markdown or otherwise) is left to the caller.
+//
+// ORIGINAL SOURCE: move-docgen/src/docgen.rs (same declarations documented, private
+// functions included)
+
+use crate::interface::{EnumInterface, FunctionInterface, ModuleInterface, StructInterface};
+use move_command_line_common::files::FileHash;
+use move_compiler::parser::ast::{self as P, Definition, LeadingNameAccess_, ModuleMember};
+use move_compiler::shared::NumericalAddress;
+use move_core_types::account_address::AccountAddress;
+use move_symbol_pool::Symbol;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Serialize)]
+pub(crate) struct ModuleDocs {
+    #[serde(flatten)]
+    module: ModuleSummary,
+    doc: Option<String>,
+    structs: Vec<Documented<StructInterface>>,
+    enums: Vec<Documented<EnumInterface>>,
+    functions: Vec<Documented<FunctionInterface>>,
+    constants: Vec<ConstantDocs>,
+}
+
+#[derive(Serialize)]
+struct ModuleSummary {
+    address: String,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct Documented<T> {
+    #[serde(flatten)]
+    item: T,
+    doc: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ConstantDocs {
+    name: String,
+    signature: String,
+    doc: Option<String>,
+}
+
+/// Doc comments of one source module, by declaration name.
+#[derive(Default)]
+pub(crate) struct SourceDocs {
+    doc: Option<String>,
+    members: BTreeMap<String, Option<String>>,
+    /// Constants in declaration order: name, source signature, doc.
+    constants: Vec<(String, String, Option<String>)>,
+}
+
+fn doc_text(doc: &P::DocComment) -> Option<String> {
+    doc.comment().map(|comment| comment.value.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// Source text of a constant declaration up to its `=`, e.g. `const EMax: u64`.
+fn constant_signature(constant: &P::Constant, sources: &HashMap<FileHash, &str>) -> Option<String> {
+    let loc = constant.loc;
+    let source = sources.get(&loc.file_hash())?;
+    let text = source.get(loc.start() as usize..loc.end() as usize)?;
+    let signature = text.split('=').next().unwrap_or(text);
+    Some(signature.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Address a module is declared at, with named addresses resolved through `named_addresses`.
+fn declared_address(
+    address: &P::LeadingNameAccess,
+    named_addresses: &BTreeMap<String, NumericalAddress>,
+) -> Option<AccountAddress> {
+    match &address.value {
+        LeadingNameAccess_::AnonymousAddress(numerical) => Some(numerical.into_inner()),
+        LeadingNameAccess_::Name(name) | LeadingNameAccess_::GlobalAddress(name) => {
+            named_addresses.get(name.value.as_str()).map(|numerical| numerical.into_inner())
+        }
+    }
+}
+
+/// Collect the doc comments of every module in `package`'s source definitions, by address
+/// and module name: modules of the same name at two addresses are kept apart.
+pub(crate) fn collect_source_docs(
+    program: &P::Program,
+    package: Option<Symbol>,
+    named_addresses: &BTreeMap<String, NumericalAddress>,
+    files: &BTreeMap<String, String>,
+) -> BTreeMap<(AccountAddress, String), SourceDocs> {
+    let sources: HashMap<FileHash, &str> = files
+        .values()
+        .map(|contents| (FileHash::new(contents), contents.as_str()))
+        .collect();
+    let modules = program
+        .source_definitions
+        .iter()
+        .filter(|def| def.package == package)
+        .flat_map(|def| match &def.def {
+            Definition::Module(module) => vec![(module.address.as_ref(), module)],
+            Definition::Address(address) => {
+                address.modules.iter().map(|module| (Some(&address.addr), module)).collect()
+            }
+        });
+
+    let mut docs = BTreeMap::new();
+    for (address, module) in modules {
+        let Some(address) = address.and_then(|address| declared_address(address, named_addresses)) else {
+            continue;
+        };
+        let mut source_docs = SourceDocs { doc: doc_text(&module.doc), ..SourceDocs::default() };
+        for member in &module.members {
+            match member {
+                ModuleMember::Function(function) => {
+                    source_docs.members.insert(function.name.0.value.to_string(), doc_text(&function.doc));
+                }
+                ModuleMember::Struct(struct_def) => {
+                    source_docs.members.insert(struct_def.name.0.value.to_string(), doc_text(&struct_def.doc));
+                }
+                ModuleMember::Enum(enum_def) => {
+                    source_docs.members.insert(enum_def.name.0.value.to_string(), doc_text(&enum_def.doc));
+                }
+                ModuleMember::Constant(constant) => {
+                    let name = constant.name.0.value.to_string();
+                    let signature = constant_signature(constant, &sources).unwrap_or_else(|| format!("const {}", name));
+                    source_docs.constants.push((name, signature, doc_text(&constant.doc)));
+                }
+                _ => {}
+            }
+        }
+        docs.insert((address, module.name.0.value.to_string()), source_docs);
+    }
+    docs
+}
+
+/// Pair a compiled module's summary with the doc comments of its source.
+pub(crate) fn module_docs(interface: ModuleInterface, mut source: SourceDocs) -> ModuleDocs {
+    let ModuleInterface { address, name, structs, enums, functions } = interface;
+    let mut doc_for = |name: &str| source.members.remove(name).flatten();
+    let structs = structs
+        .into_iter()
+        .map(|item| Documented { doc: doc_for(&item.name), item })
+        .collect();
+    let enums = enums
+        .into_iter()
+        .map(|item| Documented { doc: doc_for(&item.name), item })
+        .collect();
+    let functions = functions
+        .into_iter()
+        .map(|item| Documented { doc: doc_for(&item.name), item })
+        .collect();
+    let constants = source
+        .constants
+        .into_iter()
+        .map(|(name, signature, doc)| ConstantDocs { name, signature, doc })
+        .collect();
+    ModuleDocs {
+        module: ModuleSummary { address, name },
+        doc: source.doc,
+        structs,
+        enums,
+        functions,
+        constants,
+    }
+}
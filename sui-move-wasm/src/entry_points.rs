@@ -0,0 +1,268 @@
+// Callable functions of the root modules with what a transaction builder needs per
+// parameter (`emitEntryPoints`).
+//
+// Each public or entry function is listed under its fully qualified name with its type
+// parameters, and every parameter is classified the way a programmable transaction passes it:
+// - "pure": BCS bytes; `layout` is the annotated `MoveTypeLayout` to encode them with
+// - "object": an object argument (a type with `key`), by value or by reference
+// - "txContext": `sui::tx_context::TxContext`, supplied by the runtime and omitted by callers
+// - "typeParameter": a function type parameter; pure or object depending on instantiation
+// - "other": a value that can only come from an earlier command's result
+// Types print with hex addresses so they can be fed to a transaction builder as is.
+//
+// ORIGINAL SOURCE: sui-types/src/transaction.rs / sui-adapter execution/entry argument rules
+// (`is_primitive` pure types), sui-json-rpc-types SuiMoveNormalizedFunction (same selection)
+
+use crate::interface::{abilities, TypePrinter};
+use move_binary_format::{
+    file_format::{DatatypeHandleIndex, SignatureToken, Visibility},
+    CompiledModule,
+};
+use move_core_types::{
+    account_address::AccountAddress,
+    annotated_value::{MoveEnumLayout, MoveFieldLayout, MoveStructLayout, MoveTypeLayout},
+    language_storage::{ModuleId, StructTag, TypeTag},
+};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub(crate) struct EntryPoint {
+    /// `0x...::module::function`.
+    #[serde(rename = "fullName")]
+    full_name: String,
+    /// "public", "package" or "private" (private `entry` functions).
+    visibility: &'static str,
+    #[serde(rename = "isEntry")]
+    is_entry: bool,
+    /// Ability constraints of each type parameter.
+    #[serde(rename = "typeParameters")]
+    type_parameters: Vec<Vec<&'static str>>,
+    parameters: Vec<EntryParameter>,
+    #[serde(rename = "return")]
+    return_: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct EntryParameter {
+    #[serde(rename = "type")]
+    type_: String,
+    kind: &'static str,
+    /// "immutable" or "mutable" for reference parameters; absent when passed by value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reference: Option<&'static str>,
+    /// BCS layout of a "pure" parameter; absent if a type it uses was not compiled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layout: Option<MoveTypeLayout>,
+}
+
+/// Datatype names whose values are passed as pure BCS bytes besides primitives and vectors.
+const PURE_DATATYPES: [(AccountAddress, &str, &str); 4] = [
+    (AccountAddress::ONE, "string", "String"),
+    (AccountAddress::ONE, "ascii", "String"),
+    (AccountAddress::ONE, "option", "Option"),
+    (AccountAddress::TWO, "object", "ID"),
+];
+
+/// Resolves type layouts over every compiled module (root and dependencies).
+pub(crate) struct Layouts<'a> {
+    modules: BTreeMap<ModuleId, &'a CompiledModule>,
+}
+
+impl<'a> Layouts<'a> {
+    pub(crate) fn new(modules: impl Iterator<Item = &'a CompiledModule>) -> Self {
+        Layouts { modules: modules.map(|module| (module.self_id(), module)).collect() }
+    }
+
+    /// Layout of `tag`, or None if one of its datatypes is not among the compiled modules.
+    fn layout(&self, tag: &TypeTag) -> Option<MoveTypeLayout> {
+        Some(match tag {
+            TypeTag::Bool => MoveTypeLayout::Bool,
+            TypeTag::U8 => MoveTypeLayout::U8,
+            TypeTag::U16 => MoveTypeLayout::U16,
+            TypeTag::U32 => MoveTypeLayout::U32,
+            TypeTag::U64 => MoveTypeLayout::U64,
+            TypeTag::U128 => MoveTypeLayout::U128,
+            TypeTag::U256 => MoveTypeLayout::U256,
+            TypeTag::Address => MoveTypeLayout::Address,
+            TypeTag::Signer => MoveTypeLayout::Signer,
+            TypeTag::Vector(inner) => MoveTypeLayout::Vector(Box::new(self.layout(inner)?)),
+            TypeTag::Struct(tag) => self.datatype_layout(tag)?,
+        })
+    }
+
+    fn datatype_layout(&self, tag: &StructTag) -> Option<MoveTypeLayout> {
+        let module = self.modules.get(&tag.module_id())?;
+        let field_layouts = |fields: &[move_binary_format::file_format::FieldDefinition]| {
+            fields
+                .iter()
+                .map(|field| {
+                    let field_tag = type_tag(module, &field.signature.0, &tag.type_params)?;
+                    Some(MoveFieldLayout::new(
+                        module.identifier_at(field.name).to_owned(),
+                        self.layout(&field_tag)?,
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        if let Some(def) = module
+            .struct_defs()
+            .iter()
+            .find(|def| module.identifier_at(module.datatype_handle_at(def.struct_handle).name) == tag.name.as_ident_str())
+        {
+            let fields = field_layouts(def.fields()?)?;
+            return Some(MoveTypeLayout::Struct(Box::new(MoveStructLayout { type_: tag.clone(), fields })));
+        }
+        let def = module
+            .enum_defs()
+            .iter()
+            .find(|def| module.identifier_at(module.datatype_handle_at(def.enum_handle).name) == tag.name.as_ident_str())?;
+        let variants = def
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(tag_idx, variant)| {
+                let name = module.identifier_at(variant.variant_name).to_owned();
+                Some(((name, tag_idx as u16), field_layouts(&variant.fields)?))
+            })
+            .collect::<Option<BTreeMap<_, _>>>()?;
+        Some(MoveTypeLayout::Enum(Box::new(MoveEnumLayout { type_: tag.clone(), variants })))
+    }
+}
+
+fn datatype_tag(module: &CompiledModule, idx: DatatypeHandleIndex, type_params: Vec<TypeTag>) -> StructTag {
+    let handle = module.datatype_handle_at(idx);
+    let module_handle = module.module_handle_at(handle.module);
+    StructTag {
+        address: *module.address_identifier_at(module_handle.address),
+        module: module.identifier_at(module_handle.name).to_owned(),
+        name: module.identifier_at(handle.name).to_owned(),
+        type_params,
+    }
+}
+
+/// `token` as a type tag, with type parameters replaced by `subst`. None for references
+/// and unbound type parameters.
+fn type_tag(module: &CompiledModule, token: &SignatureToken, subst: &[TypeTag]) -> Option<TypeTag> {
+    Some(match token {
+        SignatureToken::Bool => TypeTag::Bool,
+        SignatureToken::U8 => TypeTag::U8,
+        SignatureToken::U16 => TypeTag::U16,
+        SignatureToken::U32 => TypeTag::U32,
+        SignatureToken::U64 => TypeTag::U64,
+        SignatureToken::U128 => TypeTag::U128,
+        SignatureToken::U256 => TypeTag::U256,
+        SignatureToken::Address => TypeTag::Address,
+        SignatureToken::Signer => TypeTag::Signer,
+        SignatureToken::Vector(inner) => TypeTag::Vector(Box::new(type_tag(module, inner, subst)?)),
+        SignatureToken::Datatype(idx) => TypeTag::Struct(Box::new(datatype_tag(module, *idx, vec![]))),
+        SignatureToken::DatatypeInstantiation(inst) => {
+            let (idx, args) = &**inst;
+            let args = args
+                .iter()
+                .map(|arg| type_tag(module, arg, subst))
+                .collect::<Option<Vec<_>>>()?;
+            TypeTag::Struct(Box::new(datatype_tag(module, *idx, args)))
+        }
+        SignatureToken::TypeParameter(idx) => subst.get(*idx as usize)?.clone(),
+        SignatureToken::Reference(_) | SignatureToken::MutableReference(_) => return None,
+    })
+}
+
+/// Whether values of `token` are passed as pure bytes.
+fn is_pure(module: &CompiledModule, token: &SignatureToken) -> bool {
+    let is_pure_datatype = |idx: DatatypeHandleIndex| {
+        let tag = datatype_tag(module, idx, vec![]);
+        PURE_DATATYPES.iter().any(|(address, module_name, name)| {
+            tag.address == *address && tag.module.as_str() == *module_name && tag.name.as_str() == *name
+        })
+    };
+    match token {
+        SignatureToken::Bool
+        | SignatureToken::U8
+        | SignatureToken::U16
+        | SignatureToken::U32
+        | SignatureToken::U64
+        | SignatureToken::U128
+        | SignatureToken::U256
+        | SignatureToken::Address => true,
+        SignatureToken::Vector(inner) => is_pure(module, inner),
+        SignatureToken::Datatype(idx) => is_pure_datatype(*idx),
+        SignatureToken::DatatypeInstantiation(inst) => {
+            let (idx, args) = &**inst;
+            is_pure_datatype(*idx) && args.iter().all(|arg| is_pure(module, arg))
+        }
+        _ => false,
+    }
+}
+
+fn is_tx_context(module: &CompiledModule, token: &SignatureToken) -> bool {
+    let SignatureToken::Datatype(idx) = token else { return false };
+    let tag = datatype_tag(module, *idx, vec![]);
+    tag.address == AccountAddress::TWO && tag.module.as_str() == "tx_context" && tag.name.as_str() == "TxContext"
+}
+
+fn parameter(module: &CompiledModule, token: &SignatureToken, layouts: &Layouts) -> EntryParameter {
+    let no_names = BTreeMap::new();
+    let type_ = TypePrinter::new(module, &no_names).print(token);
+    let (inner, reference) = match token {
+        SignatureToken::Reference(inner) => (&**inner, Some("immutable")),
+        SignatureToken::MutableReference(inner) => (&**inner, Some("mutable")),
+        other => (other, None),
+    };
+    let has_key = |idx: &DatatypeHandleIndex| module.datatype_handle_at(*idx).abilities.has_key();
+    let kind = match inner {
+        _ if is_tx_context(module, inner) => "txContext",
+        _ if is_pure(module, inner) => "pure",
+        SignatureToken::TypeParameter(_) => "typeParameter",
+        SignatureToken::Datatype(idx) if has_key(idx) => "object",
+        SignatureToken::DatatypeInstantiation(inst) if has_key(&inst.0) => "object",
+        _ => "other",
+    };
+    let layout = (kind == "pure")
+        .then(|| type_tag(module, inner, &[]).and_then(|tag| layouts.layout(&tag)))
+        .flatten();
+    EntryParameter { type_, kind, reference, layout }
+}
+
+/// Public and entry functions of `module`, for building transactions against it.
+pub(crate) fn entry_points(module: &CompiledModule, layouts: &Layouts) -> Vec<EntryPoint> {
+    let id = module.self_id();
+    let no_names = BTreeMap::new();
+    let printer = TypePrinter::new(module, &no_names);
+    module
+        .function_defs()
+        .iter()
+        .filter(|def| def.visibility == Visibility::Public || def.is_entry)
+        .map(|def| {
+            let handle = module.function_handle_at(def.function);
+            EntryPoint {
+                full_name: format!(
+                    "{}::{}::{}",
+                    id.address().to_hex_literal(),
+                    id.name(),
+                    module.identifier_at(handle.name)
+                ),
+                visibility: match def.visibility {
+                    Visibility::Public => "public",
+                    Visibility::Friend => "package",
+                    Visibility::Private => "private",
+                },
+                is_entry: def.is_entry,
+                type_parameters: handle.type_parameters.iter().map(|set| abilities(*set)).collect(),
+                parameters: module
+                    .signature_at(handle.parameters)
+                    .0
+                    .iter()
+                    .map(|token| parameter(module, token, layouts))
+                    .collect(),
+                return_: module
+                    .signature_at(handle.return_)
+                    .0
+                    .iter()
+                    .map(|token| printer.print(token))
+                    .collect(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,364 @@
+// Source formatting for the playground's "Format" button.
+//
+// The pinned move-compiler ships no pretty-printer (movefmt and prettier-plugin-move live
+// outside the compiler), so this is a canonicalizing pass rather than a full formatter:
+// - line endings are normalized to `\n`
+// - trailing whitespace is removed and leading tabs become four spaces
+// - runs of blank lines collapse to one; leading blank lines are dropped
+// - the file ends with exactly one newline
+// - with `indent`, indentation is rescaled from the file's own unit (its smallest indent) to
+//   `indent` spaces per level, keeping continuation offsets beyond a whole level
+// - with `maxWidth`, a longer line is broken at the commas of its outermost bracketed list
+//   (`(...)`, `[...]` or `{...}` closing on that line): one item per line, one level deeper,
+//   and the closing bracket back at the line's indentation. The pieces are broken again
+//   while they are still too long and have such a list; lines with a `//` comment are kept.
+// Spacing inside lines is left as written, and no tokens are added or removed, so comments
+// and attributes survive. String literals and block comments are never touched. Every rule
+// is a fixed point, so formatting formatted output returns the same bytes.
+//
+// The source is parsed first, with `edition` (default 2024.beta); a file that does not parse
+// is returned unchanged as an error.
+
+use crate::MoveCompilerResult;
+use move_compiler::{
+    Compiler, Flags,
+    diagnostics::report_diagnostics_to_buffer,
+    editions::{Edition, Flavor},
+    shared::{PackageConfig, PackagePaths},
+};
+use move_symbol_pool::Symbol;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use vfs::{VfsPath, impls::memory::MemoryFS};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const SOURCE_PATH: &str = "source.move";
+/// Columns a leading tab stands for.
+const TAB_WIDTH: usize = 4;
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct FormatOptions {
+    /// Spaces per indentation level. Indentation is kept as written when unset.
+    #[serde(default)]
+    indent: Option<usize>,
+    /// Columns a line may take before it is broken. Lines are kept as written when unset.
+    #[serde(default, rename = "maxWidth")]
+    max_width: Option<usize>,
+    /// Edition the file is parsed with, as in Move.toml. Defaults to 2024.beta.
+    #[serde(default)]
+    edition: Option<String>,
+}
+
+/// Format a single `.move` file. Output is the formatted text, or the parse diagnostics.
+/// `options_json` is `{ "indent": 4, "maxWidth": 100, "edition": "2024.beta" }` (every field
+/// optional) or omitted.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn format_source(source: &str, options_json: Option<String>) -> MoveCompilerResult {
+    let options: FormatOptions = match options_json {
+        Some(json) => match serde_json::from_str(&json) {
+            Ok(options) => options,
+            Err(e) => return MoveCompilerResult::err(format!("Invalid format options: {}", e)),
+        },
+        None => FormatOptions::default(),
+    };
+    if options.indent == Some(0) {
+        return MoveCompilerResult::err("Invalid format options: indent must be at least 1".to_string());
+    }
+    if options.max_width == Some(0) {
+        return MoveCompilerResult::err("Invalid format options: maxWidth must be at least 1".to_string());
+    }
+    let edition = match options.edition.as_deref().map(crate::parse_edition).transpose() {
+        Ok(edition) => edition.unwrap_or(Edition::E2024_BETA),
+        Err(e) => return MoveCompilerResult::err(format!("Invalid format options: {}", e)),
+    };
+    if let Err(e) = check_syntax(source, edition) {
+        return MoveCompilerResult::err(e);
+    }
+    let formatted = canonicalize(source, options.indent);
+    match options.max_width {
+        Some(max_width) => {
+            let step = options.indent.or_else(|| indent_unit(&formatted)).unwrap_or(TAB_WIDTH);
+            MoveCompilerResult::ok(wrap(&formatted, max_width, step))
+        }
+        None => MoveCompilerResult::ok(formatted),
+    }
+}
+
+/// Run the compiler's parser over `source`.
+fn check_syntax(source: &str, edition: Edition) -> Result<(), String> {
+    let root = VfsPath::new(MemoryFS::new());
+    root.join(SOURCE_PATH)
+        .and_then(|path| path.create_file())
+        .and_then(|mut file| {
+            use std::io::Write;
+            file.write_all(source.as_bytes())?;
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to create {}: {}", SOURCE_PATH, e))?;
+
+    let target = PackagePaths {
+        name: Some((
+            Symbol::from("source"),
+            PackageConfig {
+                is_dependency: false,
+                edition,
+                flavor: Flavor::Sui,
+                ..PackageConfig::default()
+            },
+        )),
+        paths: vec![Symbol::from(SOURCE_PATH)],
+        named_address_map: BTreeMap::new(),
+    };
+    let compiler = Compiler::from_package_paths(Some(root), vec![target], Vec::new())
+        .map_err(|e| format!("Failed to create compiler: {}", e))?;
+    let (files_info, res) = compiler
+        .set_flags(Flags::empty())
+        .run::<{ move_compiler::PASS_PARSER }>()
+        .map_err(|e| format!("Compiler error: {}", e))?;
+    match res {
+        Ok(_) => Ok(()),
+        Err((_severity, diags)) => {
+            let buffer = report_diagnostics_to_buffer(&files_info, diags, false);
+            Err(String::from_utf8_lossy(&buffer).to_string())
+        }
+    }
+}
+
+/// Apply the whitespace rules described at the top of this module.
+fn canonicalize(source: &str, indent: Option<usize>) -> String {
+    let source = source.replace("\r\n", "\n").replace('\r', "\n");
+    let unit = indent.and_then(|_| indent_unit(&source));
+    let mut out = String::with_capacity(source.len());
+    let mut state = ScanState::Code;
+    let mut pending_blank = false;
+
+    for line in source.split('\n') {
+        let starts_in_code = state == ScanState::Code;
+        state = scan_line(line, state);
+        let ends_in_code = state == ScanState::Code;
+
+        // Lines that begin or end inside a string or block comment are copied verbatim.
+        if !starts_in_code || !ends_in_code {
+            if pending_blank {
+                out.push('\n');
+                pending_blank = false;
+            }
+            out.push_str(line);
+            out.push('\n');
+            continue;
+        }
+
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() {
+            pending_blank = !out.is_empty();
+            continue;
+        }
+        if pending_blank {
+            out.push('\n');
+            pending_blank = false;
+        }
+        let indent_len = trimmed.len() - trimmed.trim_start_matches([' ', '\t']).len();
+        let width = indent_width(&trimmed[..indent_len]);
+        let width = match (indent, unit) {
+            // Partial-level offsets are capped below one new level so the result is stable.
+            (Some(indent), Some(unit)) => width / unit * indent + (width % unit).min(indent - 1),
+            _ => width,
+        };
+        out.extend(std::iter::repeat_n(' ', width));
+        out.push_str(&trimmed[indent_len..]);
+        out.push('\n');
+    }
+
+    if out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Apply the `maxWidth` rule described at the top of this module to canonicalized `source`,
+/// indenting list items `step` columns deeper than their line.
+fn wrap(source: &str, max_width: usize, step: usize) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut state = ScanState::Code;
+    for line in source.lines() {
+        let starts_in_code = state == ScanState::Code;
+        state = scan_line(line, state);
+        if starts_in_code && state == ScanState::Code {
+            wrap_line(line, max_width, step, &mut out);
+        } else {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Push `line` to `out`, broken as needed. Every piece holds fewer brackets than `line`, so
+/// the recursion ends.
+fn wrap_line(line: &str, max_width: usize, step: usize, out: &mut String) {
+    let list = if line.chars().count() > max_width { outermost_list(line) } else { None };
+    let Some((open, commas, close)) = list else {
+        out.push_str(line);
+        out.push('\n');
+        return;
+    };
+    let indent = &line[..line.len() - line.trim_start().len()];
+    let item_indent = format!("{}{}", indent, " ".repeat(step));
+    wrap_line(&line[..=open], max_width, step, out);
+    let mut start = open + 1;
+    for end in commas.into_iter().map(|comma| comma + 1).chain([close]) {
+        let item = line[start..end].trim();
+        if !item.is_empty() {
+            wrap_line(&format!("{}{}", item_indent, item), max_width, step, out);
+        }
+        start = end;
+    }
+    wrap_line(&format!("{}{}", indent, &line[close..]), max_width, step, out);
+}
+
+/// Byte offsets of the opening bracket, the item commas and the closing bracket of the
+/// least nested `(...)`, `[...]` or `{...}` list with commas that opens and closes in
+/// `line` (the first of several). Commas inside type arguments (`<...>` after a name) do not
+/// separate items. `None` for lines without one and lines with a `//` comment.
+fn outermost_list(line: &str) -> Option<(usize, Vec<usize>, usize)> {
+    let bytes = line.as_bytes();
+    let mut state = ScanState::Code;
+    // Open brackets: (bracket, offset, commas directly inside).
+    let mut stack: Vec<(u8, usize, Vec<usize>)> = Vec::new();
+    let mut best: Option<(usize, (usize, Vec<usize>, usize))> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        match state {
+            ScanState::Code => match (bytes[i], bytes.get(i + 1)) {
+                (b'/', Some(b'/')) => return None,
+                (b'/', Some(b'*')) => {
+                    state = ScanState::BlockComment(1);
+                    i += 1;
+                }
+                (b'"', _) => state = ScanState::String,
+                (b'(' | b'[' | b'{', _) => stack.push((bytes[i], i, Vec::new())),
+                (b'<', _) if i > 0 && (bytes[i - 1].is_ascii_alphanumeric() || matches!(bytes[i - 1], b'_' | b':')) => {
+                    stack.push((b'<', i, Vec::new()))
+                }
+                (b'>', _) if stack.last().is_some_and(|(bracket, ..)| *bracket == b'<') => {
+                    stack.pop();
+                }
+                (b')' | b']' | b'}', _) => {
+                    let opening = match bytes[i] {
+                        b')' => b'(',
+                        b']' => b'[',
+                        _ => b'{',
+                    };
+                    // A `<` that never closed was a comparison.
+                    while stack.last().is_some_and(|(bracket, ..)| *bracket == b'<') {
+                        stack.pop();
+                    }
+                    if stack.last().is_some_and(|(bracket, ..)| *bracket == opening) {
+                        let (_, open, commas) = stack.pop().expect("checked above");
+                        let depth = stack.len();
+                        if !commas.is_empty() && best.as_ref().is_none_or(|(best_depth, _)| depth < *best_depth) {
+                            best = Some((depth, (open, commas, i)));
+                        }
+                    }
+                }
+                (b',', _) => {
+                    if let Some((_, _, commas)) = stack.last_mut() {
+                        commas.push(i);
+                    }
+                }
+                _ => {}
+            },
+            ScanState::String => match bytes[i] {
+                b'\\' => i += 1,
+                b'"' => state = ScanState::Code,
+                _ => {}
+            },
+            ScanState::BlockComment(depth) => match (bytes[i], bytes.get(i + 1)) {
+                (b'*', Some(b'/')) => {
+                    state = if depth == 1 { ScanState::Code } else { ScanState::BlockComment(depth - 1) };
+                    i += 1;
+                }
+                (b'/', Some(b'*')) => {
+                    state = ScanState::BlockComment(depth + 1);
+                    i += 1;
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    best.map(|(_, list)| list)
+}
+
+/// Columns covered by leading whitespace, with tabs expanded.
+fn indent_width(leading: &str) -> usize {
+    leading.chars().map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+/// The file's indentation unit: the smallest non-zero indent of a line that starts and ends
+/// in code. `None` when no such line is indented.
+fn indent_unit(source: &str) -> Option<usize> {
+    let mut state = ScanState::Code;
+    let mut unit: Option<usize> = None;
+    for line in source.split('\n') {
+        let starts_in_code = state == ScanState::Code;
+        state = scan_line(line, state);
+        if !starts_in_code || state != ScanState::Code || line.trim().is_empty() {
+            continue;
+        }
+        let leading = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        let width = indent_width(leading);
+        if width > 0 {
+            unit = Some(unit.map_or(width, |unit| unit.min(width)));
+        }
+    }
+    unit
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Code,
+    /// Inside a `b"..."` / `x"..."` literal.
+    String,
+    /// Inside a block comment, with its nesting depth.
+    BlockComment(u32),
+}
+
+/// Lexer state after `line`, given the state it starts in.
+fn scan_line(line: &str, mut state: ScanState) -> ScanState {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match state {
+            ScanState::Code => match (bytes[i], bytes.get(i + 1)) {
+                (b'/', Some(b'/')) => return ScanState::Code,
+                (b'/', Some(b'*')) => {
+                    state = ScanState::BlockComment(1);
+                    i += 1;
+                }
+                (b'"', _) => state = ScanState::String,
+                _ => {}
+            },
+            ScanState::String => match bytes[i] {
+                b'\\' => i += 1,
+                b'"' => state = ScanState::Code,
+                _ => {}
+            },
+            ScanState::BlockComment(depth) => match (bytes[i], bytes.get(i + 1)) {
+                (b'*', Some(b'/')) => {
+                    state = if depth == 1 { ScanState::Code } else { ScanState::BlockComment(depth - 1) };
+                    i += 1;
+                }
+                (b'/', Some(b'*')) => {
+                    state = ScanState::BlockComment(depth + 1);
+                    i += 1;
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    state
+}
@@ -0,0 +1,108 @@
+// Structured clean-up hints for editors (`emitHints`), separate from the warnings text.
+//
+// Kinds:
+// - "unusedUse": a `use` alias nothing refers to (the compiler's unused-alias warning)
+// - "unusedConstant": a constant nothing refers to (the compiler's unused-constant warning)
+// - "unreachableFunction": a private function no public, `public(package)`, entry or `init`
+//   function of its module reaches, even if other unreachable functions call it
+// Each hint carries the input file key, the byte range of the span and its source text,
+// so an editor can offer removing it directly. Hints are taken from the diagnostics before
+// `silenceWarnings`/`suppressWarnings` apply.
+
+use move_binary_format::file_format::{Bytecode, FunctionDefinitionIndex, Visibility};
+use move_compiler::{
+    compiled_unit::NamedCompiledModule,
+    diagnostics::Diagnostic,
+    shared::files::MappedFiles,
+};
+use move_ir_types::location::Loc;
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+#[derive(Serialize, Clone)]
+pub(crate) struct Hint {
+    kind: &'static str,
+    /// Input file key.
+    file: String,
+    /// Byte offsets of the span in the file.
+    start: u32,
+    end: u32,
+    /// Source text of the span, e.g. the alias of an unused `use`.
+    text: String,
+    message: String,
+}
+
+fn hint(kind: &'static str, loc: Loc, message: String, files: &MappedFiles) -> Option<Hint> {
+    let (file, source) = files.get(&loc.file_hash())?;
+    let text = source.get(loc.start() as usize..loc.end() as usize)?.to_string();
+    Some(Hint { kind, file: file.to_string(), start: loc.start(), end: loc.end(), text, message })
+}
+
+/// Hints from the unused-alias and unused-constant warnings among `diags`.
+pub(crate) fn diagnostic_hints(diags: &[Diagnostic], files: &MappedFiles) -> Vec<Hint> {
+    diags
+        .iter()
+        .filter_map(|diag| {
+            let kind = match diag.info().message() {
+                "unused alias" => "unusedUse",
+                "unused constant" => "unusedConstant",
+                _ => return None,
+            };
+            let (_, _, (loc, message), _, _) = diag.clone().into_codespan_format();
+            hint(kind, loc, message, files)
+        })
+        .collect()
+}
+
+/// Private functions of `module` that none of its externally callable functions reach.
+pub(crate) fn unreachable_function_hints(module: &NamedCompiledModule, files: &MappedFiles) -> Vec<Hint> {
+    let compiled = &module.module;
+    let self_handle = compiled.self_handle_idx();
+    let defs = compiled.function_defs();
+    let def_of = |handle_idx| defs.iter().position(|def| def.function == handle_idx);
+
+    let mut reached = BTreeSet::new();
+    let mut worklist: Vec<usize> = defs
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| {
+            def.visibility != Visibility::Private
+                || def.is_entry
+                || compiled.identifier_at(compiled.function_handle_at(def.function).name).as_str() == "init"
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    while let Some(idx) = worklist.pop() {
+        if !reached.insert(idx) {
+            continue;
+        }
+        let Some(code) = &defs[idx].code else { continue };
+        for instruction in &code.code {
+            let handle_idx = match instruction {
+                Bytecode::Call(handle_idx) => *handle_idx,
+                Bytecode::CallGeneric(inst_idx) => compiled.function_instantiation_at(*inst_idx).handle,
+                _ => continue,
+            };
+            if compiled.function_handle_at(handle_idx).module != self_handle {
+                continue;
+            }
+            if let Some(callee) = def_of(handle_idx) {
+                worklist.push(callee);
+            }
+        }
+    }
+
+    (0..defs.len())
+        .filter(|idx| !reached.contains(idx))
+        .filter_map(|idx| {
+            let name = compiled.identifier_at(compiled.function_handle_at(defs[idx].function).name);
+            let loc = module
+                .source_map
+                .get_function_source_map(FunctionDefinitionIndex(idx as u16))
+                .ok()?
+                .definition_location;
+            let message = format!("Function '{}' is not reachable from any public, entry or init function", name);
+            hint("unreachableFunction", loc, message, files)
+        })
+        .collect()
+}
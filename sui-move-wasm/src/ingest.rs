@@ -0,0 +1,202 @@
+// Incremental package input, behind `PackageBuilder` (builder.rs).
+//
+// Every file is written into the in-memory filesystem the compiler reads as it arrives, so
+// no `files`/`dependencies` JSON string has to be built and parsed. A dependency's `.move`
+// sources then live only in the filesystem: its package group records their paths with
+// empty contents. Manifests, lockfiles and root sources are kept in the groups as well,
+// since setup reads them (manifest resolution, lock checks, framework address detection).
+//
+// A path added by two packages (e.g. variants of a dependency for different build modes)
+// keeps every copy in its group until `prepare` knows the mode, then the selected copy is
+// written to the filesystem.
+//
+// `compile()`, `compile_workspace` and `CompilerSession` take their inputs through `Ingestion`
+// too, so every entry point runs the same checks.
+
+use crate::{
+    check_dependency_cycles, check_input_contents, check_input_sizes, check_lock_option, check_path_collisions,
+    ensure_vfs_parents, select_dependency_scope, select_mode, validate_input_path, BuildLogger, CompileOptions,
+    PackageGroup,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use vfs::{MemoryFS, VfsPath};
+
+/// Package files received so far, already written to `root`.
+pub(crate) struct Ingestion {
+    root: VfsPath,
+    files: BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    /// Path -> every package that added it (None for the root), first writer first.
+    owners: BTreeMap<String, Vec<Option<String>>>,
+    /// (package, path) -> size in bytes, checked against the limits once options are known.
+    sizes: BTreeMap<(Option<String>, String), usize>,
+}
+
+impl Default for Ingestion {
+    fn default() -> Self {
+        Ingestion {
+            root: VfsPath::new(MemoryFS::new()),
+            files: BTreeMap::new(),
+            dep_packages: Vec::new(),
+            owners: BTreeMap::new(),
+            sizes: BTreeMap::new(),
+        }
+    }
+}
+
+impl Ingestion {
+    /// Ingest the inputs of the one-shot API.
+    pub(crate) fn of(files: &BTreeMap<String, String>, dep_packages: Vec<PackageGroup>) -> Result<Self, String> {
+        let mut ingestion = Ingestion::default();
+        for (path, contents) in files {
+            ingestion.add_file(None, path, contents)?;
+        }
+        for group in dep_packages {
+            ingestion.add_dependency(group)?;
+        }
+        Ok(ingestion)
+    }
+
+    /// Declare a dependency group. Files it already carries (e.g. stubs generated from
+    /// `normalizedModules`) are added as well.
+    pub(crate) fn add_dependency(&mut self, mut group: PackageGroup) -> Result<(), String> {
+        if self.dep_packages.iter().any(|pkg| pkg.name == group.name) {
+            return Err(format!("Dependency '{}' was already added", group.name));
+        }
+        let files = std::mem::take(&mut group.files);
+        let name = group.name.clone();
+        self.dep_packages.push(group);
+        for (path, contents) in &files {
+            self.add_file(Some(&name), path, contents)?;
+        }
+        Ok(())
+    }
+
+    /// Add or replace a file of `package` (None for the root).
+    pub(crate) fn add_file(&mut self, package: Option<&str>, path: &str, contents: &str) -> Result<(), String> {
+        validate_input_path(path).map_err(|e| match package {
+            Some(name) => format!("Dependency '{}': {}", name, e),
+            None => e,
+        })?;
+        check_input_contents(package, path, contents)?;
+        if let Some(name) = package {
+            if !self.dep_packages.iter().any(|pkg| pkg.name == name) {
+                return Err(format!("Unknown package '{}'; declare it before adding its files", name));
+            }
+        }
+
+        let owner = package.map(str::to_string);
+        let owners = self.owners.entry(path.to_string()).or_default();
+        if !owners.contains(&owner) {
+            owners.push(owner.clone());
+        }
+        let first = owners[0].clone();
+        let shared = owners.len() > 1;
+        if shared && owners.len() == 2 && first != owner {
+            // The first copy so far lived in the filesystem only.
+            let existing = read_file(&self.root, path)?;
+            self.group_files(first.as_deref()).insert(path.to_string(), existing);
+        }
+        if first == owner {
+            write_file(&self.root, path, contents)?;
+        }
+        let keep = package.is_none() || !path.ends_with(".move") || shared;
+        self.group_files(package)
+            .insert(path.to_string(), if keep { contents.to_string() } else { String::new() });
+        self.sizes.insert((owner, path.to_string()), contents.len());
+        Ok(())
+    }
+
+    fn group_files(&mut self, package: Option<&str>) -> &mut BTreeMap<String, String> {
+        match package {
+            None => &mut self.files,
+            Some(name) => {
+                &mut self
+                    .dep_packages
+                    .iter_mut()
+                    .find(|pkg| pkg.name == name)
+                    .expect("files are only recorded for declared packages")
+                    .files
+            }
+        }
+    }
+
+    /// Select the files and groups of the build `options` describe and check them: input
+    /// limits, lock pins, path collisions and dependency cycles.
+    pub(crate) fn prepare(
+        self,
+        options: &CompileOptions,
+        logger: &BuildLogger,
+    ) -> Result<(VfsPath, BTreeMap<String, String>, Vec<PackageGroup>), String> {
+        let Ingestion { root, files, dep_packages, owners, sizes } = self;
+        let provided: BTreeSet<String> = dep_packages.iter().map(|pkg| pkg.name.clone()).collect();
+        // Everything below sees only the active mode's files and groups: variants of a module
+        // for different modes may share a path or a module name without clashing.
+        let (files, dep_packages) = select_mode(&files, dep_packages, options)?;
+        let dep_packages = select_dependency_scope(dep_packages, options.test_mode || options.dev_mode)?;
+        let mut inactive = provided;
+        for pkg in &dep_packages {
+            inactive.remove(&pkg.name);
+        }
+        let selected = files.keys().map(|path| (None, path)).chain(
+            dep_packages
+                .iter()
+                .flat_map(|pkg| pkg.files.keys().map(move |path| (Some(pkg.name.as_str()), path))),
+        );
+        check_input_sizes(
+            selected.map(|(package, path)| {
+                let size = sizes.get(&(package.map(str::to_string), path.clone())).copied().unwrap_or_default();
+                (package, path.as_str(), size)
+            }),
+            options.max_file_bytes,
+            options.max_total_bytes,
+        )?;
+        check_lock_option(&dep_packages, &inactive, options, logger)?;
+        check_path_collisions(&files, &dep_packages)?;
+        check_dependency_cycles(&files, &dep_packages)?;
+
+        // The selected owners of a shared path agree on its contents (or collided above).
+        for path in owners.iter().filter(|(_, owners)| owners.len() > 1).map(|(path, _)| path) {
+            let contents = files.get(path).or_else(|| dep_packages.iter().find_map(|pkg| pkg.files.get(path)));
+            if let Some(contents) = contents {
+                write_file(&root, path, contents)?;
+            }
+        }
+        Ok((root, files, dep_packages))
+    }
+}
+
+fn write_file(root: &VfsPath, path: &str, contents: &str) -> Result<(), String> {
+    let vfs_path = root.join(path).map_err(|e| format!("Invalid path {}: {}", path, e))?;
+    ensure_vfs_parents(&vfs_path)?;
+    vfs_path
+        .create_file()
+        .and_then(|mut f| {
+            use std::io::Write;
+            write!(f, "{}", contents)?;
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to create file {}: {}", path, e))
+}
+
+fn read_file(root: &VfsPath, path: &str) -> Result<String, String> {
+    root.join(path)
+        .and_then(|vfs_path| vfs_path.read_to_string())
+        .map_err(|e| format!("Failed to read file {}: {}", path, e))
+}
+
+/// A dependency group from `info_json`: the fields of an entry of `compile()`'s
+/// dependencies array, without `name` and `files`.
+pub(crate) fn dependency_group(name: &str, info_json: Option<String>) -> Result<PackageGroup, String> {
+    let mut info = match info_json {
+        Some(json) => serde_json::from_str::<serde_json::Value>(&json)
+            .map_err(|e| format!("Failed to parse dependency '{}': {}", name, e))?,
+        None => serde_json::json!({}),
+    };
+    let Some(fields) = info.as_object_mut() else {
+        return Err(format!("Dependency '{}' info must be a JSON object", name));
+    };
+    fields.insert("name".to_string(), serde_json::Value::from(name));
+    fields.insert("files".to_string(), serde_json::json!({}));
+    serde_json::from_value(info).map_err(|e| format!("Failed to parse dependency '{}': {}", name, e))
+}
@@ -0,0 +1,257 @@
+// Public API summaries of compiled root modules (`emitInterface`).
+//
+// For each module: every struct (abilities, type parameters, fields), every enum (abilities,
+// type parameters, variants with their fields) and every function a
+// transaction or another package can call (`public`, `public(package)` and `entry`). Types
+// are printed as in Move source, e.g. `vector<sui::coin::Coin<T0>>`. Addresses print as the
+// root's named address when one is bound to them, otherwise as the short hex literal.
+// Type parameters are named `T0`, `T1`, ... since bytecode keeps no parameter names.
+//
+// ORIGINAL SOURCE: sui-json-rpc-types/src/sui_move.rs - SuiMoveNormalizedModule (same selection)
+
+use move_binary_format::{
+    file_format::{Ability, AbilitySet, DatatypeHandleIndex, FieldDefinition, SignatureToken, Visibility},
+    CompiledModule,
+};
+use move_core_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub(crate) struct ModuleInterface {
+    pub(crate) address: String,
+    pub(crate) name: String,
+    pub(crate) structs: Vec<StructInterface>,
+    pub(crate) enums: Vec<EnumInterface>,
+    pub(crate) functions: Vec<FunctionInterface>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct StructInterface {
+    pub(crate) name: String,
+    pub(crate) abilities: Vec<&'static str>,
+    #[serde(rename = "typeParameters")]
+    pub(crate) type_parameters: Vec<TypeParameterInterface>,
+    pub(crate) fields: Vec<FieldInterface>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct EnumInterface {
+    pub(crate) name: String,
+    pub(crate) abilities: Vec<&'static str>,
+    #[serde(rename = "typeParameters")]
+    pub(crate) type_parameters: Vec<TypeParameterInterface>,
+    /// Variants in declaration order (their tag order).
+    pub(crate) variants: Vec<VariantInterface>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct VariantInterface {
+    name: String,
+    fields: Vec<FieldInterface>,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct TypeParameterInterface {
+    name: String,
+    constraints: Vec<&'static str>,
+    #[serde(rename = "isPhantom")]
+    is_phantom: bool,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct FieldInterface {
+    name: String,
+    #[serde(rename = "type")]
+    type_: String,
+}
+
+#[derive(Serialize, Clone, PartialEq)]
+pub(crate) struct FunctionInterface {
+    pub(crate) name: String,
+    /// "public", "package" (`public(package)`) or "private" (private `entry` functions, or
+    /// any private function in documentation).
+    pub(crate) visibility: &'static str,
+    #[serde(rename = "isEntry")]
+    pub(crate) is_entry: bool,
+    #[serde(rename = "typeParameters")]
+    pub(crate) type_parameters: Vec<TypeParameterInterface>,
+    pub(crate) parameters: Vec<String>,
+    #[serde(rename = "return")]
+    pub(crate) return_: Vec<String>,
+}
+
+pub(crate) fn abilities(set: AbilitySet) -> Vec<&'static str> {
+    set.into_iter()
+        .map(|ability| match ability {
+            Ability::Copy => "copy",
+            Ability::Drop => "drop",
+            Ability::Store => "store",
+            Ability::Key => "key",
+        })
+        .collect()
+}
+
+/// Type printer for one module, resolving addresses through `address_names`.
+pub(crate) struct TypePrinter<'a> {
+    module: &'a CompiledModule,
+    address_names: &'a BTreeMap<AccountAddress, String>,
+}
+
+impl<'a> TypePrinter<'a> {
+    pub(crate) fn new(module: &'a CompiledModule, address_names: &'a BTreeMap<AccountAddress, String>) -> Self {
+        TypePrinter { module, address_names }
+    }
+
+    fn address(&self, address: &AccountAddress) -> String {
+        match self.address_names.get(address) {
+            Some(name) => name.clone(),
+            None => address.to_hex_literal(),
+        }
+    }
+
+    fn datatype(&self, idx: DatatypeHandleIndex) -> String {
+        let handle = self.module.datatype_handle_at(idx);
+        let module = self.module.module_handle_at(handle.module);
+        format!(
+            "{}::{}::{}",
+            self.address(self.module.address_identifier_at(module.address)),
+            self.module.identifier_at(module.name),
+            self.module.identifier_at(handle.name)
+        )
+    }
+
+    pub(crate) fn print(&self, token: &SignatureToken) -> String {
+        match token {
+            SignatureToken::Bool => "bool".to_string(),
+            SignatureToken::U8 => "u8".to_string(),
+            SignatureToken::U16 => "u16".to_string(),
+            SignatureToken::U32 => "u32".to_string(),
+            SignatureToken::U64 => "u64".to_string(),
+            SignatureToken::U128 => "u128".to_string(),
+            SignatureToken::U256 => "u256".to_string(),
+            SignatureToken::Address => "address".to_string(),
+            SignatureToken::Signer => "signer".to_string(),
+            SignatureToken::Vector(inner) => format!("vector<{}>", self.print(inner)),
+            SignatureToken::Datatype(idx) => self.datatype(*idx),
+            SignatureToken::DatatypeInstantiation(inst) => {
+                let (idx, args) = &**inst;
+                let args: Vec<String> = args.iter().map(|arg| self.print(arg)).collect();
+                format!("{}<{}>", self.datatype(*idx), args.join(", "))
+            }
+            SignatureToken::Reference(inner) => format!("&{}", self.print(inner)),
+            SignatureToken::MutableReference(inner) => format!("&mut {}", self.print(inner)),
+            SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+        }
+    }
+}
+
+/// Summarize `module`. `address_names` maps addresses to the named address printed for them.
+/// With `include_private`, private non-entry functions are listed as well.
+pub(crate) fn module_interface(
+    module: &CompiledModule,
+    address_names: &BTreeMap<AccountAddress, String>,
+    include_private: bool,
+) -> ModuleInterface {
+    let printer = TypePrinter { module, address_names };
+    let id = module.self_id();
+
+    let datatype_type_parameters = |idx: DatatypeHandleIndex| -> Vec<TypeParameterInterface> {
+        module
+            .datatype_handle_at(idx)
+            .type_parameters
+            .iter()
+            .enumerate()
+            .map(|(idx, param)| TypeParameterInterface {
+                name: format!("T{}", idx),
+                constraints: abilities(param.constraints),
+                is_phantom: param.is_phantom,
+            })
+            .collect()
+    };
+    let fields = |fields: &[FieldDefinition]| -> Vec<FieldInterface> {
+        fields
+            .iter()
+            .map(|field| FieldInterface {
+                name: module.identifier_at(field.name).to_string(),
+                type_: printer.print(&field.signature.0),
+            })
+            .collect()
+    };
+
+    let structs = module
+        .struct_defs()
+        .iter()
+        .map(|def| {
+            let handle = module.datatype_handle_at(def.struct_handle);
+            StructInterface {
+                name: module.identifier_at(handle.name).to_string(),
+                abilities: abilities(handle.abilities),
+                type_parameters: datatype_type_parameters(def.struct_handle),
+                fields: def.fields().map(fields).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    let enums = module
+        .enum_defs()
+        .iter()
+        .map(|def| {
+            let handle = module.datatype_handle_at(def.enum_handle);
+            EnumInterface {
+                name: module.identifier_at(handle.name).to_string(),
+                abilities: abilities(handle.abilities),
+                type_parameters: datatype_type_parameters(def.enum_handle),
+                variants: def
+                    .variants
+                    .iter()
+                    .map(|variant| VariantInterface {
+                        name: module.identifier_at(variant.variant_name).to_string(),
+                        fields: fields(&variant.fields),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let functions = module
+        .function_defs()
+        .iter()
+        .filter(|def| include_private || def.visibility != Visibility::Private || def.is_entry)
+        .map(|def| {
+            let handle = module.function_handle_at(def.function);
+            let signature = |idx| -> Vec<String> {
+                module.signature_at(idx).0.iter().map(|token| printer.print(token)).collect()
+            };
+            FunctionInterface {
+                name: module.identifier_at(handle.name).to_string(),
+                visibility: match def.visibility {
+                    Visibility::Public => "public",
+                    Visibility::Friend => "package",
+                    Visibility::Private => "private",
+                },
+                is_entry: def.is_entry,
+                type_parameters: handle
+                    .type_parameters
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, constraints)| TypeParameterInterface {
+                        name: format!("T{}", idx),
+                        constraints: abilities(*constraints),
+                        is_phantom: false,
+                    })
+                    .collect(),
+                parameters: signature(handle.parameters),
+                return_: signature(handle.return_),
+            }
+        })
+        .collect();
+
+    ModuleInterface {
+        address: printer.address(id.address()),
+        name: id.name().to_string(),
+        structs,
+        enums,
+        functions,
+    }
+}
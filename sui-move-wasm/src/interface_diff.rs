@@ -0,0 +1,207 @@
+// Public API diff between two builds of a package (`diff_interfaces`).
+//
+// Modules are matched by name, and the new modules are moved to the old modules' address
+// first, so a package rebuilt at 0x0 compares cleanly against its published version. Only
+// the surface other packages and transactions see is listed: public and entry functions,
+// and every struct (fields are part of the layout even when private).
+//
+// The verdicts come from the checkers validators run on an upgrade, module by module, and
+// every policy rejects a removed module:
+// - `compatible`: Compatibility::upgrade_check
+// - `additive`: InclusionCheck::Subset
+// - `dep_only`: InclusionCheck::Equal
+//
+// ORIGINAL SOURCE: sui-adapter/src/programmable_transactions/execution.rs - check_compatibility
+
+use crate::interface::{module_interface, FunctionInterface, StructInterface};
+use move_binary_format::compatibility::{Compatibility, InclusionCheck};
+use move_binary_format::normalized::{self, RcPool};
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub(crate) struct InterfaceDiff {
+    #[serde(rename = "compatibleUnderCompatiblePolicy")]
+    compatible: bool,
+    #[serde(rename = "compatibleUnderAdditivePolicy")]
+    additive: bool,
+    #[serde(rename = "compatibleUnderDependencyOnlyPolicy")]
+    dep_only: bool,
+    /// Why the new modules are not a compatible upgrade, one entry per failing module.
+    incompatibilities: Vec<String>,
+    /// Modules whose interface changed, by name.
+    modules: BTreeMap<String, ModuleDiff>,
+}
+
+#[derive(Serialize, Default)]
+struct ModuleDiff {
+    /// "added", "removed" or "changed".
+    status: &'static str,
+    #[serde(rename = "addedFunctions", skip_serializing_if = "Vec::is_empty")]
+    added_functions: Vec<FunctionInterface>,
+    #[serde(rename = "removedFunctions", skip_serializing_if = "Vec::is_empty")]
+    removed_functions: Vec<FunctionInterface>,
+    #[serde(rename = "changedFunctions", skip_serializing_if = "Vec::is_empty")]
+    changed_functions: Vec<Changed<FunctionInterface>>,
+    #[serde(rename = "addedStructs", skip_serializing_if = "Vec::is_empty")]
+    added_structs: Vec<StructInterface>,
+    #[serde(rename = "removedStructs", skip_serializing_if = "Vec::is_empty")]
+    removed_structs: Vec<StructInterface>,
+    #[serde(rename = "changedStructs", skip_serializing_if = "Vec::is_empty")]
+    changed_structs: Vec<Changed<StructInterface>>,
+}
+
+#[derive(Serialize)]
+struct Changed<T> {
+    name: String,
+    /// Which parts differ, e.g. "parameters", "abilities" or "fields".
+    changes: Vec<&'static str>,
+    old: T,
+    new: T,
+}
+
+fn function_changes(old: &FunctionInterface, new: &FunctionInterface) -> Vec<&'static str> {
+    let parts = [
+        ("visibility", old.visibility == new.visibility),
+        ("isEntry", old.is_entry == new.is_entry),
+        ("typeParameters", old.type_parameters == new.type_parameters),
+        ("parameters", old.parameters == new.parameters),
+        ("return", old.return_ == new.return_),
+    ];
+    parts.iter().filter(|(_, same)| !same).map(|(part, _)| *part).collect()
+}
+
+fn struct_changes(old: &StructInterface, new: &StructInterface) -> Vec<&'static str> {
+    let parts = [
+        ("abilities", old.abilities == new.abilities),
+        ("typeParameters", old.type_parameters == new.type_parameters),
+        ("fields", old.fields == new.fields),
+    ];
+    parts.iter().filter(|(_, same)| !same).map(|(part, _)| *part).collect()
+}
+
+fn is_exposed(function: &FunctionInterface) -> bool {
+    function.visibility == "public" || function.is_entry
+}
+
+/// Diff the public surface of `old` and `new`, which are matched by module name.
+pub(crate) fn diff_interfaces(old: Vec<CompiledModule>, new: Vec<CompiledModule>) -> InterfaceDiff {
+    let no_names = BTreeMap::new();
+    let mut pool = RcPool::new();
+    let mut additive = true;
+    let mut dep_only = true;
+    let old_address: Option<AccountAddress> = old.first().map(|module| *module.address());
+    let by_name = |modules: Vec<CompiledModule>| -> BTreeMap<String, CompiledModule> {
+        modules.into_iter().map(|module| (module.self_id().name().to_string(), module)).collect()
+    };
+    let mut old = by_name(old);
+    let new = by_name(new);
+
+    let mut incompatibilities = Vec::new();
+    let mut modules = BTreeMap::new();
+    for (name, mut new_module) in new {
+        let exposed_interface = |module: &CompiledModule| {
+            let mut interface = module_interface(module, &no_names, false);
+            interface.functions.retain(is_exposed);
+            interface
+        };
+        let Some(old_module) = old.remove(&name) else {
+            let interface = exposed_interface(&new_module);
+            modules.insert(
+                name,
+                ModuleDiff {
+                    status: "added",
+                    added_functions: interface.functions,
+                    added_structs: interface.structs,
+                    ..ModuleDiff::default()
+                },
+            );
+            continue;
+        };
+        if let Some(address) = old_address {
+            crate::substitute_self_address(&mut new_module, address);
+        }
+        let old_normalized = normalized::Module::new(&mut pool, &old_module, true);
+        let new_normalized = normalized::Module::new(&mut pool, &new_module, true);
+        if let Err(err) = Compatibility::upgrade_check().check(&old_normalized, &new_normalized) {
+            incompatibilities.push(format!("{}: {}", name, err));
+        }
+        additive &= InclusionCheck::Subset.check(&old_normalized, &new_normalized).is_ok();
+        dep_only &= InclusionCheck::Equal.check(&old_normalized, &new_normalized).is_ok();
+
+        let old_interface = exposed_interface(&old_module);
+        let new_interface = exposed_interface(&new_module);
+
+        let mut diff = ModuleDiff { status: "changed", ..ModuleDiff::default() };
+        let mut new_functions: BTreeMap<String, FunctionInterface> =
+            new_interface.functions.into_iter().map(|f| (f.name.clone(), f)).collect();
+        for old_function in old_interface.functions {
+            match new_functions.remove(&old_function.name) {
+                None => diff.removed_functions.push(old_function),
+                Some(new_function) => {
+                    let changes = function_changes(&old_function, &new_function);
+                    if changes.is_empty() {
+                        continue;
+                    }
+                    diff.changed_functions.push(Changed {
+                        name: old_function.name.clone(),
+                        changes,
+                        old: old_function,
+                        new: new_function,
+                    });
+                }
+            }
+        }
+        diff.added_functions.extend(new_functions.into_values());
+
+        let mut new_structs: BTreeMap<String, StructInterface> =
+            new_interface.structs.into_iter().map(|s| (s.name.clone(), s)).collect();
+        for old_struct in old_interface.structs {
+            match new_structs.remove(&old_struct.name) {
+                None => diff.removed_structs.push(old_struct),
+                Some(new_struct) => {
+                    let changes = struct_changes(&old_struct, &new_struct);
+                    if changes.is_empty() {
+                        continue;
+                    }
+                    diff.changed_structs.push(Changed {
+                        name: old_struct.name.clone(),
+                        changes,
+                        old: old_struct,
+                        new: new_struct,
+                    });
+                }
+            }
+        }
+        diff.added_structs.extend(new_structs.into_values());
+
+        let unchanged = diff.added_functions.is_empty()
+            && diff.removed_functions.is_empty()
+            && diff.changed_functions.is_empty()
+            && diff.added_structs.is_empty()
+            && diff.removed_structs.is_empty()
+            && diff.changed_structs.is_empty();
+        if !unchanged {
+            modules.insert(name, diff);
+        }
+    }
+    for (name, old_module) in old {
+        incompatibilities.push(format!("{}: module removed", name));
+        additive = false;
+        dep_only = false;
+        let interface = module_interface(&old_module, &no_names, false);
+        modules.insert(
+            name,
+            ModuleDiff {
+                status: "removed",
+                removed_functions: interface.functions.into_iter().filter(is_exposed).collect(),
+                removed_structs: interface.structs,
+                ..ModuleDiff::default()
+            },
+        );
+    }
+
+    InterfaceDiff { compatible: incompatibilities.is_empty(), additive, dep_only, incompatibilities, modules }
+}
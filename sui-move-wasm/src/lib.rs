@@ -3,42 +3,55 @@ use blake2::digest::Update;
 use blake2::Blake2bVar;
 use sha2::{Sha256, Digest};
 use move_bytecode_utils::Modules;
-use move_compiler::{Compiler, Flags, editions::{Flavor, Edition}, shared::{NumericalAddress, PackageConfig, PackagePaths}, diagnostics::report_diagnostics_to_buffer};
+use move_compiler::{Compiler, Flags, editions::{Flavor, Edition}, shared::{NumberFormat, NumericalAddress, PackageConfig, PackagePaths}, diagnostics::report_diagnostics_to_buffer};
 use move_core_types::{account_address::AccountAddress, language_storage::ModuleId};
 use move_symbol_pool::Symbol;
 #[cfg(feature = "testing")]
 use move_unit_test::{UnitTestingConfig, extensions::set_extension_hook};
 #[cfg(feature = "testing")]
 use move_vm_runtime::native_extensions::NativeContextExtensions;
+#[cfg(feature = "testing")]
+use move_command_line_common::files::FileHash;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::rc::Rc;
 use std::sync::Arc;
 use sui_protocol_config::ProtocolConfig;
 use sui_types::{
     base_types::{SuiAddress, TxContext},
     digests::TransactionDigest,
-    gas_model::tables::initial_cost_schedule_for_unit_tests,
+    gas_model::tables::{cost_table_for_version, initial_cost_schedule_for_unit_tests},
     in_memory_storage::InMemoryStorage,
     metrics::LimitsMetrics,
 };
-use vfs::{impls::memory::MemoryFS, VfsPath};
+use vfs::VfsPath;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
-use move_compiler::compiled_unit::AnnotatedCompiledModule;
+use move_compiler::compiled_unit::{AnnotatedCompiledModule, NamedCompiledModule};
+use move_disassembler::disassembler::Disassembler;
+use move_ir_types::location::Spanned;
+use move_compiler::{diagnostics::{codes::Severity, Diagnostics}, shared::files::MappedFiles, SteppedCompiler};
+use move_compiler::{construct_pre_compiled_lib, FullyCompiledProgram};
+use move_binary_format::CompiledModule;
+use move_binary_format::file_format_common::{VERSION_MAX, VERSION_MIN};
+use move_binary_format::file_format::{Bytecode, CodeOffset, FunctionDefinitionIndex, SignatureIndex, SignatureToken};
 use sui_types::{
     move_package::{FnInfo, FnInfoKey, FnInfoMap},
     error::SuiError,
 };
 use sui_protocol_config::{Chain, ProtocolVersion};
 use sui_verifier::verifier as sui_bytecode_verifier;
+use sui_verifier::meter::SuiVerifierMeter;
+use move_bytecode_verifier_meter::Scope;
+use move_core_types::vm_status::StatusCode;
+use move_vm_config::verifier::MeterConfig;
 
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-
     #[wasm_bindgen(js_namespace = console)]
     fn error(s: &str);
 
@@ -46,23 +59,204 @@ extern "C" {
     fn warn(s: &str);
 }
 
-#[wasm_bindgen]
+// Native builds (see `api`) have no console; fall back to stderr.
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn error(s: &str) {
+    eprintln!("error: {}", s);
+}
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+fn warn(s: &str) {
+    eprintln!("warning: {}", s);
+}
+
+/// JS function receiving build log events; native builds have none.
+#[cfg(feature = "wasm")]
+type LogCallback = js_sys::Function;
+#[cfg(not(feature = "wasm"))]
+type LogCallback = std::convert::Infallible;
+
+/// Routes build log events to an optional JS callback.
+///
+/// The callback receives `{ level, phase, message }` objects. Without a callback,
+/// warnings and errors fall back to the console as before and info events are dropped.
+struct BuildLogger {
+    callback: Option<LogCallback>,
+}
+
+impl BuildLogger {
+    fn new(callback: Option<LogCallback>) -> Self {
+        Self { callback }
+    }
+
+    fn emit(&self, level: &str, phase: &str, message: &str) {
+        match &self.callback {
+            #[cfg(not(feature = "wasm"))]
+            Some(never) => match *never {},
+            #[cfg(feature = "wasm")]
+            Some(callback) => {
+                let event = js_sys::Object::new();
+                let _ = js_sys::Reflect::set(&event, &"level".into(), &level.into());
+                let _ = js_sys::Reflect::set(&event, &"phase".into(), &phase.into());
+                let _ = js_sys::Reflect::set(&event, &"message".into(), &message.into());
+                // A throwing callback must not abort the build.
+                let _ = callback.call1(&JsValue::NULL, &event);
+            }
+            None => match level {
+                "warn" => warn(message),
+                "error" => error(message),
+                _ => {}
+            },
+        }
+    }
+
+    fn info(&self, phase: &str, message: &str) {
+        self.emit("info", phase, message);
+    }
+
+    fn warn(&self, phase: &str, message: &str) {
+        self.emit("warn", phase, message);
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone)]
 pub struct MoveCompilerResult {
     success: bool,
     output: String, // JSON string of compiled units or errors
+    counts: DiagnosticCounts,
+    errors_by_file: Option<BTreeMap<String, String>>,
+    edition_hints: Vec<String>,
 }
 
-#[wasm_bindgen]
 impl MoveCompilerResult {
-    #[wasm_bindgen(getter)]
+    /// A successful result with no compiler diagnostics behind it.
+    fn ok(output: String) -> Self {
+        MoveCompilerResult {
+            success: true,
+            output,
+            counts: DiagnosticCounts::default(),
+            errors_by_file: None,
+            edition_hints: Vec::new(),
+        }
+    }
+
+    /// A failed result, with the counts of the diagnostics in its report.
+    fn err(failure: impl Into<BuildFailure>) -> Self {
+        let BuildFailure { message, counts, errors_by_file, edition_hints, .. } = failure.into();
+        MoveCompilerResult { success: false, output: message, counts, errors_by_file, edition_hints }
+    }
+
+    /// The result of compiling a package: its output as JSON, or the failure.
+    fn compiled(result: Result<CompilationOutput, BuildFailure>) -> Self {
+        match result {
+            Ok(output) => MoveCompilerResult {
+                counts: output.diagnostics,
+                ..MoveCompilerResult::ok(serde_json::to_string(&output).unwrap_or_default())
+            },
+            Err(failure) => MoveCompilerResult::err(failure),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl MoveCompilerResult {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn success(&self) -> bool {
         self.success
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn output(&self) -> String {
         self.output.clone()
     }
+
+    /// Warnings shown in the output (the `warnings` text, or the failure report).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = warningCount))]
+    pub fn warning_count(&self) -> u32 {
+        self.counts.warnings
+    }
+
+    /// Errors in the failure report; 0 on success.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = errorCount))]
+    pub fn error_count(&self) -> u32 {
+        self.counts.errors
+    }
+
+    /// Warnings hidden by `silenceWarnings` or `suppressWarnings`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = suppressedWarningCount))]
+    pub fn suppressed_warning_count(&self) -> u32 {
+        self.counts.suppressed_warnings
+    }
+
+    /// With `perModuleErrors` and compiler errors: JSON object of file key -> that file's
+    /// rendered diagnostics ("" for diagnostics without a file).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = errorsByFile))]
+    pub fn errors_by_file(&self) -> Option<String> {
+        self.errors_by_file.as_ref().map(|by_file| serde_json::to_string(by_file).unwrap_or_default())
+    }
+
+    /// On parse errors in dependency files: which edition each such dependency was parsed
+    /// with and how to override it. Also appended to the report.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = editionHints))]
+    pub fn edition_hints(&self) -> Vec<String> {
+        self.edition_hints.clone()
+    }
+}
+
+/// Compiler diagnostics behind a result, counted as its text shows them. Notes count as
+/// neither warnings nor errors.
+#[derive(Default, Clone, Copy)]
+struct DiagnosticCounts {
+    errors: u32,
+    warnings: u32,
+    suppressed_warnings: u32,
+}
+
+impl DiagnosticCounts {
+    fn of(diags: &[move_compiler::diagnostics::Diagnostic]) -> Self {
+        let mut counts = DiagnosticCounts::default();
+        for diag in diags {
+            match diag.info().severity() {
+                Severity::Note => {}
+                Severity::Warning => counts.warnings += 1,
+                _ => counts.errors += 1,
+            }
+        }
+        counts
+    }
+
+    fn add(&mut self, other: DiagnosticCounts) {
+        self.errors += other.errors;
+        self.warnings += other.warnings;
+        self.suppressed_warnings += other.suppressed_warnings;
+    }
+}
+
+/// Why a build failed: the report shown to the user, with the counts of the compiler
+/// diagnostics it renders. Failures outside the compiler (manifest, limits, verification)
+/// are plain messages with zero counts.
+struct BuildFailure {
+    message: String,
+    counts: DiagnosticCounts,
+    /// Under `perModuleErrors`: the compiler's diagnostics by file key.
+    errors_by_file: Option<BTreeMap<String, String>>,
+    /// Edition notes for dependencies with parse errors, also appended to `message`.
+    edition_hints: Vec<String>,
+    /// Named addresses the compiler reported as unbound.
+    unbound_addresses: BTreeSet<String>,
+}
+
+impl From<String> for BuildFailure {
+    fn from(message: String) -> Self {
+        BuildFailure {
+            message,
+            counts: DiagnosticCounts::default(),
+            errors_by_file: None,
+            edition_hints: Vec::new(),
+            unbound_addresses: BTreeSet::new(),
+        }
+    }
 }
 
 /// Compilation output containing bytecode, dependencies, and lockfile.
@@ -77,14 +271,220 @@ impl MoveCompilerResult {
 /// - Package IDs with suffix for diamond dependencies (MoveStdlib, MoveStdlib_1, etc.)
 #[derive(Serialize)]
 pub struct CompilationOutput {
-    modules: Vec<String>, // Base64 encoded bytecode
+    modules: EncodedModules, // Base64 encoded bytecode (raw bytes from `compile_bytes`)
+    /// Module names, index-aligned with `modules`.
+    #[serde(rename = "moduleNames")]
+    module_names: Vec<String>,
+    /// Serialized module sizes in bytes, index-aligned with `modules`.
+    #[serde(rename = "moduleSizes")]
+    module_sizes: Vec<usize>,
+    /// Binary format version each module was serialized at, index-aligned with `modules`.
+    #[serde(rename = "moduleVersions")]
+    module_versions: Vec<u32>,
+    /// Number of modules in `modules`.
+    #[serde(rename = "moduleCount")]
+    module_count: usize,
+    /// Sum of `moduleSizes`: raw bytecode bytes, before base64 encoding.
+    #[serde(rename = "totalBytecodeBytes")]
+    total_bytecode_bytes: usize,
+    /// With `emitInterface`: the public API of each module, index-aligned with `modules`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interface: Option<Vec<interface::ModuleInterface>>,
+    /// With `emitEntryPoints`: public and entry functions of the root modules, with
+    /// each parameter classified for transaction building.
+    #[serde(rename = "entryPoints", skip_serializing_if = "Option::is_none")]
+    entry_points: Option<Vec<entry_points::EntryPoint>>,
+    /// With `emitHints`: unused `use`s and constants and unreachable private functions,
+    /// with file spans for one-click fixes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hints: Option<Vec<hints::Hint>>,
+    /// With `emitConstants`: root module name -> its constants with decoded values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constants: Option<BTreeMap<String, Vec<constants::ConstantInfo>>>,
+    /// With `emitDebugBundle`: per root module, its source map and the source text it was
+    /// compiled from, for the Move trace debugger.
+    #[serde(rename = "debugBundle", skip_serializing_if = "Option::is_none")]
+    debug_bundle: Option<Vec<debug_bundle::DebugBundle>>,
+    /// Published dependencies the root uses that have no output address (and so are missing
+    /// from `dependencies`). Fatal under `strictTreeShaking`.
+    #[serde(rename = "treeShakeWarnings", skip_serializing_if = "Vec::is_empty")]
+    tree_shake_warnings: Vec<String>,
+    /// With `withFriends`: root module name -> its declared friends (canonical module IDs).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    friends: Option<BTreeMap<String, Vec<String>>>,
     dependencies: Vec<String>, // Hex encoded dependency IDs
     digest: Vec<u8>, // Blake2b-256 package digest
+    /// Compilation (original) address -> output (published) address, canonical form, for
+    /// every dependency with an address, plus the root when it has a `published-at`.
+    #[serde(rename = "addressMapping")]
+    address_mapping: BTreeMap<String, String>,
+    /// With `estimatePublishCost`: estimated storage and computation cost of publishing.
+    #[serde(rename = "costEstimate", skip_serializing_if = "Option::is_none")]
+    cost_estimate: Option<CostEstimate>,
     /// V4 Move.lock content generated during compilation.
     /// ORIGINAL: move-package-alt/src/package/root_package.rs:251 - save_lockfile_to_disk()
     lockfile: String,
+    /// Rendered compiler warnings, followed by the builder's own (e.g. files outside the
+    /// package layout, `packageLimits: "warn"` violations), unless `silenceWarnings`.
     #[serde(skip_serializing_if = "Option::is_none")]
     warnings: Option<String>,
+    /// Declared dependency IDs that tree-shaking found unreferenced by the root package.
+    /// Informational only: they stay in `dependencies` because the CLI digest covers the
+    /// full linkage table.
+    #[serde(rename = "prunedDependencies")]
+    pruned_dependencies: Vec<String>,
+    /// Packages declared in the root `[dependencies]` none of whose modules the root package
+    /// reaches. Transitive dependencies and implicit framework packages are never listed.
+    #[serde(rename = "unusedDependencies")]
+    unused_dependencies: Vec<String>,
+    /// In test mode: dependency packages reached only from test code.
+    #[serde(rename = "testOnlyDependencies", skip_serializing_if = "Vec::is_empty")]
+    test_only_dependencies: Vec<String>,
+    /// Adjustments made on the user's behalf, e.g. an implicit `std`/`sui` address, and the
+    /// compiler's note-level diagnostics (e.g. deprecation guidance) unless `silenceNotes`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+    #[serde(rename = "buildInfo")]
+    build_info: BuildInfo,
+    /// Counts of the compiler warnings in `warnings` and of those hidden.
+    #[serde(skip)]
+    diagnostics: DiagnosticCounts,
+}
+
+impl CompilationOutput {
+    /// Module bytecode, in dependency order.
+    pub fn modules(&self) -> &[Vec<u8>] {
+        self.modules.bytes()
+    }
+
+    /// Module names, index-aligned with `modules`.
+    pub fn module_names(&self) -> &[String] {
+        &self.module_names
+    }
+
+    /// IDs of the packages the modules link against, hex encoded.
+    pub fn dependencies(&self) -> &[String] {
+        &self.dependencies
+    }
+
+    /// Blake2b-256 package digest.
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+
+    /// Generated V4 Move.lock content.
+    pub fn lockfile(&self) -> &str {
+        &self.lockfile
+    }
+
+    /// Rendered compiler and builder warnings, if any.
+    pub fn warnings(&self) -> Option<&str> {
+        self.warnings.as_deref()
+    }
+}
+
+/// Build metadata for publication and source-verification tooling.
+///
+/// ORIGINAL SOURCE REFERENCES:
+/// - move-package/src/compilation/compiled_package.rs - CompiledPackageInfo
+#[derive(Serialize)]
+struct BuildInfo {
+    #[serde(rename = "packageName")]
+    package_name: String,
+    edition: String,
+    flavor: String,
+    /// Named addresses the root package was compiled with (name -> canonical address).
+    #[serde(rename = "addressAliasInstantiation")]
+    address_alias_instantiation: BTreeMap<String, String>,
+    /// Root manifest `[addresses]` names in declaration order (`preserveAddressOrder`).
+    #[serde(rename = "declaredAddressOrder", skip_serializing_if = "Option::is_none")]
+    declared_address_order: Option<Vec<String>>,
+    #[serde(rename = "buildFlags")]
+    build_flags: BuildFlagsInfo,
+    #[serde(rename = "suiVersion")]
+    sui_version: String,
+    #[serde(rename = "suiMoveVersion")]
+    sui_move_version: String,
+}
+
+#[derive(Serialize)]
+struct BuildFlagsInfo {
+    #[serde(rename = "testMode")]
+    test_mode: bool,
+    #[serde(rename = "devMode")]
+    dev_mode: bool,
+    #[serde(rename = "silenceWarnings")]
+    silence_warnings: bool,
+}
+
+impl BuildInfo {
+    fn new(
+        package_name: &str,
+        root: &PackagePaths<Symbol, String>,
+        declared_address_order: Option<Vec<String>>,
+        options: &CompileOptions,
+    ) -> Self {
+        let (edition, flavor) = match &root.name {
+            Some((_, config)) => (config.edition.to_string(), config.flavor.to_string()),
+            None => (Edition::LEGACY.to_string(), Flavor::Sui.to_string()),
+        };
+        BuildInfo {
+            package_name: package_name.to_string(),
+            edition,
+            flavor,
+            address_alias_instantiation: root
+                .named_address_map
+                .iter()
+                .map(|(name, addr)| (name.clone(), addr.into_inner().to_canonical_string(true)))
+                .collect(),
+            declared_address_order,
+            build_flags: BuildFlagsInfo {
+                test_mode: options.test_mode,
+                dev_mode: options.dev_mode,
+                silence_warnings: options.silence_warnings,
+            },
+            sui_version: sui_version(),
+            sui_move_version: sui_move_version(),
+        }
+    }
+}
+
+/// Serialized module bytecode, in the encoding of the API that produced it.
+enum EncodedModules {
+    /// Base64 strings, for the JSON string API.
+    Base64(Vec<Vec<u8>>),
+    /// Raw bytes, surfaced to JS as `Uint8Array`s by `compile_bytes`.
+    #[cfg_attr(not(feature = "wasm"), allow(dead_code))]
+    Raw(Vec<Vec<u8>>),
+}
+
+impl EncodedModules {
+    fn bytes(&self) -> &[Vec<u8>] {
+        match self {
+            EncodedModules::Base64(bytes) | EncodedModules::Raw(bytes) => bytes,
+        }
+    }
+
+    #[cfg(feature = "wasm")]
+    fn into_raw(self) -> Self {
+        match self {
+            EncodedModules::Base64(bytes) | EncodedModules::Raw(bytes) => EncodedModules::Raw(bytes),
+        }
+    }
+}
+
+impl Serialize for EncodedModules {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeSeq;
+        let mut seq = serializer.serialize_seq(Some(self.bytes().len()))?;
+        for bytes in self.bytes() {
+            match self {
+                EncodedModules::Base64(_) => seq.serialize_element(&general_purpose::STANDARD.encode(bytes))?,
+                EncodedModules::Raw(_) => seq.serialize_element(serde_bytes::Bytes::new(bytes))?,
+            }
+        }
+        seq.end()
+    }
 }
 
 // [REMOVED] Manual MoveToml structs definition
@@ -93,47 +493,245 @@ pub struct CompilationOutput {
 mod manifest;
 use manifest::SourceManifest;
 
+mod session;
+pub use session::CompilerSession;
+
+mod builder;
+pub use builder::PackageBuilder;
+
+mod ingest;
+use ingest::Ingestion;
+
+mod format;
+pub use format::format_source;
+
+mod stubs;
+
+mod interface;
+
+mod interface_diff;
+
+mod docs;
+
+mod entry_points;
+
+mod hints;
+
+mod constants;
+
+mod debug_bundle;
+
+pub mod api;
+
 // Removed MoveToml and MoveTomlPackage structs
 
 
 // New structure for package-grouped dependencies
 #[derive(Deserialize)]
+#[serde(try_from = "PackageGroupInput")]
 struct PackageGroup {
     name: String,
     files: BTreeMap<String, String>,
+    edition: Option<String>,
+    /// "sui" or "core"; overrides the dependency manifest's flavor.
+    flavor: Option<String>,
+    address_mapping: Option<BTreeMap<String, String>>,
+    published_id_for_output: Option<String>,
+    /// Whether the package comes from `[dependencies]` or `[dev-dependencies]`.
+    scope: DependencyScope,
+    /// Move.toml dependency alias -> resolved package name.
+    dep_alias_to_package_name: BTreeMap<String, String>,
+    /// Build modes the package belongs to; empty means every mode.
+    modes: Vec<String>,
+    /// Where the files were fetched from, checked against `moveLock` pins.
+    source: Option<PackageSource>,
+}
+
+/// Origin of a dependency group's files, as the JS resolver records it for Move.lock.
+#[derive(Deserialize, Default, Clone)]
+struct PackageSource {
+    #[serde(default)]
+    git: Option<String>,
+    #[serde(default)]
+    rev: Option<String>,
+    #[serde(default)]
+    subdir: Option<String>,
+}
+
+/// Wire format of a dependency group. Differs from `PackageGroup` only in accepting
+/// `normalizedModules`, which are turned into interface stub files on deserialization.
+#[derive(Deserialize)]
+struct PackageGroupInput {
+    name: String,
+    #[serde(default)]
+    files: BTreeMap<String, String>,
     #[serde(default)]
     edition: Option<String>,
+    #[serde(default)]
+    flavor: Option<String>,
     #[serde(default, rename = "addressMapping")]
     address_mapping: Option<BTreeMap<String, String>>,
     #[serde(default, rename = "publishedIdForOutput")]
     published_id_for_output: Option<String>,
+    #[serde(default)]
+    scope: DependencyScope,
+    #[serde(default, rename = "depAliasToPackageName")]
+    dep_alias_to_package_name: BTreeMap<String, String>,
+    #[serde(default)]
+    modes: Vec<String>,
+    /// On-chain modules as returned by `sui_getNormalizedMoveModulesByPackage`.
+    #[serde(default, rename = "normalizedModules")]
+    normalized_modules: BTreeMap<String, stubs::NormalizedModule>,
+    #[serde(default)]
+    source: Option<PackageSource>,
 }
 
-
-
-fn package_version_from_lock(lock_contents: &str, package_name: &str) -> Option<String> {
-    let mut in_pkg = false;
-    for line in lock_contents.lines() {
-        let trimmed = line.trim();
-        if trimmed == "[[package]]" {
-            in_pkg = false;
-            continue;
+impl TryFrom<PackageGroupInput> for PackageGroup {
+    type Error = String;
+
+    fn try_from(input: PackageGroupInput) -> Result<Self, String> {
+        let PackageGroupInput {
+            name,
+            mut files,
+            mut edition,
+            flavor,
+            mut address_mapping,
+            published_id_for_output,
+            scope,
+            dep_alias_to_package_name,
+            modes,
+            normalized_modules,
+            source,
+        } = input;
+
+        let mut package_address: Option<String> = None;
+        for module in normalized_modules.values() {
+            match &package_address {
+                Some(address) if !same_address(address, &module.address) => {
+                    return Err(format!(
+                        "Dependency '{}': normalized modules span several addresses ({} and {})",
+                        name, address, module.address
+                    ));
+                }
+                Some(_) => {}
+                None => package_address = Some(module.address.clone()),
+            }
+            files.insert(
+                format!("dependencies/{}/sources/{}.move", name, module.name),
+                stubs::module_stub(module),
+            );
         }
-        if trimmed == format!("name = \"{}\"", package_name) {
-            in_pkg = true;
-            continue;
+        if let Some(address) = package_address {
+            // Stubs are 2024 syntax, and the package is published at the modules' address:
+            // mapping its name there gives it a dependency ID for tree shaking and output.
+            edition.get_or_insert_with(|| "2024.beta".to_string());
+            address_mapping
+                .get_or_insert_with(BTreeMap::new)
+                .entry(name.clone())
+                .or_insert(address);
+        }
+        // A group without files only declares named addresses (e.g. a framework package
+        // linked on-chain, `Sui` as `sui`), so they have to be given explicitly.
+        if files.is_empty() && address_mapping.as_ref().is_none_or(|mapping| mapping.is_empty()) {
+            return Err(format!(
+                "Dependency '{}' has no files; declare its named address in addressMapping (e.g. {{ \"{}\": \"0x..\" }})",
+                name,
+                name.to_lowercase()
+            ));
         }
-        if in_pkg && trimmed.starts_with("version = \"") {
-            let mut parts = trimmed.split('"');
-            parts.next();
-            if let Some(version) = parts.next() {
-                return Some(version.to_string());
+
+        Ok(PackageGroup {
+            name,
+            files,
+            edition,
+            flavor,
+            address_mapping,
+            published_id_for_output,
+            scope,
+            dep_alias_to_package_name,
+            modes,
+            source,
+        })
+    }
+}
+
+fn same_address(a: &str, b: &str) -> bool {
+    match (parse_hex_address_to_bytes(a), parse_hex_address_to_bytes(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Whether a dependency comes from `[dependencies]` or `[dev-dependencies]`.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyScope {
+    #[default]
+    Normal,
+    Dev,
+}
+
+/// Select the dependency groups that take part in a build.
+///
+/// Dev-scoped packages (from `[dev-dependencies]`) are only included when `include_dev` is set,
+/// matching the CLI which only pulls them in for `sui move test`. A normal dependency must never
+/// reach into a dev-only package, since that package would be missing from a regular build.
+fn select_dependency_scope(
+    dep_packages: Vec<PackageGroup>,
+    include_dev: bool,
+) -> Result<Vec<PackageGroup>, String> {
+    let dev_names: std::collections::HashSet<&str> = dep_packages
+        .iter()
+        .filter(|pkg| pkg.scope == DependencyScope::Dev)
+        .map(|pkg| pkg.name.as_str())
+        .collect();
+
+    for pkg in dep_packages.iter().filter(|pkg| pkg.scope == DependencyScope::Normal) {
+        for (alias, target) in &pkg.dep_alias_to_package_name {
+            if dev_names.contains(target.as_str()) {
+                return Err(format!(
+                    "Dependency '{}' depends on '{}' (as '{}'), which is only declared in [dev-dependencies]. \
+                     Move it to [dependencies] so it is available outside of test builds.",
+                    pkg.name, target, alias
+                ));
             }
         }
     }
-    None
+
+    Ok(dep_packages
+        .into_iter()
+        .filter(|pkg| include_dev || pkg.scope == DependencyScope::Normal)
+        .collect())
+}
+
+/// Drop root files and dependency groups that belong only to modes other than `options.mode`.
+///
+/// Root files are assigned to modes through `options.file_modes`, dependency groups through
+/// their `modes`; an empty mode list means the file or package is part of every build. Two
+/// variants of a module (e.g. a mocked oracle for "dev" and the real one for "mainnet") only
+/// clash when both are selected.
+fn select_mode(
+    files: &BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    options: &CompileOptions,
+) -> Result<(BTreeMap<String, String>, Vec<PackageGroup>), String> {
+    if let Some(path) = options.file_modes.keys().find(|path| !files.contains_key(*path)) {
+        return Err(format!("fileModes names '{}', which is not one of the input files", path));
+    }
+    let active = |modes: &[String]| {
+        modes.is_empty() || options.mode.as_ref().is_some_and(|mode| modes.contains(mode))
+    };
+    let files = files
+        .iter()
+        .filter(|(path, _)| options.file_modes.get(*path).is_none_or(|modes| active(modes)))
+        .map(|(path, contents)| (path.clone(), contents.clone()))
+        .collect();
+    let dep_packages = dep_packages.into_iter().filter(|pkg| active(&pkg.modes)).collect();
+    Ok((files, dep_packages))
 }
 
+
+
 fn append_git_revision(version: String) -> String {
     if let Some(revision) = option_env!("GIT_REVISION") {
         if revision.is_empty() {
@@ -146,27 +744,25 @@ fn append_git_revision(version: String) -> String {
     }
 }
 
-#[wasm_bindgen]
+/// Version of the bundled Move toolchain: `SUI_MOVE_VERSION` if set at build time,
+/// otherwise the version build.rs resolved from a Sui workspace's Cargo.lock;
+/// build.rs fails when neither is available.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn sui_move_version() -> String {
-    if let Some(version) = option_env!("SUI_MOVE_VERSION") {
-        return version.to_string();
-    }
-    let lock_contents = ""; // include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock"));
-    match package_version_from_lock(lock_contents, "sui-move") {
-        Some(version) => append_git_revision(version),
-        None => "unknown".to_string(),
+    match option_env!("SUI_MOVE_VERSION").filter(|version| !version.is_empty()) {
+        Some(version) => version.to_string(),
+        None => append_git_revision(option_env!("SUI_MOVE_LOCKED_VERSION").unwrap_or_default().to_string()),
     }
 }
 
-#[wasm_bindgen]
+/// Version of the bundled Sui crates: `SUI_VERSION` if set at build time, otherwise the
+/// version build.rs resolved from a Sui workspace's Cargo.lock;
+/// build.rs fails when neither is available.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn sui_version() -> String {
-    if let Some(version) = option_env!("SUI_VERSION") {
-        return version.to_string();
-    }
-    let lock_contents = ""; // include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock"));
-    match package_version_from_lock(lock_contents, "sui") {
-        Some(version) => append_git_revision(version),
-        None => "unknown".to_string(),
+    match option_env!("SUI_VERSION").filter(|version| !version.is_empty()) {
+        Some(version) => version.to_string(),
+        None => append_git_revision(option_env!("SUI_LOCKED_VERSION").unwrap_or_default().to_string()),
     }
 }
 
@@ -186,88 +782,856 @@ fn fn_info(units: &[AnnotatedCompiledModule]) -> FnInfoMap {
     fn_info_map
 }
 
+/// Build a `FnInfoMap` from bytecode alone.
+///
+/// Test attributes are not part of the binary format, so every function is a regular
+/// function unless the caller says the modules may contain tests: then every function is
+/// treated as possibly `#[test]`, which relaxes the checks the Sui verifier skips for tests.
+fn fn_info_from_bytecode(modules: &[CompiledModule], may_contain_test_functions: bool) -> FnInfoMap {
+    let mut fn_info_map = BTreeMap::new();
+    for m in modules {
+        let mod_addr = *m.address();
+        for def in m.function_defs() {
+            let handle = m.function_handle_at(def.function);
+            let fn_name = m.identifier_at(handle.name).to_string();
+            fn_info_map.insert(
+                FnInfoKey { fn_name, mod_addr },
+                FnInfo { is_test: may_contain_test_functions },
+            );
+        }
+    }
+    fn_info_map
+}
+
+// Limits validators apply when signing a publish transaction.
+// ORIGINAL SOURCE: sui-config/src/verifier_signing_config.rs - VerifierSigningConfig defaults
+const SIGNING_MAX_PER_FUN_METER_UNITS: u128 = 2_200_000;
+const SIGNING_MAX_PER_MOD_METER_UNITS: u128 = 2_200_000;
+const SIGNING_MAX_BACK_EDGES_PER_FUNCTION: usize = 10_000;
+const SIGNING_MAX_BACK_EDGES_PER_MODULE: usize = 10_000;
+const SIGNING_SANITY_CHECK_WITH_REGEX_REFERENCE_SAFETY: usize = 2_200_000;
+
+// ORIGINAL SOURCE: VerifierSigningConfig::meter_config_for_signing()
+fn signing_meter_config() -> MeterConfig {
+    MeterConfig {
+        max_per_fun_meter_units: Some(SIGNING_MAX_PER_FUN_METER_UNITS),
+        max_per_mod_meter_units: Some(SIGNING_MAX_PER_MOD_METER_UNITS),
+        max_per_pkg_meter_units: Some(SIGNING_MAX_PER_MOD_METER_UNITS),
+    }
+}
+
+/// Explain which signing limit a metered verification failure ran into, with the meter
+/// units used in each scope when it failed.
+fn describe_signing_limit(status: StatusCode, meter: &SuiVerifierMeter) -> Option<String> {
+    match status {
+        StatusCode::PROGRAM_TOO_COMPLEX => {
+            let usage = |scope: Scope| {
+                let limit = meter.get_limit(scope).map_or("unlimited".to_string(), |limit| limit.to_string());
+                format!("{} of {} units", meter.get_usage(scope), limit)
+            };
+            Some(format!(
+                "verifier meter limit exceeded (function: {}, module: {}, package: {})",
+                usage(Scope::Function),
+                usage(Scope::Module),
+                usage(Scope::Package)
+            ))
+        }
+        StatusCode::TOO_MANY_BACK_EDGES => Some(format!(
+            "back edge limit exceeded (limit: {} per function, {} per module)",
+            SIGNING_MAX_BACK_EDGES_PER_FUNCTION, SIGNING_MAX_BACK_EDGES_PER_MODULE
+        )),
+        _ => None,
+    }
+}
+
 // Ported from sui-move-build/src/lib.rs
-fn verify_bytecode(units: &[AnnotatedCompiledModule], fn_info: &FnInfoMap, test_mode: bool) -> Result<(), String> {
-    let verifier_config = ProtocolConfig::get_for_version(ProtocolVersion::MAX, Chain::Unknown)
-        .verifier_config(/* signing_limits */ None);
+//
+// With `metered`, verification mirrors what a validator runs when signing a publish:
+// the signing limits are applied to the verifier config and a shared `SuiVerifierMeter`
+// accumulates cost across the root package. Modules of `dependency_packages` were metered
+// when they were published and are verified unmetered. Modules of `core_packages` (Core
+// flavor) skip the Sui verifier.
+fn verify_bytecode(
+    modules: &[&NamedCompiledModule],
+    fn_info: &FnInfoMap,
+    protocol_config: &ProtocolConfig,
+    test_mode: bool,
+    metered: bool,
+    core_packages: &BTreeSet<Symbol>,
+    dependency_packages: &BTreeSet<Symbol>,
+) -> Result<(), String> {
+    let signing_limits = metered.then_some((
+        SIGNING_MAX_BACK_EDGES_PER_FUNCTION,
+        SIGNING_MAX_BACK_EDGES_PER_MODULE,
+        SIGNING_SANITY_CHECK_WITH_REGEX_REFERENCE_SAFETY,
+    ));
+    let verifier_config = protocol_config.verifier_config(signing_limits);
+    let mut meter = SuiVerifierMeter::new(signing_meter_config());
 
-    for unit in units {
-        let m = &unit.named_module.module;
+    for m in modules {
+        let core = m.package_name.is_some_and(|name| core_packages.contains(&name));
+        let dependency = m.package_name.is_some_and(|name| dependency_packages.contains(&name));
+        verify_module(&m.module, fn_info, &verifier_config, &mut meter, !test_mode && !core, metered && !dependency)?;
+    }
+    Ok(())
+}
+
+/// Run the Move verifier, then (with `sui_checks`) the Sui verifier, on one module.
+fn verify_module(
+    m: &CompiledModule,
+    fn_info: &FnInfoMap,
+    verifier_config: &move_vm_config::verifier::VerifierConfig,
+    meter: &mut SuiVerifierMeter,
+    sui_checks: bool,
+    metered: bool,
+) -> Result<(), String> {
+    let module_name = m.self_id().name().to_string();
+    if metered {
+        move_bytecode_verifier::verify_module_with_config_metered(verifier_config, m, meter).map_err(|err| {
+            match describe_signing_limit(err.major_status(), meter) {
+                Some(limit) => format!("Module Verification Failure in '{}': {}: {}", module_name, limit, err),
+                None => format!("Module Verification Failure in '{}': {}", module_name, err),
+            }
+        })?;
+    } else {
         move_bytecode_verifier::verify_module_unmetered(m).map_err(|err| {
-             format!("Module Verification Failure: {}", err)
+             format!("Module Verification Failure in '{}': {}", module_name, err)
         })?;
-        
-        if !test_mode {
-            sui_bytecode_verifier::sui_verify_module_unmetered(m, fn_info, &verifier_config).map_err(|err| {
-                 format!("Sui Module Verification Failure: {}", err)
+    }
+
+    if sui_checks {
+        if metered {
+            sui_bytecode_verifier::sui_verify_module_metered(m, fn_info, meter, verifier_config).map_err(|err| {
+                 format!("Sui Module Verification Failure in '{}': {}", module_name, err)
+            })?;
+        } else {
+            sui_bytecode_verifier::sui_verify_module_unmetered(m, fn_info, verifier_config).map_err(|err| {
+                 format!("Sui Module Verification Failure in '{}': {}", module_name, err)
             })?;
         }
     }
     Ok(())
 }
-fn parse_hex_address_to_bytes(addr: &str) -> Option<[u8; 32]> {
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AddressParseError {
+    Empty,
+    NotHex,
+    TooLong,
+}
+
+impl std::fmt::Display for AddressParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddressParseError::Empty => write!(f, "address is empty"),
+            AddressParseError::NotHex => write!(f, "address is not valid hex"),
+            AddressParseError::TooLong => write!(f, "address is longer than 32 bytes"),
+        }
+    }
+}
+
+/// Parse a hex address, accepting the Sui short form (`0x2`) by zero-padding to 32 bytes.
+fn parse_hex_address_to_bytes(addr: &str) -> Result<[u8; 32], AddressParseError> {
     let addr_clean = addr.trim().trim_start_matches("0x");
     if addr_clean.is_empty() {
-        return None;
+        return Err(AddressParseError::Empty);
     }
     let addr_str_normalized = if addr_clean.len() % 2 != 0 {
         format!("0{}", addr_clean)
     } else {
         addr_clean.to_string()
     };
-    let bytes = hex::decode(addr_str_normalized).ok()?;
+    let bytes = hex::decode(addr_str_normalized).map_err(|_| AddressParseError::NotHex)?;
     if bytes.len() > 32 {
-        return None;
+        return Err(AddressParseError::TooLong);
     }
     let mut addr_bytes = [0u8; 32];
     let start = 32 - bytes.len();
     addr_bytes[start..].copy_from_slice(&bytes);
-    Some(addr_bytes)
+    Ok(addr_bytes)
+}
+
+/// Parse a manifest address, keeping the notation it was written in so diagnostics echo it:
+/// `0x`-prefixed is hex, all digits is decimal (as `NumericalAddress::parse_str` reads
+/// Move.toml), and anything else falls back to unprefixed hex.
+fn parse_address_with_format(addr: &str) -> Result<([u8; 32], NumberFormat), AddressParseError> {
+    let trimmed = addr.trim();
+    if trimmed.starts_with("0x") || trimmed.is_empty() || !trimmed.bytes().all(|b| b.is_ascii_digit()) {
+        return parse_hex_address_to_bytes(trimmed).map(|bytes| (bytes, NumberFormat::Hex));
+    }
+    // Big-endian base-10 accumulation: bytes = bytes * 10 + digit.
+    let mut bytes = [0u8; 32];
+    for digit in trimmed.bytes().map(|b| b - b'0') {
+        let mut carry = digit as u16;
+        for byte in bytes.iter_mut().rev() {
+            let value = *byte as u16 * 10 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+        if carry != 0 {
+            return Err(AddressParseError::TooLong);
+        }
+    }
+    Ok((bytes, NumberFormat::Decimal))
+}
+
+/// Toolchain and protocol metadata in one call.
+///
+/// Output format: `{ "sui_move_version": "...", "sui_version": "...",
+/// "protocol_version": { "default": 90, "min": 1, "max": 90 }, "editions": ["legacy", ...],
+/// "bytecode_version": 7 }`. The default protocol version is the one builds use when no
+/// `protocolVersion` is given.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn build_info() -> String {
+    let protocol_config = ProtocolConfig::get_for_version(ProtocolVersion::MAX, Chain::Unknown);
+    serde_json::json!({
+        "sui_move_version": sui_move_version(),
+        "sui_version": sui_version(),
+        "protocol_version": {
+            "default": ProtocolVersion::MAX.as_u64(),
+            "min": ProtocolVersion::MIN.as_u64(),
+            "max": ProtocolVersion::MAX.as_u64(),
+        },
+        "editions": SUPPORTED_EDITIONS,
+        "bytecode_version": protocol_config.move_binary_format_version(),
+    })
+    .to_string()
+}
+
+/// Resolve the protocol config used for verification and test natives.
+///
+/// Defaults to the newest version bundled with sui-types on an unknown chain, which is
+/// what the CLI uses when no network is configured.
+fn resolve_protocol_config(version: Option<u64>, chain: Option<&str>) -> Result<ProtocolConfig, String> {
+    let chain = match chain.unwrap_or("unknown") {
+        "mainnet" => Chain::Mainnet,
+        "testnet" => Chain::Testnet,
+        "unknown" => Chain::Unknown,
+        other => {
+            return Err(format!(
+                "Unsupported chain \"{}\" (expected \"mainnet\", \"testnet\" or \"unknown\")",
+                other
+            ));
+        }
+    };
+    let min = ProtocolVersion::MIN.as_u64();
+    let max = ProtocolVersion::MAX.as_u64();
+    let version = version.unwrap_or(max);
+    if version < min || version > max {
+        return Err(format!(
+            "Unsupported protocol version {} (supported range: {}..={})",
+            version, min, max
+        ));
+    }
+    Ok(ProtocolConfig::get_for_version(ProtocolVersion::new(version), chain))
+}
+
+/// `name = "_"` declares a named address without assigning it.
+fn is_unassigned_address(addr: &str) -> bool {
+    addr.trim() == "_"
 }
 
 // [REMOVED] blake2b256 - Replaced by MovePackage::compute_digest_for_modules_and_deps
 
 
-fn parse_edition(edition_str: &str) -> Edition {
+const SUPPORTED_EDITIONS: &[&str] = &["legacy", "2024", "2024.alpha", "2024.beta"];
+
+/// Parse a manifest edition string. Unknown editions are rejected rather than
+/// silently compiled with legacy semantics.
+fn parse_edition(edition_str: &str) -> Result<Edition, String> {
     match edition_str {
-        "legacy" => Edition::LEGACY,
-        "2024" | "2024.alpha" => Edition::E2024_ALPHA,
-        "2024.beta" => Edition::E2024_BETA,
-        _ => Edition::LEGACY,
+        "legacy" => Ok(Edition::LEGACY),
+        "2024" | "2024.alpha" => Ok(Edition::E2024_ALPHA),
+        "2024.beta" => Ok(Edition::E2024_BETA),
+        _ => Err(format!(
+            "Unsupported edition \"{}\" (supported: {})",
+            edition_str,
+            SUPPORTED_EDITIONS.join(", ")
+        )),
+    }
+}
+
+/// Parse a package flavor. Packages are Sui-flavored unless they ask for Core Move.
+fn parse_flavor(flavor_str: &str) -> Result<Flavor, String> {
+    match flavor_str {
+        "sui" => Ok(Flavor::Sui),
+        "core" => Ok(Flavor::Core),
+        _ => Err(format!("Unsupported flavor \"{}\" (supported: sui, core)", flavor_str)),
     }
 }
 
 #[cfg(feature = "testing")]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct MoveTestResult {
     passed: bool,
     output: String,
+    gas: TestGasReport,
+    notes: Vec<String>,
+    failures: Vec<TestFailureInfo>,
+    coverage: Option<TestCoverageReport>,
+    counts: TestCounts,
+    diagnostics: DiagnosticCounts,
 }
 
 #[cfg(feature = "testing")]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl MoveTestResult {
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn passed(&self) -> bool {
         self.passed
     }
 
-    #[wasm_bindgen(getter)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
     pub fn output(&self) -> String {
         self.output.clone()
     }
-}
 
-// Create a separate test store per-thread (though Wasm is usually single-threaded).
-#[cfg(feature = "testing")]
-thread_local! {
-    static TEST_STORE_INNER: RefCell<InMemoryStorage> = RefCell::new(InMemoryStorage::default());
-}
+    /// Gas summary as `{ totalGasUsed, tests: [{ name, passed, aborted, gasUsed }] }`.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn gas(&self) -> JsValue {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.gas.serialize(&serializer).unwrap_or(JsValue::NULL)
+    }
 
-#[cfg(feature = "testing")]
-static TEST_STORE: Lazy<sui_move_natives::test_scenario::InMemoryTestStore> = Lazy::new(|| {
-    sui_move_natives::test_scenario::InMemoryTestStore(&TEST_STORE_INNER)
-});
+    /// Adjustments made on the user's behalf, e.g. an implicit `std`/`sui` address.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn notes(&self) -> Vec<String> {
+        self.notes.clone()
+    }
+
+    /// Failed tests that aborted, as `[{ test, module, function, abortCode, status, file,
+    /// line, frames }]`, with source locations mapped back to the input file keys.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn failures(&self) -> JsValue {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        self.failures.serialize(&serializer).unwrap_or(JsValue::NULL)
+    }
+
+    /// Root module coverage when run with `withCoverage`, keyed by module name then
+    /// function name: `{ [module]: { [function]: { instructions, executed } } }`.
+    /// `undefined` otherwise.
+    #[cfg(feature = "wasm")]
+    #[wasm_bindgen(getter)]
+    pub fn coverage(&self) -> JsValue {
+        let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+        match &self.coverage {
+            Some(coverage) => coverage.serialize(&serializer).unwrap_or(JsValue::NULL),
+            None => JsValue::UNDEFINED,
+        }
+    }
+
+    /// Tests found in the compiled packages, before `includePackages` filtering.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = numDiscovered))]
+    pub fn num_discovered(&self) -> u32 {
+        self.counts.discovered
+    }
+
+    /// Tests that were executed.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = numRun))]
+    pub fn num_run(&self) -> u32 {
+        self.counts.run
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = numPassed))]
+    pub fn num_passed(&self) -> u32 {
+        self.counts.passed
+    }
+
+    /// Failed tests other than those out of gas.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = numFailed))]
+    pub fn num_failed(&self) -> u32 {
+        self.counts.failed
+    }
+
+    /// Tests that ran out of their gas budget (see `gasLimit`).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = numOutOfGas))]
+    pub fn num_out_of_gas(&self) -> u32 {
+        self.counts.out_of_gas
+    }
+
+    /// Tests skipped because their package was not selected by `includePackages`.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = numFiltered))]
+    pub fn num_filtered(&self) -> u32 {
+        self.counts.filtered
+    }
+
+    /// Compiler warnings in the failure report. The test build does not render warnings
+    /// on success, so they count as suppressed there.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = warningCount))]
+    pub fn warning_count(&self) -> u32 {
+        self.diagnostics.warnings
+    }
+
+    /// Compiler errors in the failure report; 0 once the tests compiled.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = errorCount))]
+    pub fn error_count(&self) -> u32 {
+        self.diagnostics.errors
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter, js_name = suppressedWarningCount))]
+    pub fn suppressed_warning_count(&self) -> u32 {
+        self.diagnostics.suppressed_warnings
+    }
+}
+
+/// Test accounting of a `test()` run.
+#[cfg(feature = "testing")]
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct TestCounts {
+    pub discovered: u32,
+    pub run: u32,
+    pub passed: u32,
+    pub failed: u32,
+    pub out_of_gas: u32,
+    pub filtered: u32,
+}
+
+#[cfg(feature = "testing")]
+impl TestCounts {
+    /// Count the executed tests in `records`. A test that exhausted its gas budget counts
+    /// as out of gas rather than failed: the budget is how `gasLimit` bounds a test.
+    fn from_records(records: &[move_unit_test::test_runner::TestRunRecord], discovered: u32, filtered: u32) -> Self {
+        let out_of_gas = records.iter().filter(|record| is_out_of_gas(record)).count() as u32;
+        let passed = records.iter().filter(|record| record.passed).count() as u32;
+        TestCounts {
+            discovered,
+            run: records.len() as u32,
+            passed,
+            failed: records.len() as u32 - passed - out_of_gas,
+            out_of_gas,
+            filtered,
+        }
+    }
+}
+
+#[cfg(feature = "testing")]
+fn is_out_of_gas(record: &move_unit_test::test_runner::TestRunRecord) -> bool {
+    !record.passed && record.abort.as_ref().is_some_and(|abort| abort.status == StatusCode::OUT_OF_GAS)
+}
+
+#[cfg(feature = "testing")]
+impl MoveTestResult {
+    fn failed(output: String) -> Self {
+        MoveTestResult {
+            passed: false,
+            output,
+            gas: TestGasReport::default(),
+            notes: Vec::new(),
+            failures: Vec::new(),
+            coverage: None,
+            counts: TestCounts::default(),
+            diagnostics: DiagnosticCounts::default(),
+        }
+    }
+
+    /// A build that failed with `diags`, rendered as the compiler reports them.
+    fn compile_failed(files: &MappedFiles, diags: Diagnostics, ansi_color: bool) -> Self {
+        let diags = diags.into_vec();
+        let counts = DiagnosticCounts::of(&diags);
+        let hints = framework_address_hints(&unbound_addresses(&diags));
+        let buffer = report_diagnostics_to_buffer(files, Diagnostics::from(diags), ansi_color);
+        let mut output = String::from_utf8_lossy(&buffer).to_string();
+        for hint in hints {
+            output.push_str(&format!("\nnote: {}", hint));
+        }
+        MoveTestResult { diagnostics: counts, ..MoveTestResult::failed(output) }
+    }
+}
+
+/// Gas consumed by a `test()` run, taken from the patched unit test runner.
+///
+/// Gas is metered with `initial_cost_schedule_for_unit_tests` unless `gasSchedule` is
+/// "protocol"; unit-test figures are stable across runs but are not the computation a
+/// transaction would be charged on chain.
+#[cfg(feature = "testing")]
+#[derive(Serialize, Default)]
+pub struct TestGasReport {
+    #[serde(rename = "totalGasUsed")]
+    total_gas_used: u64,
+    tests: Vec<TestGasEntry>,
+}
+
+#[cfg(feature = "testing")]
+#[derive(Serialize)]
+struct TestGasEntry {
+    /// Fully qualified test name (`address::module::function`).
+    name: String,
+    passed: bool,
+    /// The test aborted; `gas_used` covers execution up to the abort point.
+    aborted: bool,
+    #[serde(rename = "gasUsed")]
+    gas_used: u64,
+}
+
+#[cfg(feature = "testing")]
+impl TestGasReport {
+    /// Gas used by every executed test together.
+    pub fn total_gas_used(&self) -> u64 {
+        self.total_gas_used
+    }
+
+    fn from_records(records: &[move_unit_test::test_runner::TestRunRecord]) -> Self {
+        let tests: Vec<TestGasEntry> = records
+            .iter()
+            .map(|record| TestGasEntry {
+                name: format!("{}::{}", record.module, record.function),
+                passed: record.passed,
+                aborted: record.aborted,
+                gas_used: record.gas_used,
+            })
+            .collect();
+        TestGasReport {
+            total_gas_used: tests.iter().map(|t| t.gas_used).sum(),
+            tests,
+        }
+    }
+}
+
+/// Instruction coverage of the root modules over a `test()` run.
+#[cfg(feature = "testing")]
+pub type TestCoverageReport = BTreeMap<String, BTreeMap<String, FunctionCoverage>>;
+
+#[cfg(feature = "testing")]
+#[derive(Serialize)]
+pub struct FunctionCoverage {
+    /// Number of bytecode instructions in the function.
+    instructions: usize,
+    /// Code offsets executed by at least one test, ascending.
+    executed: Vec<u16>,
+}
+
+/// Lay the traced offsets over every function with a body in `modules`, so functions no
+/// test reached are reported with nothing executed.
+#[cfg(feature = "testing")]
+fn coverage_report<'a>(
+    modules: impl Iterator<Item = &'a CompiledModule>,
+    mut traced: move_unit_test::test_runner::TestCoverage,
+) -> TestCoverageReport {
+    let mut report = TestCoverageReport::new();
+    for module in modules {
+        let id = module.self_id();
+        let functions = report.entry(id.name().to_string()).or_default();
+        for def in module.function_defs() {
+            let Some(code) = &def.code else { continue };
+            let name = module.identifier_at(module.function_handle_at(def.function).name).to_string();
+            let executed = traced.remove(&(id.clone(), name.clone())).unwrap_or_default();
+            functions.insert(
+                name,
+                FunctionCoverage {
+                    instructions: code.code.len(),
+                    executed: executed.into_iter().collect(),
+                },
+            );
+        }
+    }
+    report
+}
+
+/// A failed test that aborted, located in the original input files.
+#[cfg(feature = "testing")]
+#[derive(Serialize)]
+pub struct TestFailureInfo {
+    /// Fully qualified test name (`address::module::function`).
+    test: String,
+    /// Module and function where execution stopped.
+    module: String,
+    function: String,
+    #[serde(rename = "abortCode")]
+    abort_code: Option<u64>,
+    status: String,
+    /// "native" (raised by a native function), "abort" (Move `abort`/`assert!`)
+    /// or "error" (any other VM error, e.g. arithmetic).
+    kind: &'static str,
+    /// The aborting native as `address::module::function`, for `kind == "native"`.
+    #[serde(rename = "nativeFunction", skip_serializing_if = "Option::is_none")]
+    native_function: Option<String>,
+    /// Decoded `#[error]` constant when the abort code is a clever error.
+    #[serde(rename = "cleverError", skip_serializing_if = "Option::is_none")]
+    clever_error: Option<CleverError>,
+    file: Option<String>,
+    line: Option<usize>,
+    /// Abort stack trace, innermost frame first.
+    frames: Vec<TestFrameInfo>,
+}
+
+#[cfg(feature = "testing")]
+#[derive(Serialize)]
+struct TestFrameInfo {
+    module: String,
+    function: String,
+    file: Option<String>,
+    line: Option<usize>,
+}
+
+/// A clever abort code (Move 2024 `#[error]` constants and `assert!` without a code),
+/// decoded against the module that raised it.
+///
+/// ORIGINAL SOURCE: sui-types/src/error.rs - CleverError / move-command-line-common error_bitset.rs
+#[derive(Serialize)]
+struct CleverError {
+    /// Name of the `#[error]` constant; absent for a bare `assert!`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    constant: Option<String>,
+    /// Source line of the abort.
+    line: u16,
+    /// The constant's value: the message for `vector<u8>` constants, otherwise the rendered value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+}
+
+impl std::fmt::Display for CleverError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.constant {
+            Some(constant) => write!(f, "{} (line {})", constant, self.line)?,
+            None => write!(f, "assertion failure (line {})", self.line)?,
+        }
+        if let Some(value) = &self.value {
+            write!(f, ": {}", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode `abort_code` as a clever error raised by `module`, or `None` if it is a plain code.
+fn decode_clever_error(module: &CompiledModule, abort_code: u64) -> Option<CleverError> {
+    use move_command_line_common::error_bitset::ErrorBitset;
+    use move_core_types::runtime_value::MoveValue;
+
+    let bitset = ErrorBitset::from_u64(abort_code)?;
+    let line = bitset.line_number()?;
+    let constant_value = |index: u16| module.constant_pool().get(index as usize)?.deserialize_constant();
+    let as_bytes = |value: &MoveValue| match value {
+        MoveValue::Vector(elems) => elems
+            .iter()
+            .map(|elem| match elem {
+                MoveValue::U8(byte) => Some(*byte),
+                _ => None,
+            })
+            .collect::<Option<Vec<u8>>>(),
+        _ => None,
+    };
+
+    let constant = bitset
+        .identifier_index()
+        .and_then(constant_value)
+        .and_then(|name| String::from_utf8(as_bytes(&name)?).ok());
+    let value = bitset.constant_index().and_then(constant_value).map(|value| {
+        as_bytes(&value)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .unwrap_or_else(|| value.to_string())
+    });
+    Some(CleverError { constant, line, value })
+}
+
+/// Decode an abort code raised by the given module (e.g. from an on-chain transaction failure).
+///
+/// Output is the decoded error as JSON (`{ constant?, line, value? }`); fails if the code is
+/// not a clever error.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn decode_abort_code(module_base64: &str, code: u64) -> MoveCompilerResult {
+    let result = (|| -> Result<CleverError, String> {
+        let bytes = general_purpose::STANDARD
+            .decode(module_base64)
+            .map_err(|e| format!("Module is not valid base64: {}", e))?;
+        let module = CompiledModule::deserialize_with_defaults(&bytes)
+            .map_err(|e| format!("Module failed to deserialize: {}", e))?;
+        decode_clever_error(&module, code)
+            .ok_or_else(|| format!("Abort code {} is not a clever error", code))
+    })();
+    match result {
+        Ok(error) => MoveCompilerResult::ok(serde_json::to_string(&error).unwrap_or_default()),
+        Err(e) => MoveCompilerResult::err(e),
+    }
+}
+
+/// Maps compiler file hashes back to the input file keys, for source-mapping test frames.
+#[cfg(feature = "testing")]
+struct SourceIndex<'a> {
+    files: &'a MappedFiles,
+}
+
+#[cfg(feature = "testing")]
+impl<'a> SourceIndex<'a> {
+    fn new(files: &'a MappedFiles) -> Self {
+        SourceIndex { files }
+    }
+
+    /// File key and 1-based line of a byte offset in the file with `hash`.
+    fn locate(&self, hash: Option<FileHash>, start: Option<u32>) -> (Option<String>, Option<usize>) {
+        let (Some(hash), Some(start)) = (hash, start) else {
+            return (None, None);
+        };
+        match self.files.get(&hash) {
+            Some((path, contents)) => {
+                let contents: &str = &contents;
+                let prefix = contents.get(..start as usize).unwrap_or(contents);
+                (Some(path.to_string()), Some(prefix.matches('\n').count() + 1))
+            }
+            None => (None, None),
+        }
+    }
+
+    fn failures(
+        &self,
+        records: &[move_unit_test::test_runner::TestRunRecord],
+        modules: &BTreeMap<ModuleId, CompiledModule>,
+    ) -> Vec<TestFailureInfo> {
+        records
+            .iter()
+            .filter(|record| !record.passed)
+            .filter_map(|record| {
+                let abort = record.abort.as_ref()?;
+                let frames: Vec<TestFrameInfo> = abort
+                    .frames
+                    .iter()
+                    .map(|frame| {
+                        let (file, line) = self.locate(frame.file_hash, frame.start);
+                        TestFrameInfo {
+                            module: frame.module.clone(),
+                            function: frame.function.clone(),
+                            file,
+                            line,
+                        }
+                    })
+                    .collect();
+                let (module, function, file, line) = match frames.first() {
+                    Some(top) => (top.module.clone(), top.function.clone(), top.file.clone(), top.line),
+                    None => (record.module.clone(), record.function.clone(), None, None),
+                };
+                let kind = match (&abort.native, abort.status) {
+                    (Some(_), _) => "native",
+                    (None, StatusCode::ABORTED) => "abort",
+                    (None, _) => "error",
+                };
+                Some(TestFailureInfo {
+                    test: format!("{}::{}", record.module, record.function),
+                    module,
+                    function,
+                    abort_code: abort.abort_code,
+                    status: format!("{:?}", abort.status),
+                    kind,
+                    clever_error: match (&abort.location, abort.abort_code) {
+                        (Some(location), Some(code)) => modules
+                            .get(location)
+                            .and_then(|module| decode_clever_error(module, code)),
+                        _ => None,
+                    },
+                    native_function: abort
+                        .native
+                        .as_ref()
+                        .map(|(module, function)| format!("{}::{}", module, function)),
+                    file,
+                    line,
+                    frames,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Render the source location of every frame of each failure, for the text report.
+#[cfg(feature = "testing")]
+fn format_failure_locations(failures: &[TestFailureInfo]) -> String {
+    use std::fmt::Write;
+    let mut out = String::new();
+    for failure in failures.iter().filter(|f| !f.frames.is_empty()) {
+        let _ = writeln!(out, "\nSource locations for {}:", failure.test);
+        if let Some(clever) = &failure.clever_error {
+            let _ = writeln!(out, "    aborted with {}", clever);
+        }
+        if let Some(native) = &failure.native_function {
+            let code = failure.abort_code.map(|c| c.to_string()).unwrap_or_else(|| "?".to_string());
+            let _ = writeln!(out, "    aborted in native function {} with code {}", native, code);
+        }
+        for frame in &failure.frames {
+            let location = match (&frame.file, frame.line) {
+                (Some(file), Some(line)) => format!("{}:{}", file, line),
+                _ => "<unknown source>".to_string(),
+            };
+            let _ = writeln!(out, "    at {}::{} ({})", frame.module, frame.function, location);
+        }
+    }
+    out
+}
+
+// Create a separate test store per-thread (though Wasm is usually single-threaded).
+#[cfg(feature = "testing")]
+thread_local! {
+    static TEST_STORE_INNER: RefCell<InMemoryStorage> = RefCell::new(InMemoryStorage::default());
+}
+
+#[cfg(feature = "testing")]
+static TEST_STORE: Lazy<sui_move_natives::test_scenario::InMemoryTestStore> = Lazy::new(|| {
+    sui_move_natives::test_scenario::InMemoryTestStore(&TEST_STORE_INNER)
+});
+
+// Protocol config selected by the current `test()` call; read by the extension hook,
+// which is installed once and cannot capture per-call state.
+#[cfg(feature = "testing")]
+thread_local! {
+    static TEST_PROTOCOL_CONFIG: RefCell<Option<ProtocolConfig>> = const { RefCell::new(None) };
+}
+
+/// Per-test gas budget when no `gasLimit` is given (the CLI's default bound).
+#[cfg(feature = "testing")]
+const DEFAULT_TEST_GAS_LIMIT: u64 = 1_000_000;
+
+#[cfg(feature = "testing")]
+fn test_protocol_config() -> ProtocolConfig {
+    TEST_PROTOCOL_CONFIG
+        .with(|config| config.borrow().clone())
+        .unwrap_or_else(ProtocolConfig::get_for_max_version_UNSAFE)
+}
+
+/// Transaction context the tests run under, set per `test()` call.
+#[cfg(feature = "testing")]
+#[derive(Clone)]
+struct TestTxContextConfig {
+    sender: SuiAddress,
+    digest: TransactionDigest,
+    epoch: u64,
+    epoch_timestamp_ms: u64,
+}
+
+#[cfg(feature = "testing")]
+impl Default for TestTxContextConfig {
+    fn default() -> Self {
+        TestTxContextConfig {
+            sender: SuiAddress::ZERO,
+            digest: TransactionDigest::default(),
+            epoch: 0,
+            epoch_timestamp_ms: 0,
+        }
+    }
+}
+
+#[cfg(feature = "testing")]
+impl TestTxContextConfig {
+    fn from_options(options: &TestOptions) -> Result<Self, String> {
+        let mut config = TestTxContextConfig::default();
+        if let Some(sender) = &options.sender {
+            let bytes = parse_hex_address_to_bytes(sender)
+                .map_err(|e| format!("Invalid sender \"{}\": {}", sender, e))?;
+            config.sender = SuiAddress::from(AccountAddress::new(bytes));
+        }
+        if let Some(seed) = options.rng_seed {
+            // Fresh object IDs derive from the transaction digest, so seed it.
+            let hash: [u8; 32] = Sha256::digest(seed.to_le_bytes()).into();
+            config.digest = TransactionDigest::new(hash);
+        }
+        config.epoch = options.epoch.unwrap_or(0);
+        config.epoch_timestamp_ms = options.epoch_timestamp_ms.unwrap_or(0);
+        Ok(config)
+    }
+}
+
+// Read by the extension hook for the same reason as `TEST_PROTOCOL_CONFIG`.
+#[cfg(feature = "testing")]
+thread_local! {
+    static TEST_TX_CONTEXT: RefCell<TestTxContextConfig> = RefCell::new(TestTxContextConfig::default());
+}
 
 #[cfg(feature = "testing")]
 static SET_EXTENSION_HOOK: Lazy<()> =
@@ -278,22 +1642,23 @@ fn new_testing_object_and_natives_cost_runtime(ext: &mut NativeContextExtensions
     let registry = prometheus::Registry::new();
     let metrics = Arc::new(LimitsMetrics::new(&registry));
     let store = Lazy::force(&TEST_STORE);
-    let protocol_config = ProtocolConfig::get_for_max_version_UNSAFE();
+    let protocol_config = test_protocol_config();
 
     ext.add(sui_move_natives::object_runtime::ObjectRuntime::new(
         store,
         BTreeMap::new(),
         false,
-        Box::leak(Box::new(ProtocolConfig::get_for_max_version_UNSAFE())),
+        Box::leak(Box::new(protocol_config.clone())),
         metrics,
         0,
     ));
     ext.add(sui_move_natives::NativesCostTable::from_protocol_config(&protocol_config));
+    let tx_config = TEST_TX_CONTEXT.with(|config| config.borrow().clone());
     let tx_context = TxContext::new_from_components(
-        &SuiAddress::ZERO,
-        &TransactionDigest::default(),
-        &0,
-        0,
+        &tx_config.sender,
+        &tx_config.digest,
+        &tx_config.epoch,
+        tx_config.epoch_timestamp_ms,
         0,
         0,
         0,
@@ -306,166 +1671,768 @@ fn new_testing_object_and_natives_cost_runtime(ext: &mut NativeContextExtensions
     ext.add(store);
 }
 
-fn setup_vfs(
+fn parse_package_inputs(
     files_json: &str,
     dependencies_json: &str,
-) -> Result<(VfsPath, BTreeMap<String, String>, Vec<PackageGroup>), String> {
+) -> Result<(BTreeMap<String, String>, Vec<PackageGroup>), String> {
     let files: BTreeMap<String, String> = serde_json::from_str(files_json)
-        .map_err(|e| format!("Failed to parse files JSON: {}", e))?;
+        .map_err(|e| format!("Failed to parse files JSON: {}", describe_files_json_error(files_json, e)))?;
 
     let dep_packages: Vec<PackageGroup> = if dependencies_json.is_empty() {
         vec![]
     } else {
-        serde_json::from_str(dependencies_json)
-            .map_err(|e| format!("Failed to parse dependencies JSON: {}", e))?
+        serde_json::from_str(dependencies_json).map_err(|e| {
+            format!("Failed to parse dependencies JSON: {}", describe_dependencies_json_error(dependencies_json, e))
+        })?
     };
 
-    let fs = MemoryFS::new();
-    let root = VfsPath::new(fs);
-
-    let ensure_parents = |path: &VfsPath| -> Result<(), String> {
-        let parent = path.parent();
-        let mut ancestors = vec![];
-        let mut curr_path = parent;
+    Ok((files, dedupe_dependency_groups(dep_packages)?))
+}
 
-        loop {
-            ancestors.push(curr_path.clone());
-            if curr_path.as_str() == "/" { break; }
-            let next = curr_path.parent();
-            if next.as_str() == curr_path.as_str() { break; }
-            curr_path = next;
+/// Drop repeated dependency groups: a resolver walking a diamond (A -> C, B -> C) may send C
+/// twice. Copies with identical files and linkage are merged into the first, which keeps the
+/// widest `scope` (normal over dev) and `modes` (the union, or every mode if either copy has
+/// no modes). Copies whose files differ are version skew and fail, listing the differing
+/// paths with a content hash per copy; copies that link differently (`addressMapping`,
+/// `publishedIdForOutput`, edition, flavor or aliases) fail naming the differing field.
+fn dedupe_dependency_groups(dep_packages: Vec<PackageGroup>) -> Result<Vec<PackageGroup>, String> {
+    let short_hash = |contents: Option<&String>| match contents {
+        Some(contents) => hex::encode(&Sha256::digest(contents.as_bytes())[..8]),
+        None => "missing".to_string(),
+    };
+    let mut unique: Vec<PackageGroup> = Vec::with_capacity(dep_packages.len());
+    for pkg in dep_packages {
+        let Some(first) = unique.iter_mut().find(|seen| seen.name == pkg.name) else {
+            unique.push(pkg);
+            continue;
+        };
+        if first.files != pkg.files {
+            let paths: BTreeSet<&String> = first.files.keys().chain(pkg.files.keys()).collect();
+            let differences: Vec<String> = paths
+                .into_iter()
+                .filter(|path| first.files.get(*path) != pkg.files.get(*path))
+                .map(|path| {
+                    format!("{} ({} vs {})", path, short_hash(first.files.get(path)), short_hash(pkg.files.get(path)))
+                })
+                .collect();
+            return Err(format!(
+                "Dependency '{}' is provided twice with different files (version skew?):\n  {}",
+                pkg.name,
+                differences.join("\n  ")
+            ));
         }
-
-        while let Some(p) = ancestors.pop() {
-            if !p.exists().map_err(|e| e.to_string())? {
-                p.create_dir().map_err(|e| e.to_string())?;
+        let field = if first.address_mapping != pkg.address_mapping {
+            Some("addressMapping")
+        } else if first.published_id_for_output != pkg.published_id_for_output {
+            Some("publishedIdForOutput")
+        } else if first.edition != pkg.edition {
+            Some("edition")
+        } else if first.flavor != pkg.flavor {
+            Some("flavor")
+        } else if first.dep_alias_to_package_name != pkg.dep_alias_to_package_name {
+            Some("depAliasToPackageName")
+        } else {
+            None
+        };
+        if let Some(field) = field {
+            return Err(format!(
+                "Dependency '{}' is provided twice with the same files but a different {}",
+                pkg.name, field
+            ));
+        }
+        if pkg.scope == DependencyScope::Normal {
+            first.scope = DependencyScope::Normal;
+        }
+        if first.modes.is_empty() || pkg.modes.is_empty() {
+            first.modes.clear();
+        } else {
+            for mode in pkg.modes {
+                if !first.modes.contains(&mode) {
+                    first.modes.push(mode);
+                }
             }
         }
-        Ok(())
-    };
-
-    for (name, content) in &files {
-        let path = root.join(name).map_err(|e| format!("Invalid path {}: {}", name, e))?;
-        ensure_parents(&path)?;
-        path.create_file()
-            .and_then(|mut f| {
-                use std::io::Write;
-                write!(f, "{}", content)?;
-                Ok(())
-            })
-            .map_err(|e| format!("Failed to create file {}: {}", name, e))?;
-    }
-
-    for pkg in &dep_packages {
-        for (name, content) in &pkg.files {
-            let path = root.join(name).map_err(|e| format!("Invalid dep path {}: {}", name, e))?;
-            ensure_parents(&path)?;
-            path.create_file()
-                .and_then(|mut f| {
-                    use std::io::Write;
-                    write!(f, "{}", content)?;
-                    Ok(())
-                })
-                .map_err(|e| format!("Failed to create dep file {}: {}", name, e))?;
+        if first.source.is_none() {
+            first.source = pkg.source;
         }
     }
-
-    Ok((root, files, dep_packages))
+    Ok(unique)
 }
 
-fn compile_impl(
-    files_json: &str,
-    dependencies_json: &str,
-    options_json: Option<String>,
-    graph_json: Option<String>,  // DependencyGraph JSON for lockfile generation
-) -> MoveCompilerResult {
-    #[cfg(debug_assertions)]
-    #[cfg(debug_assertions)]
-    console_error_panic_hook::set_once();
-
+/// Name the entry of a files object that failed to deserialize, e.g. a file whose content
+/// is not a string, or quote the input around the syntax error.
+fn describe_files_json_error(json: &str, error: serde_json::Error) -> String {
+    if let Ok(entries) = serde_json::from_str::<BTreeMap<String, serde_json::Value>>(json) {
+        if let Some((path, value)) = entries.iter().find(|(_, value)| !value.is_string()) {
+            return format!("content of '{}' must be a string, found {}", path, json_kind(value));
+        }
+    }
+    describe_json_syntax_error(json, error)
+}
 
-    // START ANSI SUPPORT
-    // Parse options early
-    let options: CompileOptions = options_json
-        .and_then(|json| serde_json::from_str(&json).ok())
-        .unwrap_or_default();
+/// Name the dependency entry (index and name) that failed to deserialize.
+fn describe_dependencies_json_error(json: &str, error: serde_json::Error) -> String {
+    let Ok(entries) = serde_json::from_str::<Vec<serde_json::Value>>(json) else {
+        return describe_json_syntax_error(json, error);
+    };
+    for (index, entry) in entries.into_iter().enumerate() {
+        let name = entry.get("name").and_then(|name| name.as_str()).unwrap_or("?").to_string();
+        if let Err(e) = serde_json::from_value::<PackageGroup>(entry) {
+            return format!("dependency #{} ('{}'): {}", index, name, e);
+        }
+    }
+    error.to_string()
+}
 
-    // ANSI SUPPORT
-    // Use options.ansi_color instead of hardcoded true
-    let ansi_color = options.ansi_color;
-    // Allow overriding via explicit flag, otherwise follow options
-    if ansi_color {
-       colored::control::set_override(true);
-    } else {
-       colored::control::set_override(false);
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
     }
-    // END ANSI SUPPORT
+}
 
-    let (root, files, dep_packages) = match setup_vfs(files_json, dependencies_json) {
-        Ok(res) => res,
-        Err(e) => return MoveCompilerResult { success: false, output: e },
+/// serde's message plus the input around the reported position.
+fn describe_json_syntax_error(json: &str, error: serde_json::Error) -> String {
+    let Some(line) = json.lines().nth(error.line().saturating_sub(1)) else {
+        return error.to_string();
     };
+    let column = error.column().saturating_sub(1);
+    let start = line.char_indices().map(|(i, _)| i).take_while(|i| *i + 40 <= column).last().unwrap_or(0);
+    let snippet: String = line[start..].chars().take(80).collect();
+    format!("{} (near `{}`)", error, snippet)
+}
 
-    // Build PackagePaths for targets (root package)
-    let mut root_named_address_map = BTreeMap::<String, NumericalAddress>::new();
-    let mut root_package_name = "root".to_string();
-    let mut root_edition = Edition::LEGACY;
-    let mut _root_published_at: Option<[u8; 32]> = None;
-
-    if let Some(move_toml_content) = files.get("Move.toml") {
+/// Default `maxFileBytes`: far above any hand-written module, below generated blobs.
+const DEFAULT_MAX_FILE_BYTES: usize = 8 * 1024 * 1024;
+/// Default `maxTotalBytes`: several times the Sui framework with all system packages.
+const DEFAULT_MAX_TOTAL_BYTES: usize = 256 * 1024 * 1024;
 
+/// Describe a problem with `path` of `package` (None for the root).
+fn describe_input_file(package: Option<&str>, path: &str, problem: &str) -> String {
+    match package {
+        Some(name) => format!("Dependency '{}': {} {}", name, path, problem),
+        None => format!("{} {}", path, problem),
+    }
+}
 
+/// Reject inputs that cannot be Move sources or manifests: compiled bytecode (`.mv`) and
+/// binary content.
+fn check_input_contents(package: Option<&str>, path: &str, contents: &str) -> Result<(), String> {
+    if path.ends_with(".mv") {
+        return Err(describe_input_file(
+            package,
+            path,
+            "is compiled bytecode; bytecode dependencies are not supported, pass the package's \
+             sources or its normalizedModules instead",
+        ));
+    }
+    if contents.contains('\0') {
+        return Err(describe_input_file(
+            package,
+            path,
+            "contains binary data (NUL bytes); only text files can be compiled",
+        ));
+    }
+    Ok(())
+}
 
-        match toml::from_str::<SourceManifest>(move_toml_content) {
-            Ok(manifest) => {
-                root_package_name = manifest.package.name.to_string();
+/// Reject files or packages over the size limits, given `(package, path, size)` per file.
+fn check_input_sizes<'a>(
+    sizes: impl Iterator<Item = (Option<&'a str>, &'a str, usize)>,
+    max_file_bytes: Option<usize>,
+    max_total_bytes: Option<usize>,
+) -> Result<(), String> {
+    let max_file_bytes = max_file_bytes.unwrap_or(DEFAULT_MAX_FILE_BYTES);
+    let max_total_bytes = max_total_bytes.unwrap_or(DEFAULT_MAX_TOTAL_BYTES);
+    let mut total = 0usize;
+    for (package, path, size) in sizes {
+        if size > max_file_bytes {
+            return Err(describe_input_file(
+                package,
+                path,
+                &format!("is {} bytes, over the {} byte limit (maxFileBytes)", size, max_file_bytes),
+            ));
+        }
+        total += size;
+    }
+    if total > max_total_bytes {
+        return Err(format!(
+            "Input files total {} bytes, over the {} byte limit (maxTotalBytes)",
+            total, max_total_bytes
+        ));
+    }
+    Ok(())
+}
 
-                // Extract Edition
-                if let Some(edition_str) = manifest.package.edition {
-                    root_edition = parse_edition(&edition_str);
-                }
+/// Input file keys must be relative paths that stay inside the package tree.
+fn validate_input_path(path: &str) -> Result<(), String> {
+    if path.starts_with('/') || path.starts_with('\\') {
+        return Err(format!("Invalid path {}: absolute paths are not allowed", path));
+    }
+    if path.split(['/', '\\']).any(|component| component == "..") {
+        return Err(format!("Invalid path {}: '..' components are not allowed", path));
+    }
+    Ok(())
+}
 
-                // Extract Published At
-                if let Some(published_at_str) = manifest.package.published_at {
-                    _root_published_at = parse_hex_address_to_bytes(&published_at_str);
+/// Reject paths used by two packages (root or dependencies) with different contents;
+/// every package is written into one filesystem, so one file would silently replace the other.
+fn check_path_collisions(files: &BTreeMap<String, String>, dep_packages: &[PackageGroup]) -> Result<(), String> {
+    // Path -> (owning dependency, or None for the root; contents).
+    let mut owners: BTreeMap<&str, (Option<&str>, &str)> =
+        files.iter().map(|(path, contents)| (path.as_str(), (None, contents.as_str()))).collect();
+    let mut collisions = Vec::new();
+    for pkg in dep_packages {
+        for (path, contents) in &pkg.files {
+            match owners.get(path.as_str()) {
+                Some((owner, owner_contents)) if *owner_contents != contents.as_str() => {
+                    let owner = match owner {
+                        Some(name) => format!("dependency '{}'", name),
+                        None => "root".to_string(),
+                    };
+                    collisions.push(format!("{} ({} and dependency '{}')", path, owner, pkg.name));
                 }
-
-                // Extract Addresses
-                if let Some(addresses) = manifest.addresses {
-                    for (name, addr_opt) in addresses {
-                        if let Some(addr_str) = addr_opt {
-                            let name_str = name.as_str().to_string();
-                            if let Some(bytes) = parse_hex_address_to_bytes(&addr_str) {
-                                root_named_address_map.insert(
-                                    name_str,
-                                    NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex)
-                                );
-                            }
-                        }
-                    }
+                Some(_) => {}
+                None => {
+                    owners.insert(path, (Some(&pkg.name), contents));
                 }
             }
-            Err(_e) => {
-                 // Ignore parse errors
-            }
         }
     }
+    if collisions.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "File paths are used by several packages with different contents \
+         (place each dependency under its own directory, e.g. dependencies/<name>/):\n  {}",
+        collisions.join("\n  ")
+    ))
+}
 
+/// The package's own Move.toml: the shallowest one among its files.
+fn package_manifest(files: &BTreeMap<String, String>) -> Option<(&str, &str)> {
+    files
+        .iter()
+        .filter(|(path, _)| path.as_str() == "Move.toml" || path.ends_with("/Move.toml"))
+        .min_by_key(|(path, _)| path.matches('/').count())
+        .map(|(path, contents)| (path.as_str(), contents.as_str()))
+}
 
-    // Collect all dependency file paths to exclude them from root targets
-    let mut dependency_paths = std::collections::HashSet::new();
-    for pkg_group in &dep_packages {
-        for path in pkg_group.files.keys() {
+/// `addr_subst` tables of a manifest's `[dependencies]` and `[dev-dependencies]`, by
+/// dependency name. An address value assigns; a name renames from the dependency's scope.
+///
+/// ORIGINAL SOURCE: move-package/src/source_package/manifest_parser.rs - parse_substitution
+fn manifest_substitutions(path: &str, move_toml: &str) -> Result<BTreeMap<String, manifest::Substitution>, String> {
+    let doc: toml::Value = toml::from_str(move_toml).map_err(|e| format!("Failed to parse {}: {}", path, e))?;
+    let mut substitutions = BTreeMap::new();
+    for section in ["dependencies", "dev-dependencies"] {
+        let Some(deps) = doc.get(section).and_then(|deps| deps.as_table()) else { continue };
+        for (dep, info) in deps {
+            let Some(table) = info.get("addr_subst").and_then(|subst| subst.as_table()) else { continue };
+            let mut subst = manifest::Substitution::new();
+            for (name, value) in table {
+                let value = value
+                    .as_str()
+                    .ok_or_else(|| format!("{}: addr_subst.{} of dependency '{}' must be a string", path, name, dep))?;
+                let entry = if value.starts_with("0x") || value.bytes().all(|b| b.is_ascii_digit()) {
+                    let (bytes, _) = parse_address_with_format(value)
+                        .map_err(|e| format!("{}: addr_subst.{} of dependency '{}': \"{}\" ({})", path, name, dep, value, e))?;
+                    manifest::SubstOrRename::Assign(AccountAddress::new(bytes))
+                } else {
+                    manifest::SubstOrRename::RenameFrom(value.to_string())
+                };
+                subst.insert(name.clone(), entry);
+            }
+            substitutions.insert(dep.clone(), subst);
+        }
+    }
+    Ok(substitutions)
+}
+
+/// Apply the `addr_subst` tables of the root and dependency manifests, with Move package
+/// semantics: `dep_name = "0x.."` assigns the dependency's named address, which it must
+/// declare as `_`, and `local = "dep_name"` makes the dependency's `dep_name` address
+/// visible as `local` in the declaring package. `dep_package_paths` holds one target per
+/// dependency group, fileless ones included.
+fn apply_address_substitutions(
+    root_files: &BTreeMap<String, String>,
+    root_named_address_map: &mut BTreeMap<String, NumericalAddress>,
+    dep_packages: &[PackageGroup],
+    dep_package_paths: &mut [PackagePaths<Symbol, String>],
+    unassigned_addresses: &BTreeMap<String, BTreeSet<String>>,
+) -> Result<(), String> {
+    let no_aliases = BTreeMap::new();
+    let declarers = std::iter::once((None, root_files, &no_aliases))
+        .chain(dep_packages.iter().map(|pkg| (Some(pkg.name.as_str()), &pkg.files, &pkg.dep_alias_to_package_name)));
+    // Collected first: renames read dependency maps that assignments may change.
+    let mut assignments = Vec::new();
+    let mut renames = Vec::new();
+    for (declarer, files, aliases) in declarers {
+        let Some((path, move_toml)) = package_manifest(files) else { continue };
+        for (dep, subst) in manifest_substitutions(path, move_toml)? {
+            let target = aliases.get(&dep).cloned().unwrap_or(dep);
+            for (name, entry) in subst {
+                match entry {
+                    manifest::SubstOrRename::Assign(address) => assignments.push((target.clone(), name, address)),
+                    manifest::SubstOrRename::RenameFrom(from) => {
+                        renames.push((declarer.map(str::to_string), target.clone(), name, from))
+                    }
+                }
+            }
+        }
+    }
+    fn map_of<'a>(
+        paths: &'a mut [PackagePaths<Symbol, String>],
+        name: &str,
+    ) -> Option<&'a mut BTreeMap<String, NumericalAddress>> {
+        paths
+            .iter_mut()
+            .find(|target| target.name.as_ref().is_some_and(|(target_name, _)| target_name.as_str() == name))
+            .map(|target| &mut target.named_address_map)
+    }
+    for (target, name, address) in assignments {
+        let Some(map) = map_of(dep_package_paths, &target) else { continue };
+        if let Some(assigned) = map.get(&name) {
+            return Err(format!(
+                "addr_subst assigns '{}' of dependency '{}', which already assigns it {}; only addresses \
+                 declared as \"_\" can be assigned",
+                name,
+                target,
+                assigned.into_inner().to_hex_literal()
+            ));
+        }
+        if !unassigned_addresses.get(&target).is_some_and(|names| names.contains(&name)) {
+            return Err(format!(
+                "addr_subst assigns '{}' of dependency '{}', which does not declare that address",
+                name, target
+            ));
+        }
+        map.insert(name, NumericalAddress::new(address.into_bytes(), NumberFormat::Hex));
+    }
+    for (declarer, target, name, from) in renames {
+        let Some(address) = map_of(dep_package_paths, &target).and_then(|map| map.get(&from).copied()) else {
+            return Err(format!(
+                "addr_subst renames '{}' from '{}' of dependency '{}', which does not define that address",
+                name, from, target
+            ));
+        };
+        match declarer {
+            None => {
+                root_named_address_map.insert(name, address);
+            }
+            Some(declarer) => {
+                if let Some(map) = map_of(dep_package_paths, &declarer) {
+                    map.insert(name, address);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reject dependency groups whose `[dependencies]` form a cycle, naming the packages on it.
+///
+/// The compiler only reports such a cycle as a module ordering or resolution failure.
+/// Edges come from each package's Move.toml (aliases resolved through
+/// `depAliasToPackageName`); the root package takes part under its manifest name. Cycles
+/// between the modules themselves are caught after compilation by `check_module_cycles`.
+fn check_dependency_cycles(files: &BTreeMap<String, String>, dep_packages: &[PackageGroup]) -> Result<(), String> {
+    // Package name -> (its manifest path, declared dependencies).
+    let mut manifests: BTreeMap<String, &str> = BTreeMap::new();
+    let mut edges: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    if let Some((path, move_toml)) = package_manifest(files) {
+        let name = toml::from_str::<toml::Value>(move_toml)
+            .ok()
+            .and_then(|doc| doc.get("package")?.get("name")?.as_str().map(str::to_string));
+        if let Some(name) = name {
+            manifests.insert(name.clone(), path);
+            edges.insert(name, declared_dependency_names(move_toml).into_iter().collect());
+        }
+    }
+    for pkg in dep_packages {
+        let Some((path, move_toml)) = package_manifest(&pkg.files) else {
+            continue;
+        };
+        let dependencies = declared_dependency_names(move_toml)
+            .into_iter()
+            .map(|alias| pkg.dep_alias_to_package_name.get(&alias).cloned().unwrap_or(alias))
+            .collect();
+        manifests.insert(pkg.name.clone(), path);
+        edges.insert(pkg.name.clone(), dependencies);
+    }
+
+    let Some(cycle) = find_cycle(&edges) else {
+        return Ok(());
+    };
+    let declared: Vec<String> = cycle
+        .windows(2)
+        .map(|edge| format!("{} -> {}: declared in {}", edge[0], edge[1], manifests[&edge[0]]))
+        .collect();
+    Err(format!(
+        "Cyclic package dependency: {}\n  {}",
+        cycle.join(" -> "),
+        declared.join("\n  ")
+    ))
+}
+
+/// Reject compiled modules whose imports make their packages depend on each other, naming
+/// the packages on the cycle and one import per edge.
+///
+/// Edges are read from each module's module handles, so they reflect what the bytecode
+/// links against. Modules without a package name belong to the root.
+fn check_module_cycles<'a>(modules: impl Iterator<Item = &'a NamedCompiledModule>) -> Result<(), String> {
+    let modules: Vec<&NamedCompiledModule> = modules.collect();
+    let package = |module: &NamedCompiledModule| module.package_name.map_or_else(|| "root".to_string(), |name| name.to_string());
+    let package_of: BTreeMap<ModuleId, String> =
+        modules.iter().map(|module| (module.module.self_id(), package(*module))).collect();
+
+    let mut edges: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    // (from, to) -> the first import that makes the edge.
+    let mut imports: BTreeMap<(String, String), String> = BTreeMap::new();
+    for named in modules.iter().copied() {
+        let module = &named.module;
+        let from = package(named);
+        for handle in module.module_handles() {
+            let id = module.module_id_for_handle(handle);
+            let Some(to) = package_of.get(&id).filter(|to| **to != from) else {
+                continue;
+            };
+            edges.entry(from.clone()).or_default().insert(to.clone());
+            imports.entry((from.clone(), to.clone())).or_insert_with(|| {
+                format!("{} uses {}", module.self_id().to_canonical_string(true), id.to_canonical_string(true))
+            });
+        }
+    }
+
+    let Some(cycle) = find_cycle(&edges) else {
+        return Ok(());
+    };
+    let uses: Vec<String> = cycle
+        .windows(2)
+        .map(|edge| format!("{} -> {}: {}", edge[0], edge[1], imports[&(edge[0].clone(), edge[1].clone())]))
+        .collect();
+    Err(format!(
+        "Cyclic package dependency between modules: {}\n  {}",
+        cycle.join(" -> "),
+        uses.join("\n  ")
+    ))
+}
+
+/// A cycle in `edges`, as the nodes on it with the first repeated at the end.
+fn find_cycle(edges: &BTreeMap<String, BTreeSet<String>>) -> Option<Vec<String>> {
+    // Depth-first search; `path` is the current chain of nodes.
+    fn visit<'a>(
+        node: &'a str,
+        edges: &'a BTreeMap<String, BTreeSet<String>>,
+        path: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+    ) -> Option<Vec<String>> {
+        if let Some(start) = path.iter().position(|entry| *entry == node) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|entry| entry.to_string()).collect();
+            cycle.push(node.to_string());
+            return Some(cycle);
+        }
+        if done.contains(node) {
+            return None;
+        }
+        path.push(node);
+        for next in edges.get(node).into_iter().flatten() {
+            if let Some(cycle) = visit(next, edges, path, done) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(node);
+        None
+    }
+
+    let mut done = BTreeSet::new();
+    edges.keys().find_map(|node| visit(node, edges, &mut Vec::new(), &mut done))
+}
+
+/// Create every missing ancestor directory of `path`.
+fn ensure_vfs_parents(path: &VfsPath) -> Result<(), String> {
+    let parent = path.parent();
+    let mut ancestors = vec![];
+    let mut curr_path = parent;
+
+    loop {
+        ancestors.push(curr_path.clone());
+        if curr_path.as_str() == "/" { break; }
+        let next = curr_path.parent();
+        if next.as_str() == curr_path.as_str() { break; }
+        curr_path = next;
+    }
+
+    while let Some(p) = ancestors.pop() {
+        if !p.exists().map_err(|e| e.to_string())? {
+            p.create_dir().map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Compiler inputs derived from the root manifest and the dependency groups.
+#[derive(Clone)]
+struct PackageTargets {
+    root_package_name: String,
+    /// Root package first, then every source dependency (flagged via `is_dependency`).
+    targets: Vec<PackagePaths<Symbol, String>>,
+    /// Output IDs of published dependencies, in insertion order.
+    dependency_ids: Vec<[u8; 32]>,
+    /// Compilation (original) address -> output (latest) address.
+    compilation_to_output: BTreeMap<AccountAddress, AccountAddress>,
+    /// Compilation addresses of published dependencies.
+    known_compilation_addresses: std::collections::HashSet<AccountAddress>,
+    /// Notes to surface in the output (see `CompilationOutput::notes`).
+    notes: Vec<String>,
+    /// Builder warnings, added to the output's `warnings`.
+    warnings: Vec<String>,
+    /// Root `[addresses]` names in declaration order, with `preserveAddressOrder`.
+    address_order: Option<Vec<String>>,
+    /// Names declared in the root manifest's `[dependencies]`.
+    declared_dependencies: Vec<String>,
+    /// Dependencies with neither `publishedIdForOutput` nor an address of their own.
+    unpublished_dependencies: Vec<String>,
+    /// Root compilation address -> its `published-at`, when the manifest sets one.
+    root_address_mapping: Option<(AccountAddress, AccountAddress)>,
+}
+
+/// Package layout folder a root source file belongs to.
+///
+/// ORIGINAL SOURCE: move-package/src/source_package/layout.rs - SourcePackageLayout
+enum SourceLayout {
+    /// `sources/` and `scripts/`: always part of the build.
+    Sources,
+    /// `tests/`: test mode only (or `includeTests`).
+    Tests,
+    /// `examples/`: test mode only (or `includeExamples`).
+    Examples,
+    /// Anything else; compiled as sources with a warning.
+    Other,
+}
+
+impl SourceLayout {
+    fn classify(path: &str) -> Self {
+        match path.split('/').next() {
+            Some("sources") | Some("scripts") if path.contains('/') => SourceLayout::Sources,
+            Some("tests") if path.contains('/') => SourceLayout::Tests,
+            Some("examples") if path.contains('/') => SourceLayout::Examples,
+            _ => SourceLayout::Other,
+        }
+    }
+}
+
+/// Parse a Move.toml, naming the file on failure.
+///
+/// toml's error display carries the line/column and the offending snippet.
+fn parse_manifest_file(path: &str, contents: &str) -> Result<SourceManifest, String> {
+    toml::from_str::<SourceManifest>(contents).map_err(|e| format!("Failed to parse {}: {}", path, e))
+}
+
+/// Parse a manifest, or with `ignoreManifestErrors` log the failure and return `None`
+/// so the caller falls back to defaults.
+fn load_manifest(
+    path: &str,
+    contents: &str,
+    ignore_errors: bool,
+    logger: &BuildLogger,
+) -> Result<Option<SourceManifest>, String> {
+    match parse_manifest_file(path, contents) {
+        Ok(manifest) => Ok(Some(manifest)),
+        Err(e) if ignore_errors => {
+            logger.warn("parsing", &format!("{} (ignored)", e));
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Read a Move.toml's package metadata without compiling.
+///
+/// Output format: `{ "name": "...", "edition": "2024.beta", "publishedAt": "0x...",
+/// "addresses": { "pkg": "0x0", "other": null }, "dependencies": [ ... ],
+/// "devDependencies": [ ... ] }`, or `{ "error": "..." }` if the manifest does not parse.
+/// Each dependency is `{ "name", "kind", ... }` with kind "git" (`git`, `rev`, `subdir`),
+/// "local" (`local`), "system" (`system`), "onChain" or "external" (`resolver`, `data`),
+/// plus `override` and `renameFrom` when set.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn parse_manifest(move_toml: &str) -> String {
+    let result = parse_manifest_file("Move.toml", move_toml).and_then(|manifest| {
+        Ok(serde_json::json!({
+            "name": manifest.package.name,
+            "edition": manifest.package.edition,
+            "publishedAt": manifest.package.published_at,
+            "addresses": manifest
+                .addresses
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, addr)| (name, addr.filter(|addr| !is_unassigned_address(addr))))
+                .collect::<BTreeMap<_, _>>(),
+            "dependencies": manifest_dependencies(&manifest.dependencies)?,
+            "devDependencies": manifest_dependencies(&manifest.dev_dependencies)?,
+        }))
+    });
+    match result {
+        Ok(manifest) => manifest.to_string(),
+        Err(error) => serde_json::json!({ "error": error }).to_string(),
+    }
+}
+
+/// Entries of a `[dependencies]`-style table, classified by the key that selects their kind.
+///
+/// ORIGINAL SOURCE: move-package-alt/src/schema/manifest.rs - ManifestDependencyInfo
+fn manifest_dependencies(
+    dependencies: &BTreeMap<String, manifest::ManifestDependency>,
+) -> Result<Vec<serde_json::Value>, String> {
+    dependencies
+        .iter()
+        .map(|(name, dep)| {
+            let mut entry = if let Some(git) = &dep.git {
+                serde_json::json!({ "kind": "git", "git": git, "rev": dep.rev, "subdir": dep.subdir })
+            } else if let Some(local) = &dep.local {
+                serde_json::json!({ "kind": "local", "local": local })
+            } else if let Some(system) = &dep.system {
+                serde_json::json!({ "kind": "system", "system": system })
+            } else if dep.on_chain.is_some() {
+                serde_json::json!({ "kind": "onChain" })
+            } else if let Some((resolver, data)) = dep.r.as_ref().and_then(|r| r.iter().next()) {
+                serde_json::json!({ "kind": "external", "resolver": resolver, "data": data })
+            } else {
+                return Err(format!(
+                    "Dependency '{}' has none of git, local, system, on-chain or r.<resolver>",
+                    name
+                ));
+            };
+            entry["name"] = serde_json::Value::String(name.clone());
+            if let Some(is_override) = dep.dep_override {
+                entry["override"] = serde_json::Value::Bool(is_override);
+            }
+            if let Some(rename_from) = &dep.rename_from {
+                entry["renameFrom"] = serde_json::Value::String(rename_from.clone());
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+fn build_package_targets(
+    files: &BTreeMap<String, String>,
+    dep_packages: &[PackageGroup],
+    options: &CompileOptions,
+    logger: &BuildLogger,
+) -> Result<PackageTargets, String> {
+    // Build PackagePaths for targets (root package)
+    let mut root_named_address_map = BTreeMap::<String, NumericalAddress>::new();
+    let mut root_package_name = "root".to_string();
+    let mut root_edition = Edition::LEGACY;
+    let mut root_flavor = Flavor::Sui;
+    let mut root_published_at: Option<[u8; 32]> = None;
+    let address_order = if options.preserve_address_order {
+        Some(files.get("Move.toml").map(|toml| declared_address_order(toml)).unwrap_or_default())
+    } else {
+        None
+    };
+    let declared_dependencies = files
+        .get("Move.toml")
+        .map(|toml| declared_dependency_names(toml))
+        .unwrap_or_default();
+
+    if let Some(move_toml_content) = files.get("Move.toml") {
+
+
+
+        if let Some(manifest) =
+            load_manifest("Move.toml", move_toml_content, options.ignore_manifest_errors, logger)?
+        {
+            root_package_name = manifest.package.name.to_string();
+
+            // Extract Edition
+            if let Some(edition_str) = manifest.package.edition {
+                root_edition = parse_edition(&edition_str)
+                    .map_err(|e| format!("Invalid edition in Move.toml: {}", e))?;
+            }
+            if let Some(flavor) = manifest.package.flavor {
+                root_flavor = flavor;
+            }
+
+            // Extract Published At
+            if let Some(published_at_str) = manifest.package.published_at {
+                root_published_at = parse_hex_address_to_bytes(&published_at_str).ok();
+            }
+
+            // Extract Addresses. Every invalid entry is reported, in declaration order with
+            // `preserveAddressOrder` (as the CLI lists them) and alphabetically otherwise.
+            if let Some(addresses) = manifest.addresses {
+                let mut addresses: Vec<_> = addresses.into_iter().collect();
+                if let Some(order) = &address_order {
+                    addresses.sort_by_key(|(name, _)| order.iter().position(|declared| declared == name.as_str()));
+                }
+                let mut invalid = Vec::new();
+                for (name, addr_opt) in addresses {
+                    if let Some(addr_str) = addr_opt {
+                        if is_unassigned_address(&addr_str) {
+                            continue;
+                        }
+                        let name_str = name.as_str().to_string();
+                        match parse_address_with_format(&addr_str) {
+                            Ok((bytes, format)) => {
+                                root_named_address_map.insert(name_str, NumericalAddress::new(bytes, format));
+                            }
+                            Err(e) => {
+                                invalid.push(format!("Invalid address for '{}' in Move.toml [addresses]: \"{}\" ({})", name_str, addr_str, e));
+                            }
+                        }
+                    }
+                }
+                if !invalid.is_empty() {
+                    return Err(invalid.join("\n"));
+                }
+            }
+        }
+    }
+
+
+    // Collect all dependency file paths to exclude them from root targets
+    let mut dependency_paths = std::collections::HashSet::new();
+    for pkg_group in dep_packages {
+        for path in pkg_group.files.keys() {
             dependency_paths.insert(path.as_str());
         }
     }
 
+    let mut notes = Vec::new();
+    let mut warnings = Vec::new();
+    let include_tests = options.include_tests.unwrap_or(options.test_mode);
+    let include_examples = options.include_examples.unwrap_or(options.test_mode);
+    let mut unclassified = Vec::new();
     let mut root_targets: Vec<Symbol> = files
         .keys()
         .filter(|name| !name.ends_with("Move.toml") && name.ends_with(".move"))
         .filter(|name| !dependency_paths.contains(name.as_str()))
+        .filter(|name| match SourceLayout::classify(name) {
+            SourceLayout::Sources => true,
+            SourceLayout::Tests => include_tests,
+            SourceLayout::Examples => include_examples,
+            SourceLayout::Other => {
+                unclassified.push(name.as_str());
+                true
+            }
+        })
         .map(|s| Symbol::from(s.as_str()))
         .collect();
+    if !unclassified.is_empty() {
+        let warning = format!(
+            "Files outside sources/, tests/ and examples/ were compiled as sources: {}",
+            unclassified.join(", ")
+        );
+        logger.warn("parsing", &warning);
+        warnings.push(warning);
+    }
 
     // Sort to mimic CLI: sources/* before tests/*, then lexical.
     root_targets.sort_by(|a, b| {
@@ -486,29 +2453,47 @@ fn compile_impl(
     let mut compilation_to_output = BTreeMap::<AccountAddress, AccountAddress>::new();
     // Set of addresses used for compilation, to identify published dependencies in the graph
     let mut known_compilation_addresses = std::collections::HashSet::new();
+    let mut unpublished_dependencies = Vec::new();
+    // Dependency package name -> its named addresses declared `_`, which `addr_subst` may assign.
+    let mut unassigned_addresses = BTreeMap::<String, BTreeSet<String>>::new();
 
-    for pkg_group in &dep_packages {
+    for pkg_group in dep_packages {
         let mut named_address_map = BTreeMap::<String, NumericalAddress>::new();
+        let unassigned = unassigned_addresses.entry(pkg_group.name.clone()).or_default();
         let mut edition = Edition::LEGACY;
+        let mut flavor = Flavor::Sui;
         let mut published_at: Option<[u8; 32]> = None;
         let mut fallback_dep_id: Option<[u8; 32]> = None;
+        // The package's own `[addresses]` entry, checked against the original ID of its lock.
+        let mut own_address: Option<[u8; 32]> = None;
 
         // Dependency ID for output prefers latest-published-id.
         let mut dep_id_for_output = pkg_group
             .published_id_for_output
             .as_ref()
-            .and_then(|id| parse_hex_address_to_bytes(id));
+            .and_then(|id| parse_hex_address_to_bytes(id).ok());
 
         // Prefer address mapping supplied from JS to avoid extra parsing work in WASM.
         if let Some(ref addr_map) = pkg_group.address_mapping {
+            // A fileless group declaring a single address (`Sui` as `sui`) is published there.
+            let single_fileless = pkg_group.files.is_empty() && addr_map.len() == 1;
             for (name, addr_str) in addr_map {
-                if let Some(bytes) = parse_hex_address_to_bytes(addr_str) {
-                    named_address_map.insert(
-                        name.clone(),
-                        NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex)
-                    );
-                    if name == &pkg_group.name && fallback_dep_id.is_none() {
-                        fallback_dep_id = Some(bytes);
+                if is_unassigned_address(addr_str) {
+                    unassigned.insert(name.clone());
+                    continue;
+                }
+                match parse_hex_address_to_bytes(addr_str) {
+                    Ok(bytes) => {
+                        named_address_map.insert(
+                            name.clone(),
+                            NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex)
+                        );
+                        if (name == &pkg_group.name || single_fileless) && fallback_dep_id.is_none() {
+                            fallback_dep_id = Some(bytes);
+                        }
+                    }
+                    Err(e) => {
+                        return Err(format!("Invalid address for '{}' in dependency '{}': \"{}\" ({})", name, pkg_group.name, addr_str, e));
                     }
                 }
             }
@@ -522,32 +2507,37 @@ fn compile_impl(
 
             if let Some(toml_key) = toml_key {
                 if let Some(move_toml_content) = pkg_group.files.get(&toml_key) {
-                    if let Ok(manifest) = toml::from_str::<SourceManifest>(move_toml_content) {
+                    let manifest = load_manifest(&toml_key, move_toml_content, options.ignore_manifest_errors, logger)
+                        .map_err(|e| format!("Invalid manifest for dependency '{}': {}", pkg_group.name, e))?;
+                    if let Some(manifest) = manifest {
                         // Extract Edition
                         if let Some(edition_val) = manifest.package.edition {
-                            edition = parse_edition(&edition_val);
+                            edition = parse_edition(&edition_val)
+                                .map_err(|e| format!("Invalid edition in {}: {}", toml_key, e))?;
+                        }
+                        if let Some(flavor_val) = manifest.package.flavor {
+                            flavor = flavor_val;
                         }
                         // Extract Published At
                         if let Some(published_at_val) = manifest.package.published_at {
-                            published_at = parse_hex_address_to_bytes(&published_at_val);
+                            published_at = parse_hex_address_to_bytes(&published_at_val).ok();
                         }
 
                         // Check [addresses] section for package's own address (priority over published-at)
-                        let mut found_address_id = false;
                         if let Some(addresses) = &manifest.addresses {
                             // let pkg_name_symbol = Symbol::from(pkg_group.name.as_str());
                             if let Some(Some(addr)) = addresses.get(pkg_group.name.as_str()) {
                                 // Address is effectively AccountAddress, which we can get bytes from
                                 if fallback_dep_id.is_none() {
-                                    if let Some(bytes) = parse_hex_address_to_bytes(addr) {
+                                    if let Ok((bytes, _)) = parse_address_with_format(addr) {
                                         fallback_dep_id = Some(bytes);
-                                        found_address_id = true;
+                                        own_address = Some(bytes);
                                     }
                                 }
                             }
                         }
 
-                        if !found_address_id {
+                        if own_address.is_none() {
                             if let Some(bytes) = published_at {
                                 if fallback_dep_id.is_none() {
                                     fallback_dep_id = Some(bytes);
@@ -558,12 +2548,18 @@ fn compile_impl(
                         if let Some(addresses) = manifest.addresses {
                             for (name, addr_opt) in addresses {
                                 if let Some(addr) = addr_opt {
+                                    if is_unassigned_address(&addr) {
+                                        unassigned.insert(name.to_string());
+                                        continue;
+                                    }
                                     let name_str = name.as_str().to_string();
-                                    if let Some(bytes) = parse_hex_address_to_bytes(&addr) {
-                                        named_address_map.insert(
-                                            name_str,
-                                            NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex)
-                                        );
+                                    match parse_address_with_format(&addr) {
+                                        Ok((bytes, format)) => {
+                                            named_address_map.insert(name_str, NumericalAddress::new(bytes, format));
+                                        }
+                                        Err(e) => {
+                                            return Err(format!("Invalid address for '{}' in {}: \"{}\" ({})", name_str, toml_key, addr, e));
+                                        }
                                     }
                                 }
                             }
@@ -571,16 +2567,77 @@ fn compile_impl(
                     }
                 }
             }
+
+            // The CLI reads a package's publication from its lock files before its manifest:
+            // `[published.<env>]` of Published.toml (or a version 4 Move.lock), `[env.<env>]` of a
+            // legacy Move.lock, then the `[pinned.<env>.<name>]` entry of the root Move.lock.
+            // With `lockEnvironment`, the lock's IDs win the same way.
+            if let Some(env) = &options.lock_environment {
+                let lock_files = ["Published.toml", "Move.lock"].into_iter().flat_map(|file| {
+                    pkg_group.files.iter().filter(move |(key, _)| key.ends_with(file))
+                });
+                let publication = lock_files
+                    .map(|(key, lock)| {
+                        lock_publication(lock, env)
+                            .map(|ids| (key.as_str(), ids))
+                            .map_err(|e| format!("Invalid {} for dependency '{}': {}", key, pkg_group.name, e))
+                    })
+                    .chain(files.get("Move.lock").map(|lock| {
+                        root_lock_publication(lock, env, &pkg_group.name)
+                            .map(|ids| ("Move.lock", ids))
+                            .map_err(|e| format!("Invalid Move.lock for dependency '{}': {}", pkg_group.name, e))
+                    }))
+                    .find(|found| !matches!(found, Ok((_, (None, None)))))
+                    .transpose()?;
+                if let Some((lock_key, (original, latest))) = publication {
+                    if let Some(original) = original {
+                        // `published-at` is the latest version and differs from the
+                        // `[addresses]` entry of any upgraded package; only the original ID
+                        // the lock records must match it.
+                        if let Some(own) = own_address.filter(|own| *own != original) {
+                            let mismatch = format!(
+                                "Dependency '{}': [addresses] {} = {} differs from the original published ID {} in {}; linking against {}",
+                                pkg_group.name,
+                                pkg_group.name,
+                                AccountAddress::new(own).to_hex_literal(),
+                                AccountAddress::new(original).to_hex_literal(),
+                                lock_key,
+                                AccountAddress::new(original).to_hex_literal(),
+                            );
+                            if options.strict_published_at {
+                                return Err(mismatch);
+                            }
+                            logger.warn("parsing", &mismatch);
+                            warnings.push(mismatch);
+                        }
+                        fallback_dep_id = Some(original);
+                        named_address_map.insert(
+                            pkg_group.name.clone(),
+                            NumericalAddress::new(original, move_compiler::shared::NumberFormat::Hex),
+                        );
+                    }
+                    if dep_id_for_output.is_none() {
+                        dep_id_for_output = latest.or(original);
+                    }
+                }
+            }
         }
 
         // Use explicitly provided edition if available
         if let Some(ref edition_str) = pkg_group.edition {
 
-            edition = parse_edition(edition_str);
-
-        } else {
+            edition = parse_edition(edition_str)
+                .map_err(|e| format!("Invalid edition for dependency '{}': {}", pkg_group.name, e))?;
 
         }
+        if let Some(edition_str) = options.edition_overrides.get(&pkg_group.name) {
+            edition = parse_edition(edition_str)
+                .map_err(|e| format!("Invalid editionOverrides entry for '{}': {}", pkg_group.name, e))?;
+        }
+        if let Some(ref flavor_str) = pkg_group.flavor {
+            flavor = parse_flavor(flavor_str)
+                .map_err(|e| format!("Invalid flavor for dependency '{}': {}", pkg_group.name, e))?;
+        }
 
         let dep_files: Vec<Symbol> = pkg_group.files
             .keys()
@@ -604,6 +2661,8 @@ fn compile_impl(
             if !dependency_ids.contains(&bytes) {
                 dependency_ids.push(bytes);
             }
+        } else {
+            unpublished_dependencies.push(pkg_group.name.clone());
         }
         
         // Track the mapping from Compilation Address -> Output Address
@@ -618,20 +2677,13 @@ fn compile_impl(
              known_compilation_addresses.insert(comp_addr);
         }
 
-        // Merge dependency addresses into root map (MATCHES TEST_IMPL)
-        for (name, addr) in &named_address_map {
-             if !root_named_address_map.contains_key(name) {
-                 root_named_address_map.insert(name.clone(), *addr);
-             }
-        }
-
         dep_package_paths.push(PackagePaths {
             name: Some((
                 Symbol::from(pkg_group.name.as_str()),
                 PackageConfig {
                     is_dependency: true,
                     edition,
-                    flavor: Flavor::Sui,
+                    flavor,
                     ..PackageConfig::default()
                 },
             )),
@@ -640,17 +2692,35 @@ fn compile_impl(
         });
     }
 
-    // FALLBACK: Ensure std and sui are always defined
-    if !root_named_address_map.contains_key("std") {
-        if let Some(bytes) = parse_hex_address_to_bytes("0x1") {
-            root_named_address_map.insert("std".to_string(), NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex));
-        }
-    }
-    if !root_named_address_map.contains_key("sui") {
-        if let Some(bytes) = parse_hex_address_to_bytes("0x2") {
-            root_named_address_map.insert("sui".to_string(), NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex));
+    apply_address_substitutions(
+        files,
+        &mut root_named_address_map,
+        dep_packages,
+        &mut dep_package_paths,
+        &unassigned_addresses,
+    )?;
+    // Merge dependency addresses into the root map after substitution, so the root links
+    // against the addresses the dependencies are compiled at.
+    for target in &dep_package_paths {
+        for (name, addr) in &target.named_address_map {
+            root_named_address_map.entry(name.clone()).or_insert(*addr);
         }
     }
+    // Fileless groups only contribute named addresses; there is nothing to compile.
+    dep_package_paths.retain(|target| !target.paths.is_empty());
+    notes.extend(apply_framework_fallback(
+        &mut root_named_address_map,
+        options.implicit_framework_addresses.unwrap_or(true),
+    ));
+
+    // The root compiles at its own named address, or 0x0 when it declares none.
+    let root_address_mapping = root_published_at.map(|published_at| {
+        let compiled_at = root_named_address_map
+            .get(&root_package_name)
+            .map(|addr| addr.into_inner())
+            .unwrap_or(AccountAddress::ZERO);
+        (compiled_at, AccountAddress::new(published_at))
+    });
 
     let target_package = PackagePaths {
         name: Some((
@@ -658,7 +2728,7 @@ fn compile_impl(
             PackageConfig {
                 is_dependency: false,
                 edition: root_edition,
-                flavor: Flavor::Sui,
+                flavor: root_flavor,
                 ..PackageConfig::default()
             },
         )),
@@ -671,452 +2741,2765 @@ fn compile_impl(
     let mut all_targets = vec![target_package];
     all_targets.extend(dep_package_paths);
 
-    // Build compiler with from_package_paths
-    let mut compiler = match Compiler::from_package_paths(
-        Some(root),
-        all_targets,
-        Vec::new(), // No bytecode dependencies in this flow
-    ) {
-        Ok(c) => c,
-        Err(e) => return MoveCompilerResult {
-            success: false,
-            output: format!("Failed to create compiler: {}", e),
+    Ok(PackageTargets {
+        root_package_name,
+        targets: all_targets,
+        dependency_ids,
+        compilation_to_output,
+        known_compilation_addresses,
+        notes,
+        warnings,
+        address_order,
+        declared_dependencies,
+        unpublished_dependencies,
+        root_address_mapping,
+    })
+}
+
+/// Original and latest published IDs from the `[published.<env>]` table of a Published.toml
+/// or version 4 Move.lock (`original-id`, `published-at`), or from the `[env.<env>]` table of a
+/// legacy Move.lock (`original-published-id`, `latest-published-id`); both None when the file
+/// has neither.
+///
+/// ORIGINAL SOURCE: move-package-alt/src/schema/publication.rs - Publication (v4),
+/// move-package/src/lock_file/schema.rs - `[env.<name>]` ManagedPackage (legacy)
+fn lock_publication(lock: &str, env: &str) -> Result<(Option<[u8; 32]>, Option<[u8; 32]>), String> {
+    let lock: toml::Value = toml::from_str(lock).map_err(|e| e.to_string())?;
+    let (table, kind, original_key, latest_key) = match lock.get("published").and_then(|envs| envs.get(env)) {
+        Some(section) => (section, "published", "original-id", "published-at"),
+        None => match lock.get("env").and_then(|envs| envs.get(env)) {
+            Some(section) => (section, "env", "original-published-id", "latest-published-id"),
+            None => return Ok((None, None)),
         },
     };
-
-    let flags = if options.test_mode {
-        Flags::testing()
-    } else {
-        Flags::empty()
+    let id = |key: &str| -> Result<Option<[u8; 32]>, String> {
+        match table.get(key).and_then(|value| value.as_str()) {
+            Some(id) => parse_hex_address_to_bytes(id)
+                .map(Some)
+                .map_err(|e| format!("[{}.{}] {} \"{}\" ({})", kind, env, key, id, e)),
+            None => Ok(None),
+        }
     };
-    
-    // Note: Silence warnings is handled via post-processing of diagnostics in this simplified builder.
-    // Lint flags are not exposed via Flags directly in this version of move-compiler. 
-
-    compiler = compiler.set_flags(flags);
+    Ok((id(original_key)?, id(latest_key)?))
+}
 
-    let (compiler_files, res) = match compiler.build() {
-        Ok(res) => res,
-        Err(e) => return MoveCompilerResult {
-            success: false,
-            output: format!("Compiler initialization error: {}", e),
-        },
+/// Original and latest published IDs the version 4 root Move.lock pins for dependency `name`
+/// in `env` (`address-override`, then `latest-published-id` or `published-at`).
+fn root_lock_publication(
+    lock: &str,
+    env: &str,
+    name: &str,
+) -> Result<(Option<[u8; 32]>, Option<[u8; 32]>), String> {
+    let parsed: toml::Value = toml::from_str(lock).map_err(|e| e.to_string())?;
+    let pinned = parsed.get("pinned").and_then(|envs| envs.get(env)).is_some();
+    let Some(pin) = pinned.then(|| lock_pins(lock, Some(env))).transpose()?.and_then(|mut pins| pins.remove(name))
+    else {
+        return Ok((None, None));
     };
+    let id = |key: &str, id: Option<String>| -> Result<Option<[u8; 32]>, String> {
+        id.map(|id| {
+            parse_hex_address_to_bytes(&id).map_err(|e| format!("[pinned.{}.{}] {} \"{}\" ({})", env, name, key, id, e))
+        })
+        .transpose()
+    };
+    Ok((id("address-override", pin.address)?, id("published-at", pin.published_id)?))
+}
 
-    match res {
-        Ok((units, warning_diags)) => {
-            // VERIFICATION STEP (Ported from sui-move-build)
-            let fn_info = fn_info(&units);
-            if let Err(e) = verify_bytecode(&units, &fn_info, options.test_mode) {
-                 return MoveCompilerResult {
-                    success: false,
-                     output: format!("Bytecode Verification Failed: {}", e),
-                 };
-            }
-
-            // NEW: Filter modules to only include those that are part of the root package source files.
-            
-            // Tree Shaking / Usage-Based Dependency Filtering (Strict Parity with Sui CLI)
-            // The official CLI `dump_bytecode_as_base64` logic only retains published dependencies
-            // that are EITHER:
-            // 1. Immediately used by the root package.
-            // 2. Used by other *published* dependencies (transitive closure).
-            // Crucially, it IGNORES usages from unpublished (source) dependencies.
-            
-            // 1. Identify Published Addresses (Compilation IDs used in bytecode)
-            let published_addresses = known_compilation_addresses;
+/// A dependency as a Move.lock pins it.
+struct LockPin {
+    git: Option<String>,
+    rev: Option<String>,
+    subdir: Option<String>,
+    /// Original ID the lock fixes for the package (v4 `address-override`).
+    address: Option<String>,
+    /// Published ID the lock records for the package (`latest-published-id`, or
+    /// `published-at`), compared with `publishedIdForOutput`.
+    published_id: Option<String>,
+}
 
-            // 2. Compute Kept Addresses via Rooted Graph Traversal (Strict Usage)
-            // Start only from Root modules (the output targets).
-            // Traverse to find all reachable dependencies (both Source and Published).
-            
-            // We keep OUTPUT addresses
-            let mut kept_output_addresses = std::collections::HashSet::new();
-            // We traverse COMPILATION addresses
-            let mut visited_compilation_addresses = std::collections::HashSet::new();
-            
-            // Queue for traversal
-            // contains ModuleId to look up in units or published deps
-            let mut worklist_source_units = Vec::new();
-            let mut worklist_published_addresses = Vec::new();
-
-            // 2a. Initialize with Root Modules
-            for unit in &units {
-                let pkg_name = unit.named_module.package_name.map(|s| s.to_string()).unwrap_or("".to_string());
-                let is_root = pkg_name == "root" || pkg_name == root_package_name || unit.named_module.package_name.is_none();
-                
-                if is_root {
-                    worklist_source_units.push(unit);
+/// Dependency pins of a Move.lock by package name, without the root package.
+///
+/// Version 3 lists them as `[[move.package]]` entries (`id`, or `name` in older locks);
+/// version 4 as `[pinned.<env>.<name>]` tables, read for `env` or the lock's only environment.
+///
+/// ORIGINAL SOURCE: move-package/src/lock_file/schema.rs (v3),
+/// move-package-alt/src/schema/lockfile.rs - ParsedLockfile / Pin (v4)
+fn lock_pins(lock: &str, env: Option<&str>) -> Result<BTreeMap<String, LockPin>, String> {
+    let lock: toml::Value = toml::from_str(lock).map_err(|e| format!("Invalid moveLock: {}", e))?;
+    let version = lock.get("move").and_then(|section| section.get("version")).and_then(|v| v.as_integer());
+    let pin = |table: &toml::Value| {
+        let source = table.get("source");
+        let field = |key: &str| source.and_then(|s| s.get(key)).and_then(|v| v.as_str()).map(str::to_string);
+        let is_root = source.and_then(|s| s.get("root")).and_then(|v| v.as_bool()) == Some(true)
+            || field("local").as_deref() == Some(".");
+        let pin = LockPin {
+            git: field("git"),
+            rev: field("rev"),
+            subdir: field("subdir"),
+            address: table.get("address-override").and_then(|v| v.as_str()).map(str::to_string),
+            published_id: table
+                .get("latest-published-id")
+                .or_else(|| table.get("published-at"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        };
+        (!is_root).then_some(pin)
+    };
+    let mut pins = BTreeMap::new();
+    match version {
+        Some(4) => {
+            let Some(envs) = lock.get("pinned").and_then(|pinned| pinned.as_table()) else {
+                return Ok(pins);
+            };
+            let packages = match env {
+                Some(env) => envs
+                    .get(env)
+                    .ok_or_else(|| format!("moveLock has no [pinned.{}] environment", env))?,
+                None if envs.len() == 1 => envs.values().next().expect("one environment"),
+                None => {
+                    return Err(format!(
+                        "moveLock pins several environments ({}); set moveLockEnvironment to pick one",
+                        envs.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                }
+            };
+            for (name, table) in packages.as_table().into_iter().flatten() {
+                if let Some(pin) = pin(table) {
+                    pins.insert(name.clone(), pin);
                 }
             }
-
-            use std::fmt::Write;
-
-
-            // Helper to find a unit by ID (for traversing usage of Source Dependencies)
-            
-            let mut visited_source_units = std::collections::HashSet::new();
-            for u in &worklist_source_units {
-                visited_source_units.insert(u.named_module.module.self_id());
-            }
-
-            while !worklist_source_units.is_empty() {
-                let current_batch = worklist_source_units.split_off(0);
-                
-                for unit in current_batch {
-                    let module = &unit.named_module.module;
-                    
-                    // Traverse immediate dependencies (Imports)
-                    for dep_id in module.immediate_dependencies() {
-                        let addr = *dep_id.address();
-                        
-                        if published_addresses.contains(&addr) {
-                            // Link to Published Package
-                            // Map compilation address (addr) to output address
-                            if let Some(output_addr) = compilation_to_output.get(&addr) {
-                                if kept_output_addresses.insert(*output_addr) {
-
-                                    // We need to traverse the dependencies of this published package too.
-                                    // Published packages are identified by their COMPILATION address in 'units'
-                                    if visited_compilation_addresses.insert(addr) {
-                                        worklist_published_addresses.push(addr);
-                                    }
-                                }
-                            } else {
-                                warn(&format!("Rust: TreeShake WARNING: {} in published but no output mapping!", addr));
-                            }
-                        } else {
-                            // Link to Source Package (e.g. multisig)
-                            // Find the unit that corresponds to this dependency
-                            // Search in 'units'
-                            for valid_unit in &units {
-                                let valid_id = valid_unit.named_module.module.self_id();
-                                if valid_id == dep_id {
-                                    // Found the source module being used!
-                                    if visited_source_units.insert(valid_id) {
-                                        worklist_source_units.push(valid_unit);
-                                    }
-                                }
-                            }
-                        }
-                    }
+        }
+        Some(0..=3) => {
+            let packages = lock.get("move").and_then(|section| section.get("package")).and_then(|p| p.as_array());
+            for table in packages.into_iter().flatten() {
+                let name = table.get("id").or_else(|| table.get("name")).and_then(|v| v.as_str());
+                if let (Some(name), Some(pin)) = (name, pin(table)) {
+                    pins.insert(name.to_string(), pin);
                 }
             }
+        }
+        Some(other) => return Err(format!("Unsupported moveLock version {} (expected 3 or 4)", other)),
+        None => return Err("moveLock has no [move] version".to_string()),
+    }
+    Ok(pins)
+}
 
-            // 2b. Transitive Closure for Published Packages
-            // If we keep Pyth, we must keep Wormhole (Pyth's dependency).
-            // We search for modules in 'units' (which contains all compiled deps) matching the address.
-            while let Some(addr) = worklist_published_addresses.pop() {
-                // Find all modules belonging to this published address (Compilation ID) in our compiled set
-                for unit in &units {
-                    if *unit.named_module.module.address() == addr {
-                        // This unit belongs to a kept published package.
-                        // Check ITS dependencies.
-                        for dep_id in unit.named_module.module.immediate_dependencies() {
-                            let dep_addr = *dep_id.address();
-                             if published_addresses.contains(&dep_addr) {
-                                if let Some(output_addr) = compilation_to_output.get(&dep_addr) {
-                                    if kept_output_addresses.insert(*output_addr) {
-                                        if visited_compilation_addresses.insert(dep_addr) {
-                                            worklist_published_addresses.push(dep_addr);
-                                        }
-                                    }
-                                }
-                            }
-                            // Note: Published modules should not depend on Source modules
-                        }
-                    }
+/// Compare the dependency groups of the build against the pins of `lock`, one report line
+/// per difference: a package on one side only, a different git source or revision, or an
+/// `addressMapping` or `publishedIdForOutput` that disagrees with the lock's address.
+/// Revisions, sources and addresses are only compared when both sides record them. Pins of
+/// packages in `inactive` (groups of another mode or scope) are not expected.
+fn check_move_lock(
+    lock: &str,
+    env: Option<&str>,
+    dep_packages: &[PackageGroup],
+    inactive: &BTreeSet<String>,
+) -> Result<(), String> {
+    let mut pins = lock_pins(lock, env)?;
+    pins.retain(|name, _| !inactive.contains(name));
+    let mut mismatches = Vec::new();
+    for pkg in dep_packages {
+        let Some(pin) = pins.remove(&pkg.name) else {
+            mismatches.push(format!("{}: provided as a dependency but not pinned in Move.lock", pkg.name));
+            continue;
+        };
+        let source = pkg.source.clone().unwrap_or_default();
+        let compared = [
+            ("git", &pin.git, &source.git),
+            ("rev", &pin.rev, &source.rev),
+            ("subdir", &pin.subdir, &source.subdir),
+        ];
+        for (key, pinned, provided) in compared {
+            if let (Some(pinned), Some(provided)) = (pinned, provided) {
+                if pinned != provided {
+                    mismatches.push(format!("{}: {} \"{}\" in Move.lock, \"{}\" provided", pkg.name, key, pinned, provided));
                 }
             }
+        }
+        let mapped = pkg.address_mapping.as_ref().and_then(|mapping| mapping.get(&pkg.name));
+        if let (Some(pinned), Some(mapped)) = (&pin.address, mapped) {
+            if !same_address(pinned, mapped) {
+                mismatches.push(format!("{}: address {} in Move.lock, {} provided", pkg.name, pinned, mapped));
+            }
+        }
+        if let (Some(pinned), Some(provided)) = (&pin.published_id, &pkg.published_id_for_output) {
+            if !same_address(pinned, provided) {
+                mismatches.push(format!(
+                    "{}: published ID {} in Move.lock, publishedIdForOutput {} provided",
+                    pkg.name, pinned, provided
+                ));
+            }
+        }
+    }
+    for name in pins.keys() {
+        mismatches.push(format!("{}: pinned in Move.lock but not provided as a dependency", name));
+    }
+    if mismatches.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Dependencies do not match moveLock (set ignoreLockMismatch to build anyway):\n  {}",
+        mismatches.join("\n  ")
+    ))
+}
 
-            // 3. Filter dependency IDs
-            // FIX: Do NOT filter dependencies based on usage. CLI uses all resolved dependencies (Linkage Table)
-            // for digest calculation. Filtering causes digest mismatch.
-            //
-            // ORIGINAL SOURCE REFERENCE:
-            // - move-package-alt/src/graph/linkage.rs:40 - LinkageTable maps OriginalID -> PackageInfo
-            // - sui-move-build/src/lib.rs - dump_bytecode_as_base64() uses complete linkage table
-            // - Digest calculation includes ALL dependencies in the linkage table, not just used ones
-            let mut dependency_ids_vec: Vec<[u8; 32]> = dependency_ids
-                .iter()
-                .cloned()
-                // .filter(|bytes| kept_output_addresses.contains(&AccountAddress::new(*bytes)))
-                .collect();
-            
-            // Sort dependency IDs to ensure deterministic order (matches CLI)
-            dependency_ids_vec.sort();
-            // In the VFS, root files are top-level keys in the `files` map provided to compile_impl.
-            // The compiler returns all units because we passed dependencies as targets.
-            // let root_file_names: std::collections::HashSet<&str> = files.keys().map(|s| s.as_str()).collect();
-
-            // Handle warnings
-            // Options parsed early
+/// Names in the manifest's `[addresses]` table, in declaration order.
+///
+/// `SourceManifest` (and the compiler's `PackagePaths`) hold addresses in a `BTreeMap`,
+/// so the order has to be read from the document itself.
+fn declared_address_order(move_toml: &str) -> Vec<String> {
+    let Ok(doc) = move_toml.parse::<toml_edit::DocumentMut>() else {
+        return Vec::new();
+    };
+    doc.get("addresses")
+        .and_then(|item| item.as_table_like())
+        .map(|table| table.iter().map(|(name, _)| name.to_string()).collect())
+        .unwrap_or_default()
+}
 
+/// Root `[dependencies]` names, as written in the manifest.
+fn declared_dependency_names(move_toml: &str) -> Vec<String> {
+    let Ok(doc) = move_toml.parse::<toml_edit::DocumentMut>() else {
+        return Vec::new();
+    };
+    doc.get("dependencies")
+        .and_then(|item| item.as_table_like())
+        .map(|table| table.iter().map(|(name, _)| name.to_string()).collect())
+        .unwrap_or_default()
+}
 
+/// Framework addresses injected when neither the manifest nor a dependency defines them.
+const FRAMEWORK_FALLBACK_ADDRESSES: [(&str, &str); 2] = [("std", "0x1"), ("sui", "0x2")];
+
+/// Framework named addresses and the dependency group expected to define each.
+const FRAMEWORK_ADDRESS_PROVIDERS: [(&str, &str); 4] =
+    [("std", "MoveStdlib"), ("sui", "Sui"), ("bridge", "Bridge"), ("deepbook", "DeepBook")];
+
+/// Bind `std`/`sui` to their well-known addresses if nothing else did, returning a note
+/// per injected address. Without `implicit`, nothing is bound, for builds against a
+/// framework published elsewhere (e.g. a localnet fork); a root that uses an undefined
+/// framework address then fails with the compiler's unbound address error, which
+/// `framework_address_hints` annotates.
+fn apply_framework_fallback(named_address_map: &mut BTreeMap<String, NumericalAddress>, implicit: bool) -> Vec<String> {
+    let mut notes = Vec::new();
+    if !implicit {
+        return notes;
+    }
+    for (name, addr) in FRAMEWORK_FALLBACK_ADDRESSES {
+        if named_address_map.contains_key(name) {
+            continue;
+        }
+        if let Ok(bytes) = parse_hex_address_to_bytes(addr) {
+            named_address_map.insert(
+                name.to_string(),
+                NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex),
+            );
+            notes.push(format!("Named address '{}' was not defined; assumed {}", name, addr));
+        }
+    }
+    notes
+}
 
-            // Build module list with IDs
-            let mut module_infos: Vec<(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)> =
-                Vec::new();
-            for unit in units {
-                // Filter modules based on package name.
-                // We assigned "root" package name to limits, so we check for that.
-                // If package_name is None, we assume it's part of the compilation target (root).
-                // Dependencies usually            for unit in units {
-                let pkg_name = unit.named_module.package_name.map(|s| s.to_string()).unwrap_or("".to_string());
+/// Named addresses that `diags` report as unbound: the quoted names of diagnostics about
+/// an address (e.g. "Unbound address 'foo'").
+fn unbound_addresses(diags: &[move_compiler::diagnostics::Diagnostic]) -> BTreeSet<String> {
+    diags
+        .iter()
+        .flat_map(|diag| {
+            let (_, message, (_, label), _, _) = diag.clone().into_codespan_format();
+            [message.to_string(), label]
+        })
+        .filter(|text| text.contains("address"))
+        .flat_map(|text| text.split('\'').skip(1).step_by(2).map(str::to_string).collect::<Vec<_>>())
+        .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .collect()
+}
 
-                let is_root = pkg_name == "root" || pkg_name == root_package_name || unit.named_module.package_name.is_none();
-                
-                if is_root {
-                    let id = unit.named_module.module.self_id();
-                    module_infos.push((id, unit.named_module));
-                }
-            }
+/// Notes for the framework named addresses among `unbound`, naming the dependency group
+/// expected to define each.
+fn framework_address_hints(unbound: &BTreeSet<String>) -> Vec<String> {
+    FRAMEWORK_ADDRESS_PROVIDERS
+        .iter()
+        .filter(|(name, _)| unbound.contains(*name))
+        .map(|(name, provider)| {
+            format!(
+                "named address '{}' is not defined: add the '{}' dependency or an [addresses] entry for it",
+                name, provider
+            )
+        })
+        .collect()
+}
 
-            let fmt_id = |id: &ModuleId| {
-                format!(
-                    "{}::{}",
-                    id.address().to_canonical_string(true),
-                    id.name()
-                )
-            };
+fn compile_impl(
+    files_json: &str,
+    dependencies_json: &str,
+    options_json: Option<String>,
+    graph_json: Option<String>,  // DependencyGraph JSON for lockfile generation
+    logger: &BuildLogger,
+) -> MoveCompilerResult {
+    #[cfg(debug_assertions)]
+    #[cfg(feature = "wasm")]
+    console_error_panic_hook::set_once();
+
+
+    // START ANSI SUPPORT
+    // Parse options early
+    let options: CompileOptions = options_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let (files, dep_packages) = match parse_package_inputs(files_json, dependencies_json) {
+        Ok(res) => res,
+        Err(e) => return MoveCompilerResult::err(e),
+    };
+
+    MoveCompilerResult::compiled(compile_package(&files, dep_packages, &options, graph_json.as_deref(), logger))
+}
+
+/// Apply `moveLock` to the active dependency groups: a mismatch fails the build, or is only
+/// logged under `ignoreLockMismatch`.
+fn check_lock_option(
+    dep_packages: &[PackageGroup],
+    inactive: &BTreeSet<String>,
+    options: &CompileOptions,
+    logger: &BuildLogger,
+) -> Result<(), String> {
+    let Some(lock) = &options.move_lock else {
+        return Ok(());
+    };
+    match check_move_lock(lock, options.move_lock_environment.as_deref(), dep_packages, inactive) {
+        Err(e) if options.ignore_lock_mismatch => {
+            logger.warn("setup", &e);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Compile the root package in `files` against `dep_packages`.
+///
+/// Shared by the JSON (`compile`) and binary (`compile_bytes`) entry points; errors are
+/// returned as rendered diagnostics.
+fn compile_package(
+    files: &BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    options: &CompileOptions,
+    graph_json: Option<&str>,  // DependencyGraph JSON for lockfile generation
+    logger: &BuildLogger,
+) -> Result<CompilationOutput, BuildFailure> {
+    let (root, targets) = prepare_package(files, dep_packages, options, logger)?;
+    compile_targets(root, targets, options, graph_json, logger, None)
+}
+
+/// Setup shared by every entry point that builds a package from files and dependency
+/// groups: the in-memory filesystem, input checks, mode and scope selection and the
+/// compiler targets read from the manifests.
+fn prepare_package(
+    files: &BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    options: &CompileOptions,
+    logger: &BuildLogger,
+) -> Result<(VfsPath, PackageTargets), String> {
+    logger.info("setup", "Loading package files");
+    prepare_ingested(Ingestion::of(files, dep_packages)?, options, logger)
+}
 
-            // Use Move utility to mirror CLI dependency ordering.
-            let module_set = Modules::new(module_infos.iter().map(|(_, m)| &m.module));
-            let ordered_ids: Vec<ModuleId> = match module_set.compute_topological_order() {
-                Ok(iter) => iter.map(|m| m.self_id()).collect(),
-                Err(e) => {
-                    return MoveCompilerResult {
-                        success: false,
-                        output: format!("Failed to compute module ordering: {}", e),
+/// `prepare_package` for inputs already written to the filesystem.
+fn prepare_ingested(
+    ingestion: Ingestion,
+    options: &CompileOptions,
+    logger: &BuildLogger,
+) -> Result<(VfsPath, PackageTargets), String> {
+    let (root, files, dep_packages) = ingestion.prepare(options, logger)?;
+    logger.info("parsing", "Reading root manifest");
+    let targets = build_package_targets(&files, &dep_packages, options, logger)?;
+    Ok((root, targets))
+}
+
+/// Run the compiler over prepared targets and assemble the output: verification,
+/// tree-shaking, module ordering, digest and lockfile. With `dependencies` (from
+/// `precompile_dependencies`), only the root target is compiled.
+fn compile_targets(
+    root: VfsPath,
+    targets: PackageTargets,
+    options: &CompileOptions,
+    graph_json: Option<&str>,  // DependencyGraph JSON for lockfile generation
+    logger: &BuildLogger,
+    dependencies: Option<Arc<FullyCompiledProgram>>,
+) -> Result<CompilationOutput, BuildFailure> {
+    let _color = ColorOverride::set(options.ansi_color());
+
+    let (prepared, all_targets) = PreparedRoot::new(targets, options)?;
+    logger.info(
+        "compiling",
+        &format!("Compiling root package with {} dependencies", all_targets.len() - 1),
+    );
+    let build = build_and_verify(
+        root,
+        all_targets,
+        options,
+        &prepared.protocol_config,
+        prepared.metered,
+        logger,
+        dependencies,
+    )?;
+    prepared.finish(build, options, graph_json, logger).map_err(BuildFailure::from)
+}
+
+/// Compile the dependency targets of `all_targets` (every target but the root) into a
+/// program that `compile_targets` can reuse while only the root package changes.
+fn precompile_dependencies(
+    root: VfsPath,
+    all_targets: &[PackagePaths<Symbol, String>],
+    options: &CompileOptions,
+) -> Result<Arc<FullyCompiledProgram>, BuildFailure> {
+    let _color = ColorOverride::set(options.ansi_color());
+    let flags = if options.test_mode { Flags::testing() } else { Flags::empty() };
+    match construct_pre_compiled_lib(all_targets[1..].to_vec(), None, flags, Some(root)) {
+        Ok(Ok(program)) => Ok(Arc::new(program)),
+        Ok(Err((files, diags))) => Err(render_errors(&files, diags, options)),
+        Err(e) => Err(format!("Compiler initialization error: {}", e).into()),
+    }
+}
+
+/// Everything `compile_targets` needs besides the compiler run itself.
+struct PreparedRoot {
+    root_package: RootPackage,
+    protocol_config: ProtocolConfig,
+    metered: bool,
+}
+
+impl PreparedRoot {
+    /// Split `targets` into the root's output metadata and the compiler targets.
+    fn new(
+        targets: PackageTargets,
+        options: &CompileOptions,
+    ) -> Result<(Self, Vec<PackagePaths<Symbol, String>>), String> {
+        let protocol_config = resolve_protocol_config(options.protocol_version, options.chain.as_deref())?;
+
+        let PackageTargets {
+            root_package_name,
+            targets: all_targets,
+            dependency_ids,
+            compilation_to_output,
+            known_compilation_addresses,
+            notes,
+            warnings,
+            address_order,
+            declared_dependencies,
+            unpublished_dependencies,
+            root_address_mapping,
+        } = targets;
+        let build_info = BuildInfo::new(&root_package_name, &all_targets[0], address_order, options);
+        let root_package = RootPackage {
+            name: root_package_name,
+            target: 0,
+            dependency_ids,
+            compilation_to_output,
+            known_compilation_addresses,
+            notes,
+            warnings,
+            build_info,
+            declared_dependencies,
+            unpublished_dependencies,
+            root_address_mapping,
+        };
+        let metered = options.metered_verification.unwrap_or(!options.test_mode);
+        Ok((PreparedRoot { root_package, protocol_config, metered }, all_targets))
+    }
+
+    fn finish(
+        self,
+        build: VerifiedBuild,
+        options: &CompileOptions,
+        graph_json: Option<&str>,
+        logger: &BuildLogger,
+    ) -> Result<CompilationOutput, String> {
+        if options.test_mode && !options.keep_test_only_deps {
+            check_test_only_links(&build.units, &build.files, &build.file_targets, self.root_package.target)?;
+        }
+        if !options.test_mode {
+            check_no_test_code(&build.units, &build.fn_info)?;
+        }
+        let verified = VerifiedUnits {
+            files: &build.files,
+            units: &build.units,
+            hints: &build.hints,
+            compiler_notes: &build.compiler_notes,
+            fn_info: &build.fn_info,
+            protocol_config: &self.protocol_config,
+            metered: self.metered,
+            core_packages: &build.core_packages,
+            file_targets: &build.file_targets,
+            diagnostics: build.diagnostics,
+        };
+        assemble_root_output(verified, self.root_package, build.warnings, options, graph_json, logger)
+    }
+}
+
+/// Reject root code outside tests that uses `#[test_only]` code of a dependency: calls to
+/// test-only functions, and types of test-only modules in datatype fields, function
+/// signatures, locals or type arguments.
+///
+/// The compiler applies one set of flags to every package, so a test-mode build also
+/// compiles the dependencies' test-only code, and root code can link against it. Such
+/// modules would fail to link on-chain, and the Sui verifier (skipped in test mode) does
+/// not catch it. Each offending use is reported with its source location.
+fn check_test_only_links(
+    units: &[AnnotatedCompiledModule],
+    files: &MappedFiles,
+    file_targets: &FileTargets,
+    root_target: usize,
+) -> Result<(), String> {
+    let is_root_unit = |unit: &AnnotatedCompiledModule| unit_target(files, file_targets, unit) == Some(root_target);
+    // Test-only dependency code: whole modules, and single functions.
+    let mut test_only_modules = BTreeSet::new();
+    let mut test_only_functions = BTreeSet::new();
+    for unit in units.iter().filter(|unit| !is_root_unit(unit)) {
+        let id = unit.named_module.module.self_id();
+        if unit.attributes.is_test_or_test_only() {
+            test_only_modules.insert(id);
+            continue;
+        }
+        for (_, name, info) in &unit.function_infos {
+            if info.attributes.is_test_or_test_only() {
+                test_only_functions.insert((id.clone(), name.to_string()));
+            }
+        }
+    }
+    if test_only_modules.is_empty() && test_only_functions.is_empty() {
+        return Ok(());
+    }
+
+    let mut violations = Vec::new();
+    for unit in units.iter().filter(|unit| is_root_unit(unit) && !unit.attributes.is_test_or_test_only()) {
+        let module = &unit.named_module.module;
+        let module_name = module.self_id().name().to_string();
+        // The first type of a test-only module `token` mentions, type arguments included.
+        let test_only_type = |token: &SignatureToken| {
+            token.preorder_traversal().find_map(|token| {
+                let idx = match token {
+                    SignatureToken::Datatype(idx) => *idx,
+                    SignatureToken::DatatypeInstantiation(inst) => inst.0,
+                    _ => return None,
+                };
+                let handle = module.datatype_handle_at(idx);
+                let owner = module.module_id_for_handle(module.module_handle_at(handle.module));
+                test_only_modules
+                    .contains(&owner)
+                    .then(|| format!("{}::{}", owner.to_canonical_string(true), module.identifier_at(handle.name)))
+            })
+        };
+        let signature_type = |idx: SignatureIndex| module.signature_at(idx).0.iter().find_map(test_only_type);
+        let location = |def_idx: usize, offset: usize| {
+            unit.named_module
+                .source_map
+                .get_code_location(FunctionDefinitionIndex(def_idx as u16), offset as CodeOffset)
+                .ok()
+                .and_then(|loc| {
+                    let (file, source) = files.get(&loc.file_hash())?;
+                    let line = source.get(..loc.start() as usize)?.matches('\n').count() + 1;
+                    Some(format!(" ({}:{})", file, line))
+                })
+                .unwrap_or_default()
+        };
+
+        let struct_fields = module.struct_defs().iter().flat_map(|def| {
+            def.fields().into_iter().flatten().map(move |field| (def.struct_handle, field))
+        });
+        let variant_fields = module.enum_defs().iter().flat_map(|def| {
+            def.variants.iter().flat_map(move |variant| variant.fields.iter().map(move |field| (def.enum_handle, field)))
+        });
+        for (handle, field) in struct_fields.chain(variant_fields) {
+            if let Some(ty) = test_only_type(&field.signature.0) {
+                violations.push(format!(
+                    "{}::{} has a field of test-only type {}",
+                    module_name,
+                    module.identifier_at(module.datatype_handle_at(handle).name),
+                    ty
+                ));
+            }
+        }
+
+        let test_functions: BTreeSet<String> = unit
+            .function_infos
+            .iter()
+            .filter(|(_, _, info)| info.attributes.is_test_or_test_only())
+            .map(|(_, name, _)| name.to_string())
+            .collect();
+        for (def_idx, def) in module.function_defs().iter().enumerate() {
+            let handle = module.function_handle_at(def.function);
+            let caller = module.identifier_at(handle.name);
+            if test_functions.contains(caller.as_str()) {
+                continue;
+            }
+            let locals = def.code.as_ref().map(|code| code.locals);
+            let declared_type = [handle.parameters, handle.return_].into_iter().chain(locals).find_map(signature_type);
+            if let Some(ty) = declared_type {
+                violations.push(format!("{}::{} uses test-only type {}{}", module_name, caller, ty, location(def_idx, 0)));
+            }
+            let Some(code) = &def.code else { continue };
+            for (offset, instruction) in code.code.iter().enumerate() {
+                let type_arguments = match instruction {
+                    Bytecode::CallGeneric(idx) => Some(module.function_instantiation_at(*idx).type_parameters),
+                    Bytecode::PackGeneric(idx) | Bytecode::UnpackGeneric(idx) => {
+                        Some(module.struct_instantiation_at(*idx).type_parameters)
                     }
+                    Bytecode::VecPack(idx, _)
+                    | Bytecode::VecUnpack(idx, _)
+                    | Bytecode::VecLen(idx)
+                    | Bytecode::VecImmBorrow(idx)
+                    | Bytecode::VecMutBorrow(idx)
+                    | Bytecode::VecPushBack(idx)
+                    | Bytecode::VecPopBack(idx)
+                    | Bytecode::VecSwap(idx) => Some(*idx),
+                    _ => None,
+                };
+                // Types already reported from the signature are not repeated per instruction.
+                if let Some(ty) = type_arguments.filter(|_| declared_type.is_none()).and_then(signature_type) {
+                    violations.push(format!(
+                        "{}::{} uses test-only type {}{}",
+                        module_name,
+                        caller,
+                        ty,
+                        location(def_idx, offset)
+                    ));
                 }
-            };
 
-            let mut ordered_modules: Vec<(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)> =
-                Vec::new();
-            for id in ordered_ids {
-                if let Some((_, module)) = module_infos.iter().find(|(mid, _)| *mid == id).cloned() {
-                    ordered_modules.push((id, module));
+                let handle_idx = match instruction {
+                    Bytecode::Call(idx) => *idx,
+                    Bytecode::CallGeneric(idx) => module.function_instantiation_at(*idx).handle,
+                    _ => continue,
+                };
+                let handle = module.function_handle_at(handle_idx);
+                let callee_module = module.module_id_for_handle(module.module_handle_at(handle.module));
+                let callee = module.identifier_at(handle.name).to_string();
+                let test_only = test_only_modules.contains(&callee_module)
+                    || test_only_functions.contains(&(callee_module.clone(), callee.clone()));
+                if !test_only {
+                    continue;
                 }
+                violations.push(format!(
+                    "{}::{} calls test-only {}::{}{}",
+                    module_name,
+                    caller,
+                    callee_module.to_canonical_string(true),
+                    callee,
+                    location(def_idx, offset)
+                ));
             }
-            for pair in module_infos {
-                if !ordered_modules.iter().any(|(mid, _)| *mid == pair.0) {
-                    ordered_modules.push(pair);
+        }
+    }
+    if violations.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Root code outside tests uses #[test_only] dependency code, which does not exist on-chain \
+         (mark the user #[test_only], or set keepTestOnlyDeps to allow it):\n  {}",
+        violations.join("\n  ")
+    ))
+}
+
+/// Fail if a `#[test]`/`#[test_only]` module or function survived a non-test build.
+///
+/// `Flags::empty()` makes the compiler drop that code before typing, so this never fires
+/// on a correct compiler; it stands between a filtering regression and test-only code
+/// (e.g. test natives) in a publish.
+fn check_no_test_code(units: &[AnnotatedCompiledModule], fn_info: &FnInfoMap) -> Result<(), String> {
+    let leaked: Vec<String> = units
+        .iter()
+        .flat_map(|unit| {
+            let id = unit.named_module.module.self_id();
+            let module = unit
+                .attributes
+                .is_test_or_test_only()
+                .then(|| format!("module {}", id.to_canonical_string(true)));
+            let functions = unit
+                .function_infos
+                .iter()
+                .filter(|(_, _, info)| info.attributes.is_test_or_test_only())
+                .map(|(_, name, _)| format!("function {}::{}", id.to_canonical_string(true), name))
+                .collect::<Vec<_>>();
+            module.into_iter().chain(functions)
+        })
+        .chain(
+            fn_info
+                .iter()
+                .filter(|(_, info)| info.is_test)
+                .map(|(key, _)| format!("function {}::{} (in fn_info)", key.mod_addr.to_canonical_string(true), key.fn_name)),
+        )
+        .collect();
+    if leaked.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "Test-only code reached the output of a non-test build:\n  {}",
+        leaked.join("\n  ")
+    ))
+}
+
+/// Units from a compiler run that passed verification, with rendered warnings.
+struct VerifiedBuild {
+    files: MappedFiles,
+    units: Vec<AnnotatedCompiledModule>,
+    fn_info: FnInfoMap,
+    warnings: Option<String>,
+    /// Note-level diagnostics, rendered one per entry; kept out of `warnings`.
+    compiler_notes: Vec<String>,
+    /// With `emitHints`: hints taken from the compiler's warnings.
+    hints: Vec<hints::Hint>,
+    /// Packages compiled with the Core flavor.
+    core_packages: BTreeSet<Symbol>,
+    file_targets: FileTargets,
+    /// Counts of the warnings in `warnings` and of those hidden.
+    diagnostics: DiagnosticCounts,
+}
+
+/// Source path -> index of the compiler target it belongs to (roots first), so units are
+/// attributed to their target rather than by package name.
+type FileTargets = BTreeMap<Symbol, usize>;
+
+/// Index of the target `unit` was compiled from; None for units of a precompiled program
+/// that are not targets of this run.
+fn unit_target(files: &MappedFiles, file_targets: &FileTargets, unit: &AnnotatedCompiledModule) -> Option<usize> {
+    let (path, _) = files.get(&unit.loc.file_hash())?;
+    file_targets.get(&Symbol::from(path.as_str())).copied()
+}
+
+/// Compile `all_targets` and run the bytecode verifiers over every unit.
+/// Errors are returned as rendered diagnostics.
+fn build_and_verify(
+    root: VfsPath,
+    all_targets: Vec<PackagePaths<Symbol, String>>,
+    options: &CompileOptions,
+    protocol_config: &ProtocolConfig,
+    metered: bool,
+    logger: &BuildLogger,
+    dependencies: Option<Arc<FullyCompiledProgram>>,
+) -> Result<VerifiedBuild, BuildFailure> {
+    let parsed = ParsedBuild::start(root, all_targets, options, dependencies)?;
+    let typed = parsed.check_types(options)?;
+    let compiled = typed.generate_bytecode(options)?;
+    compiled.verify(options, protocol_config, metered, logger)
+}
+
+/// The stages of `build_and_verify`, so `compile_async` can yield between them.
+struct ParsedBuild {
+    files: MappedFiles,
+    compiler: SteppedCompiler<'static, { move_compiler::PASS_PARSER }>,
+    core_packages: BTreeSet<Symbol>,
+    dependency_packages: BTreeSet<Symbol>,
+    file_targets: FileTargets,
+    dependencies: Option<Arc<FullyCompiledProgram>>,
+}
+
+struct TypedBuild {
+    files: MappedFiles,
+    compiler: SteppedCompiler<'static, { move_compiler::PASS_TYPING }>,
+    core_packages: BTreeSet<Symbol>,
+    dependency_packages: BTreeSet<Symbol>,
+    file_targets: FileTargets,
+    dependencies: Option<Arc<FullyCompiledProgram>>,
+}
+
+struct CompiledBuild {
+    files: MappedFiles,
+    units: Vec<AnnotatedCompiledModule>,
+    warning_diags: Diagnostics,
+    core_packages: BTreeSet<Symbol>,
+    dependency_packages: BTreeSet<Symbol>,
+    file_targets: FileTargets,
+}
+
+/// Drop warnings matching any of `suppressed`: a code as rendered (`W09001`, or with its
+/// prefix, `Lint W99001`) or a message such as `unused variable`. Filtering happens on the
+/// diagnostics themselves, so it applies to every rendering of them.
+///
+/// Warnings from dependency packages never reach this point: the compiler does not
+/// report them, as with the CLI.
+fn suppress_warnings(diags: Diagnostics, suppressed: &[String]) -> Diagnostics {
+    if suppressed.is_empty() {
+        return diags;
+    }
+    let kept: Vec<_> = diags
+        .into_vec()
+        .into_iter()
+        .filter(|diag| {
+            let info = diag.info();
+            if info.severity() != Severity::Warning {
+                return true;
+            }
+            let code = format!("W{:02}{:03}", info.category(), info.code());
+            let prefixed = info.external_prefix().map(|prefix| format!("{} {}", prefix, code));
+            !suppressed.iter().any(|entry| {
+                entry.eq_ignore_ascii_case(&code)
+                    || prefixed.as_deref().is_some_and(|prefixed| entry.eq_ignore_ascii_case(prefixed))
+                    || entry.eq_ignore_ascii_case(info.message())
+            })
+        })
+        .collect();
+    Diagnostics::from(kept)
+}
+
+/// Notes for dependencies whose files have parse errors in `rendered`: a package parsed
+/// with the wrong edition (e.g. a Move.toml claiming 2024 over legacy sources) fails with
+/// many errors that do not name the edition.
+fn edition_hints(rendered: &str, dependency_editions: &[(Symbol, Edition, Vec<Symbol>)]) -> Vec<String> {
+    dependency_editions
+        .iter()
+        .filter(|(_, _, paths)| paths.iter().any(|path| rendered.contains(path.as_str())))
+        .map(|(name, edition, _)| {
+            format!(
+                "dependency '{}' was parsed as edition {}. If its sources use another edition, \
+                 set `edition` on its dependency entry or pass `editionOverrides: {{ \"{}\": \"<edition>\" }}`.",
+                name, edition, name
+            )
+        })
+        .collect()
+}
+
+/// `diags` rendered as the compiler reports them, with their counts.
+fn render_diagnostics(files: &MappedFiles, diags: Diagnostics, options: &CompileOptions) -> (String, DiagnosticCounts) {
+    let diags = diags.into_vec();
+    let counts = DiagnosticCounts::of(&diags);
+    let buffer = report_diagnostics_to_buffer(files, Diagnostics::from(diags), options.ansi_color());
+    (String::from_utf8_lossy(&buffer).to_string(), counts)
+}
+
+/// Rendered diagnostics per file key of their primary location, for `perModuleErrors`,
+/// with the counts over every file.
+fn diagnostics_by_file(
+    files: &MappedFiles,
+    diags: Diagnostics,
+    options: &CompileOptions,
+) -> (BTreeMap<String, String>, DiagnosticCounts) {
+    let mut grouped: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for diag in diags.into_vec() {
+        let (_, _, (loc, _), _, _) = diag.clone().into_codespan_format();
+        let file = files.get(&loc.file_hash()).map(|(file, _)| file.to_string()).unwrap_or_default();
+        grouped.entry(file).or_default().push(diag);
+    }
+    let mut counts = DiagnosticCounts::default();
+    let by_file = grouped
+        .into_iter()
+        .map(|(file, diags)| {
+            let (rendered, file_counts) = render_diagnostics(files, Diagnostics::from(diags), options);
+            counts.add(file_counts);
+            (file, rendered)
+        })
+        .collect();
+    (by_file, counts)
+}
+
+/// Failure for compiler errors: the rendered report without `suppressWarnings` matches,
+/// and under `perModuleErrors` the same diagnostics grouped by file key.
+fn render_errors(files: &MappedFiles, diags: Diagnostics, options: &CompileOptions) -> BuildFailure {
+    let diags = diags.into_vec();
+    let unbound = unbound_addresses(&diags);
+    let hints = framework_address_hints(&unbound);
+    let produced = DiagnosticCounts::of(&diags).warnings;
+    let diags = suppress_warnings(Diagnostics::from(diags), &options.suppress_warnings);
+    let mut failure = if options.per_module_errors {
+        let (by_file, counts) = diagnostics_by_file(files, diags, options);
+        let message = by_file.values().map(String::as_str).collect::<String>();
+        BuildFailure { counts, errors_by_file: Some(by_file), ..BuildFailure::from(message) }
+    } else {
+        let (message, counts) = render_diagnostics(files, diags, options);
+        BuildFailure { counts, ..BuildFailure::from(message) }
+    };
+    failure.counts.suppressed_warnings = produced - failure.counts.warnings;
+    failure.unbound_addresses = unbound;
+    for hint in hints {
+        failure.message.push_str(&format!("\nnote: {}", hint));
+    }
+    failure
+}
+
+impl ParsedBuild {
+    /// Start compiling `all_targets`. With precompiled `dependencies`, only the root target
+    /// (the first) is parsed; the dependencies' ASTs and units come from the program.
+    fn start(
+        root: VfsPath,
+        all_targets: Vec<PackagePaths<Symbol, String>>,
+        options: &CompileOptions,
+        dependencies: Option<Arc<FullyCompiledProgram>>,
+    ) -> Result<Self, BuildFailure> {
+        let core_packages: BTreeSet<Symbol> = all_targets
+            .iter()
+            .filter_map(|target| target.name.as_ref())
+            .filter(|(_, config)| config.flavor == Flavor::Core)
+            .map(|(name, _)| *name)
+            .collect();
+        let dependency_packages: BTreeSet<Symbol> = all_targets
+            .iter()
+            .filter_map(|target| target.name.as_ref())
+            .filter(|(_, config)| config.is_dependency)
+            .map(|(name, _)| *name)
+            .collect();
+        let dependency_editions: Vec<(Symbol, Edition, Vec<Symbol>)> = all_targets
+            .iter()
+            .filter_map(|target| {
+                let (name, config) = target.name.as_ref()?;
+                config.is_dependency.then(|| (*name, config.edition, target.paths.clone()))
+            })
+            .collect();
+        let file_targets: FileTargets = all_targets
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, target)| target.paths.iter().map(move |path| (*path, idx)))
+            .collect();
+        let flags = if options.test_mode {
+            Flags::testing()
+        } else {
+            Flags::empty()
+        };
+        let mut all_targets = all_targets;
+        let mut root_parse = None;
+        if dependencies.is_some() {
+            all_targets.truncate(1);
+        } else if all_targets.len() > 1 {
+            root_parse = Some(Self::parse_root(root.clone(), all_targets[0].clone(), flags.clone(), options)?);
+            // The root keeps its package config and named addresses but is not parsed again.
+            all_targets[0].paths.clear();
+        }
+
+        let mut compiler = Compiler::from_package_paths(
+            Some(root),
+            all_targets,
+            Vec::new(), // No bytecode dependencies in this flow
+        )
+        .map_err(|e| format!("Failed to create compiler: {}", e))?;
+        if let Some(dependencies) = &dependencies {
+            compiler = compiler.set_pre_compiled_lib(dependencies.clone());
+        }
+
+        // Note: Silence warnings is handled via post-processing of diagnostics in this simplified builder.
+        // Lint flags are not exposed via Flags directly in this version of move-compiler.
+        let (files, res) = compiler
+            .set_flags(flags)
+            .run::<{ move_compiler::PASS_PARSER }>()
+            .map_err(|e| format!("Compiler initialization error: {}", e))?;
+        match res {
+            Ok(compiler) => {
+                let (compiler, files) = match root_parse {
+                    Some((root_files, root_program)) => {
+                        // The root target is first in both programs, so its definitions keep
+                        // their named address map index.
+                        let (compiler, mut program) = compiler.into_ast();
+                        program.source_definitions.splice(0..0, root_program.source_definitions);
+                        let mut files = files;
+                        files.extend(root_files);
+                        (compiler.at_parser(program), files)
+                    }
+                    None => (compiler, files),
+                };
+                Ok(ParsedBuild { files, compiler, core_packages, dependency_packages, file_targets, dependencies })
+            }
+            Err((_severity, diags)) => {
+                let mut failure = render_errors(&files, diags, options);
+                failure.edition_hints = edition_hints(&failure.message, &dependency_editions);
+                for hint in &failure.edition_hints {
+                    failure.message.push_str(&format!("\nnote: {}", hint));
                 }
+                Err(failure)
             }
-            let module_infos = ordered_modules;
-
-            // Serialize in compiler-provided order (already dependency-topological).
-            let mut modules = vec![];
-            let mut module_bytes = vec![];
-            for (_idx, (id, module)) in module_infos.iter().enumerate() {
-                let bytes = module.serialize();
-                module_bytes.push(bytes.clone());
-                modules.push(general_purpose::STANDARD.encode(&bytes));
+        }
+    }
+
+    /// Parse the root package alone, so a syntax error in it is reported before every
+    /// dependency is parsed (and then thrown away). Parsing needs no dependency, and in the
+    /// edit loop the root is usually a handful of files against thousands of framework lines.
+    /// On success the root's files and AST are spliced into the dependencies' program, so
+    /// the root is parsed once either way.
+    fn parse_root(
+        root: VfsPath,
+        root_target: PackagePaths<Symbol, String>,
+        flags: Flags,
+        options: &CompileOptions,
+    ) -> Result<(MappedFiles, move_compiler::parser::ast::Program), BuildFailure> {
+        let compiler = Compiler::from_package_paths(Some(root), vec![root_target], Vec::new())
+            .map_err(|e| format!("Failed to create compiler: {}", e))?;
+        let (files, res) = compiler
+            .set_flags(flags)
+            .run::<{ move_compiler::PASS_PARSER }>()
+            .map_err(|e| format!("Compiler initialization error: {}", e))?;
+        match res {
+            Ok(compiler) => Ok((files, compiler.into_ast().1)),
+            Err((_severity, diags)) => Err(render_errors(&files, diags, options)),
+        }
+    }
+
+    fn check_types(self, options: &CompileOptions) -> Result<TypedBuild, BuildFailure> {
+        let ParsedBuild { files, compiler, core_packages, dependency_packages, file_targets, dependencies } = self;
+        match compiler.run::<{ move_compiler::PASS_TYPING }>() {
+            Ok(compiler) => {
+                Ok(TypedBuild { files, compiler, core_packages, dependency_packages, file_targets, dependencies })
             }
+            Err((_severity, diags)) => Err(render_errors(&files, diags, options)),
+        }
+    }
+}
 
-            // Use dependency IDs (Already filtered by Tree Shaking above)
-            // let dependency_ids_vec = dependency_ids_vec; // Already defined
-            
-            // Canonical Digest Calculation
-            let dep_object_ids: Vec<sui_types::base_types::ObjectID> = dependency_ids_vec.iter()
-                .map(|bytes| sui_types::base_types::ObjectID::new(*bytes))
-                .collect();
-            
-            let package_digest = sui_types::move_package::MovePackage::compute_digest_for_modules_and_deps(
-                &module_bytes,
-                &dep_object_ids,
-                true // hash_modules matches default behavior usually
-            );
+impl TypedBuild {
+    fn generate_bytecode(self, options: &CompileOptions) -> Result<CompiledBuild, BuildFailure> {
+        let TypedBuild { mut files, compiler, core_packages, dependency_packages, file_targets, dependencies } = self;
+        match compiler.run::<{ move_compiler::PASS_COMPILATION }>() {
+            Ok(compiler) => {
+                let (mut units, warning_diags) = compiler.into_compiled_units();
+                // Precompiled dependencies are linked against, not emitted: add their units
+                // back for verification and tree-shaking.
+                if let Some(dependencies) = dependencies {
+                    units.extend(dependencies.compiled.iter().cloned());
+                    files.extend(dependencies.files.clone());
+                }
+                Ok(CompiledBuild { files, units, warning_diags, core_packages, dependency_packages, file_targets })
+            }
+            Err((_severity, diags)) => Err(render_errors(&files, diags, options)),
+        }
+    }
+}
 
-            // ORIGINAL SOURCE: root_package.rs:251 - save_lockfile_to_disk()
-            // Generate V4 lockfile using DependencyGraph JSON from TypeScript
-            let lockfile = match &graph_json {
-                Some(graph) => generate_lockfile_v4_internal(graph),
-                None => String::new(),  // No graph provided, skip lockfile
-            };
+impl CompiledBuild {
+    fn verify(
+        self,
+        options: &CompileOptions,
+        protocol_config: &ProtocolConfig,
+        metered: bool,
+        logger: &BuildLogger,
+    ) -> Result<VerifiedBuild, BuildFailure> {
+        let CompiledBuild { files, units, warning_diags, core_packages, dependency_packages, file_targets } = self;
+        let warning_diags = warning_diags.into_vec();
+        let hints = if options.emit_hints {
+            hints::diagnostic_hints(&warning_diags, &files)
+        } else {
+            Vec::new()
+        };
+        let produced = DiagnosticCounts::of(&warning_diags).warnings;
+        let warning_diags = suppress_warnings(Diagnostics::from(warning_diags), &options.suppress_warnings);
+        // Notes are guidance, not warnings: they never fail the build and have their own switch.
+        let (note_diags, warning_diags): (Vec<_>, Vec<_>) = warning_diags
+            .into_vec()
+            .into_iter()
+            .partition(|diag| diag.info().severity() == Severity::Note);
+        let warning_diags = Diagnostics::from(warning_diags);
+        let compiler_notes = if options.silence_notes {
+            Vec::new()
+        } else {
+            note_diags
+                .into_iter()
+                .map(|diag| render_diagnostics(&files, Diagnostics::from(vec![diag]), options).0.trim_end().to_string())
+                .collect()
+        };
+        let (warnings, mut diagnostics) = if !options.silence_warnings && !warning_diags.is_empty() {
+            let (rendered, counts) = render_diagnostics(&files, warning_diags, options);
+            (Some(rendered), counts)
+        } else {
+            (None, DiagnosticCounts::default())
+        };
+        // The warnings not rendered were hidden.
+        diagnostics.suppressed_warnings = produced - diagnostics.warnings;
+        // Like `-D warnings`: surfaced warnings fail the build; silenced ones do not.
+        if options.warnings_as_errors {
+            if let Some(warnings) = warnings {
+                return Err(BuildFailure { counts: diagnostics, ..BuildFailure::from(warnings) });
+            }
+        }
 
-            let output_data = CompilationOutput {
-                modules,
-                dependencies: dependency_ids_vec
-                    .iter()
-                    .map(|bytes| AccountAddress::new(*bytes).to_canonical_string(true))
-                    .collect(),
-                digest: package_digest.to_vec(),
-                lockfile,
-                warnings: {
-                    if !options.silence_warnings && !warning_diags.is_empty() {
-                        let warning_buffer = move_compiler::diagnostics::report_diagnostics_to_buffer(&compiler_files, warning_diags, ansi_color);
-                        String::from_utf8(warning_buffer).ok()
+        check_module_cycles(units.iter().map(|unit| &unit.named_module))?;
+
+        // VERIFICATION STEP (Ported from sui-move-build)
+        logger.info("verifying", &format!("Verifying {} compiled modules", units.len()));
+        let fn_info = fn_info(&units);
+        let modules: Vec<&NamedCompiledModule> = units.iter().map(|unit| &unit.named_module).collect();
+        if let Err(e) = verify_bytecode(
+            &modules,
+            &fn_info,
+            protocol_config,
+            options.test_mode,
+            metered,
+            &core_packages,
+            &dependency_packages,
+        ) {
+            return Err(format!("Bytecode Verification Failed: {}", e).into());
+        }
+
+        Ok(VerifiedBuild {
+            files,
+            units,
+            fn_info,
+            warnings,
+            compiler_notes,
+            hints,
+            core_packages,
+            file_targets,
+            diagnostics,
+        })
+    }
+}
+
+/// A compilation that passed verification, shared by every root package built from it.
+#[derive(Clone, Copy)]
+struct VerifiedUnits<'a> {
+    files: &'a MappedFiles,
+    units: &'a [AnnotatedCompiledModule],
+    hints: &'a [hints::Hint],
+    compiler_notes: &'a [String],
+    fn_info: &'a FnInfoMap,
+    protocol_config: &'a ProtocolConfig,
+    metered: bool,
+    core_packages: &'a BTreeSet<Symbol>,
+    file_targets: &'a FileTargets,
+    diagnostics: DiagnosticCounts,
+}
+
+/// Per-root data carried from `PackageTargets` into output assembly.
+struct RootPackage {
+    name: String,
+    /// Index of the root's target in the compilation.
+    target: usize,
+    dependency_ids: Vec<[u8; 32]>,
+    compilation_to_output: BTreeMap<AccountAddress, AccountAddress>,
+    known_compilation_addresses: std::collections::HashSet<AccountAddress>,
+    notes: Vec<String>,
+    /// Builder warnings so far; assembly adds its own.
+    warnings: Vec<String>,
+    build_info: BuildInfo,
+    /// Names declared in the root manifest's `[dependencies]`.
+    declared_dependencies: Vec<String>,
+    unpublished_dependencies: Vec<String>,
+    root_address_mapping: Option<(AccountAddress, AccountAddress)>,
+}
+
+/// Assemble one root package's output from a verified compilation: tree-shaking,
+/// module ordering, serialization, digest and lockfile.
+fn assemble_root_output(
+    verified: VerifiedUnits<'_>,
+    root: RootPackage,
+    warnings: Option<String>,
+    options: &CompileOptions,
+    graph_json: Option<&str>,
+    logger: &BuildLogger,
+) -> Result<CompilationOutput, String> {
+    let VerifiedUnits {
+        files,
+        units,
+        hints,
+        compiler_notes,
+        fn_info,
+        protocol_config,
+        metered,
+        core_packages,
+        file_targets,
+        mut diagnostics,
+    } = verified;
+    let RootPackage {
+        name: _,
+        target,
+        dependency_ids,
+        compilation_to_output,
+        known_compilation_addresses,
+        mut notes,
+        warnings: mut builder_warnings,
+        build_info,
+        declared_dependencies,
+        unpublished_dependencies,
+        root_address_mapping,
+    } = root;
+    notes.extend(compiler_notes.iter().cloned());
+
+    // NEW: Filter modules to only include those that are part of the root package source files.
+    
+    // Tree Shaking / Usage-Based Dependency Filtering (Strict Parity with Sui CLI)
+    // The official CLI `dump_bytecode_as_base64` logic only retains published dependencies
+    // that are EITHER:
+    // 1. Immediately used by the root package.
+    // 2. Used by other *published* dependencies (transitive closure).
+    // Crucially, it IGNORES usages from unpublished (source) dependencies.
+    
+    logger.info("tree-shaking", "Computing reachable dependencies");
+
+    // 1. Identify Published Addresses (Compilation IDs used in bytecode)
+    let published_addresses = known_compilation_addresses;
+
+    // 2. Compute Kept Addresses via Rooted Graph Traversal (Strict Usage)
+    // Start only from Root modules (the output targets).
+    // Traverse to find all reachable dependencies (both Source and Published).
+    
+    // We keep OUTPUT addresses
+    let mut kept_output_addresses = std::collections::HashSet::new();
+    // We traverse COMPILATION addresses
+    let mut visited_compilation_addresses = std::collections::HashSet::new();
+    
+    // Queue for traversal
+    // contains ModuleId to look up in units or published deps
+    let mut worklist_source_units = Vec::new();
+    let mut worklist_published_addresses = Vec::new();
+
+    // 2a. Initialize with Root Modules
+    let is_root_unit = |unit: &AnnotatedCompiledModule| unit_target(files, file_targets, unit) == Some(target);
+    for unit in units {
+        if is_root_unit(unit) {
+            worklist_source_units.push(unit);
+        }
+    }
+
+    use std::fmt::Write;
+
+
+    // Helper to find a unit by ID (for traversing usage of Source Dependencies)
+    
+    // Published addresses reached without an output mapping, with the reported warning.
+    let mut tree_shake_warnings: Vec<(AccountAddress, String)> = Vec::new();
+    let mut missing_mapping = |addr: AccountAddress| {
+        if tree_shake_warnings.iter().any(|(missing, _)| *missing == addr) {
+            return;
+        }
+        let warning = format!(
+            "Published dependency address {} has no output mapping; it is left out of `dependencies`, \
+             so the package cannot be published as is",
+            addr.to_hex_literal()
+        );
+        logger.warn("tree-shaking", &warning);
+        tree_shake_warnings.push((addr, warning));
+    };
+    let mut visited_source_units = std::collections::HashSet::new();
+    for u in &worklist_source_units {
+        visited_source_units.insert(u.named_module.module.self_id());
+    }
+
+    while !worklist_source_units.is_empty() {
+        let current_batch = worklist_source_units.split_off(0);
+        
+        for unit in current_batch {
+            let module = &unit.named_module.module;
+            
+            // Traverse immediate dependencies (Imports)
+            for dep_id in module.immediate_dependencies() {
+                let addr = *dep_id.address();
+                
+                if published_addresses.contains(&addr) {
+                    // Link to Published Package
+                    // Map compilation address (addr) to output address
+                    if let Some(output_addr) = compilation_to_output.get(&addr) {
+                        if kept_output_addresses.insert(*output_addr) {
+
+                            // We need to traverse the dependencies of this published package too.
+                            // Published packages are identified by their COMPILATION address in 'units'
+                            if visited_compilation_addresses.insert(addr) {
+                                worklist_published_addresses.push(addr);
+                            }
+                        }
                     } else {
-                        None
+                        missing_mapping(addr);
                     }
-                },
-            };
+                } else {
+                    // Link to Source Package (e.g. multisig)
+                    // Find the unit that corresponds to this dependency
+                    // Search in 'units'
+                    for valid_unit in units {
+                        let valid_id = valid_unit.named_module.module.self_id();
+                        if valid_id == dep_id {
+                            // Found the source module being used!
+                            if visited_source_units.insert(valid_id) {
+                                worklist_source_units.push(valid_unit);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 
-            MoveCompilerResult {
-                success: true,
-                output: serde_json::to_string(&output_data).unwrap_or_default(),
+    // 2b. Transitive Closure for Published Packages
+    // If we keep Pyth, we must keep Wormhole (Pyth's dependency).
+    // We search for modules in 'units' (which contains all compiled deps) matching the address.
+    while let Some(addr) = worklist_published_addresses.pop() {
+        // Find all modules belonging to this published address (Compilation ID) in our compiled set
+        for unit in units {
+            if *unit.named_module.module.address() == addr {
+                // This unit belongs to a kept published package.
+                // Check ITS dependencies.
+                for dep_id in unit.named_module.module.immediate_dependencies() {
+                    let dep_addr = *dep_id.address();
+                     if published_addresses.contains(&dep_addr) {
+                        if let Some(output_addr) = compilation_to_output.get(&dep_addr) {
+                            if kept_output_addresses.insert(*output_addr) {
+                                if visited_compilation_addresses.insert(dep_addr) {
+                                    worklist_published_addresses.push(dep_addr);
+                                }
+                            }
+                        } else {
+                            missing_mapping(dep_addr);
+                        }
+                    }
+                    // Note: Published modules should not depend on Source modules
+                }
+            }
+        }
+    }
+
+    let tree_shake_warnings: Vec<String> =
+        tree_shake_warnings.into_iter().map(|(_, warning)| warning).collect();
+    if options.strict_tree_shaking && !tree_shake_warnings.is_empty() {
+        return Err(tree_shake_warnings.join("\n"));
+    }
+
+    // 2c. Source dependencies the root still uses must be published before the root can be
+    // (the CLI refuses to dump bytecode for publish with an unpublished dependency).
+    // Their modules are not bundled into the output, so publishing would fail on chain.
+    if !options.test_mode {
+        let mut unpublished_uses = BTreeMap::<String, Vec<String>>::new();
+        for unit in units {
+            let Some(package) = unit.named_module.package_name else { continue };
+            let id = unit.named_module.module.self_id();
+            if unpublished_dependencies.iter().any(|name| name.as_str() == package.as_str())
+                && visited_source_units.contains(&id)
+            {
+                unpublished_uses.entry(package.to_string()).or_default().push(id.name().to_string());
+            }
+        }
+        for (package, modules) in unpublished_uses {
+            let message = format!(
+                "Dependency '{}' has no published address (no publishedIdForOutput, published-at or \
+                 [addresses] entry of its own), but the root uses its modules: {}. \
+                 Publish it, or give it an address, before publishing this package",
+                package,
+                modules.join(", ")
+            );
+            if !options.allow_unpublished_dependencies {
+                return Err(message);
+            }
+            logger.warn("tree-shaking", &message);
+            builder_warnings.push(message);
+        }
+    }
+
+    // 3. Filter dependency IDs
+    // FIX: Do NOT filter dependencies based on usage. CLI uses all resolved dependencies (Linkage Table)
+    // for digest calculation. Filtering causes digest mismatch.
+    //
+    // ORIGINAL SOURCE REFERENCE:
+    // - move-package-alt/src/graph/linkage.rs:40 - LinkageTable maps OriginalID -> PackageInfo
+    // - sui-move-build/src/lib.rs - dump_bytecode_as_base64() uses complete linkage table
+    // - Digest calculation includes ALL dependencies in the linkage table, not just used ones
+    let mut dependency_ids_vec: Vec<[u8; 32]> = dependency_ids.iter().cloned().collect();
+
+    // Sort dependency IDs to ensure deterministic order (matches CLI)
+    dependency_ids_vec.sort();
+
+    // Report (but keep) declared dependencies that no root or kept published module references.
+    let pruned_dependencies: Vec<String> = dependency_ids_vec
+        .iter()
+        .map(|bytes| AccountAddress::new(*bytes))
+        .filter(|addr| !kept_output_addresses.contains(addr))
+        .map(|addr| addr.to_canonical_string(true))
+        .collect();
+
+    // Root `[dependencies]` packages that the root never reaches. In test mode, packages
+    // reached only through #[test]/#[test_only] root modules are reported separately.
+    let root_units: Vec<&AnnotatedCompiledModule> = units.iter().filter(|u| is_root_unit(u)).collect();
+    let reachable = reachable_dependency_packages(units, root_units.clone());
+    let reachable_outside_tests = if options.test_mode {
+        let non_test_roots = root_units
+            .iter()
+            .copied()
+            .filter(|u| !u.attributes.is_test_or_test_only())
+            .collect();
+        reachable_dependency_packages(units, non_test_roots)
+    } else {
+        reachable.clone()
+    };
+    let mut unused_dependencies = std::collections::BTreeSet::new();
+    let mut test_only_dependencies = std::collections::BTreeSet::new();
+    for unit in units.iter().filter(|u| !is_root_unit(u)) {
+        let Some(name) = unit.named_module.package_name else { continue };
+        // Implicit framework packages are always provided; not the user's to prune.
+        if sui_types::is_system_package(*unit.named_module.module.address()) {
+            continue;
+        }
+        let name = name.to_string();
+        if !reachable.contains(&name) {
+            // Transitive packages are not the root manifest's to remove.
+            if declared_dependencies.contains(&name) {
+                unused_dependencies.insert(name);
+            }
+        } else if !reachable_outside_tests.contains(&name) {
+            test_only_dependencies.insert(name);
+        }
+    }
+
+    // In the VFS, root files are top-level keys in the `files` map provided to compile_impl.
+    // The compiler returns all units because we passed dependencies as targets.
+    // let root_file_names: std::collections::HashSet<&str> = files.keys().map(|s| s.as_str()).collect();
+
+    // Handle warnings
+    // Options parsed early
+
+
+
+    // Build module list with IDs
+    let mut module_infos: Vec<(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)> =
+        Vec::new();
+    for unit in units {
+        if is_root_unit(unit) {
+            let id = unit.named_module.module.self_id();
+            module_infos.push((id, unit.named_module.clone()));
+        }
+    }
+
+    // Ordering rule (the same set of modules always yields the same order, whatever order
+    // the files were given in): dependency order from `Modules::compute_topological_order`,
+    // as the CLI does. `Modules` builds its graph from a map keyed by `ModuleId`, so modules
+    // with no edge between them are placed by their (address, name) key, never by
+    // compiler/input order. `module_infos` is sorted up front so nothing below depends on
+    // compiler unit order. A cycle cannot reach this point: the compiler rejects cyclic
+    // module dependencies and `check_module_cycles` cyclic packages.
+    module_infos.sort_by(|(a, _), (b, _)| (a.address(), a.name()).cmp(&(b.address(), b.name())));
+    let module_set = Modules::new(module_infos.iter().map(|(_, m)| &m.module));
+    let ordered_ids: Vec<ModuleId> = module_set
+        .compute_topological_order()
+        .map_err(|e| format!("Failed to compute module dependency order: {}", e))?
+        .map(|m| m.self_id())
+        .collect();
+
+    let mut ordered_modules: Vec<(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)> =
+        Vec::new();
+    for id in ordered_ids {
+        if let Some((_, module)) = module_infos.iter().find(|(mid, _)| *mid == id).cloned() {
+            ordered_modules.push((id, module));
+        }
+    }
+    for pair in module_infos {
+        if !ordered_modules.iter().any(|(mid, _)| *mid == pair.0) {
+            ordered_modules.push(pair);
+        }
+    }
+    let module_infos = ordered_modules;
+
+    let mut module_infos = match &options.substitute_root_address {
+        Some(address) => {
+            logger.info("verifying", &format!("Re-verifying root modules at {}", address));
+            substitute_root_address(module_infos, address, &fn_info, &protocol_config, options.test_mode, metered, core_packages)?
+        }
+        None => module_infos,
+    };
+
+    // Serialize in the dependency order computed above, at the requested binary format
+    // version or the newest the protocol accepts.
+    let max_version = protocol_config.move_binary_format_version();
+    let bytecode_version = options.bytecode_version.unwrap_or(max_version);
+    if !(VERSION_MIN..=VERSION_MAX).contains(&bytecode_version) {
+        return Err(format!(
+            "Invalid bytecodeVersion {}: the serializer produces versions {} to {}",
+            bytecode_version, VERSION_MIN, VERSION_MAX
+        ));
+    }
+    if bytecode_version > max_version {
+        return Err(format!(
+            "Invalid bytecodeVersion {}: protocol version {} accepts at most {}, so validators would reject the package",
+            bytecode_version,
+            protocol_config.version.as_u64(),
+            max_version
+        ));
+    }
+    logger.info("serializing", &format!("Serializing {} root modules", module_infos.len()));
+    let mut module_bytes = vec![];
+    let mut module_names = vec![];
+    for (id, module) in module_infos.iter_mut() {
+        // Later steps (digest, cost estimate) re-serialize at the module's own version.
+        module.module.version = bytecode_version;
+        let mut bytes = Vec::new();
+        module
+            .module
+            .serialize_with_version(bytecode_version, &mut bytes)
+            .map_err(|e| {
+                format!(
+                    "Failed to serialize module '{}' at bytecode version {}: {}",
+                    id.name(),
+                    bytecode_version,
+                    e
+                )
+            })?;
+        module_bytes.push(bytes);
+        module_names.push(id.name().to_string());
+    }
+    let module_sizes: Vec<usize> = module_bytes.iter().map(|bytes| bytes.len()).collect();
+    let module_versions = vec![bytecode_version; module_bytes.len()];
+    let module_count = module_bytes.len();
+    let total_bytecode_bytes = module_sizes.iter().sum();
+    let interface = options.emit_interface.then(|| {
+        // Print each address as a root named address bound to it (the first by name).
+        let mut address_names = BTreeMap::<AccountAddress, String>::new();
+        for (name, address) in &build_info.address_alias_instantiation {
+            if let Ok(address) = AccountAddress::from_hex_literal(address) {
+                address_names.entry(address).or_insert_with(|| name.clone());
             }
         }
-        Err(diags) => {
-            let error_buffer = move_compiler::diagnostics::report_diagnostics_to_buffer(&compiler_files, diags, ansi_color);
-            MoveCompilerResult {
-                success: false,
-                output: String::from_utf8_lossy(&error_buffer).to_string(),
+        module_infos
+            .iter()
+            .map(|(_, module)| interface::module_interface(&module.module, &address_names, false))
+            .collect()
+    });
+
+    let hints = options.emit_hints.then(|| {
+        let mut hints = hints.to_vec();
+        // Test-only functions are run by the test runner, not called; skip them in test mode.
+        if !options.test_mode {
+            for (_, module) in &module_infos {
+                hints.extend(hints::unreachable_function_hints(module, files));
             }
         }
+        hints
+    });
+    let friends = options.with_friends.then(|| {
+        module_infos
+            .iter()
+            .map(|(id, module)| {
+                let friends = module
+                    .module
+                    .immediate_friends()
+                    .iter()
+                    .map(|friend| friend.to_canonical_string(true))
+                    .collect();
+                (id.name().to_string(), friends)
+            })
+            .collect()
+    });
+    let entry_points = options.emit_entry_points.then(|| {
+        // Root modules last, so their (possibly substituted) address wins.
+        let layouts = entry_points::Layouts::new(
+            units
+                .iter()
+                .map(|unit| &unit.named_module.module)
+                .chain(module_infos.iter().map(|(_, module)| &module.module)),
+        );
+        module_infos
+            .iter()
+            .flat_map(|(_, module)| entry_points::entry_points(&module.module, &layouts))
+            .collect()
+    });
+    let constants = options.emit_constants.then(|| {
+        module_infos
+            .iter()
+            .map(|(id, module)| (id.name().to_string(), constants::module_constants(module)))
+            .collect()
+    });
+    let debug_bundle = options.emit_debug_bundle.then(|| {
+        module_infos
+            .iter()
+            .filter_map(|(_, module)| debug_bundle::debug_bundle(module, files))
+            .collect()
+    });
+
+    let limit_mode = options
+        .package_limits
+        .as_deref()
+        .unwrap_or(if options.test_mode { "off" } else { "error" });
+    let violations = match limit_mode {
+        "off" => Vec::new(),
+        "error" | "warn" => check_package_limits(&module_names, &module_sizes, &protocol_config),
+        other => return Err(format!("Invalid packageLimits '{}': expected \"error\", \"warn\" or \"off\"", other)),
+    };
+    if !violations.is_empty() {
+        if limit_mode == "error" {
+            return Err(format!("Package exceeds publish limits:\n{}", violations.join("\n")));
+        }
+        for violation in violations {
+            logger.warn("serializing", &violation);
+            builder_warnings.push(violation);
+        }
     }
+
+    // Use dependency IDs (Already filtered by Tree Shaking above)
+    // let dependency_ids_vec = dependency_ids_vec; // Already defined
+    
+    // Canonical Digest Calculation
+    let dep_object_ids: Vec<sui_types::base_types::ObjectID> = dependency_ids_vec.iter()
+        .map(|bytes| sui_types::base_types::ObjectID::new(*bytes))
+        .collect();
+    
+    let package_digest = sui_types::move_package::MovePackage::compute_digest_for_modules_and_deps(
+        &module_bytes,
+        &dep_object_ids,
+        true // hash_modules matches default behavior usually
+    );
+    if let Some(expected) = &options.expected_digest {
+        // `digest` is output as raw bytes; compare in hex, the form CI scripts pin.
+        let actual = hex::encode(package_digest);
+        let expected = expected.trim().to_ascii_lowercase();
+        let normalized = expected.strip_prefix("0x").unwrap_or(&expected);
+        if normalized != actual {
+            return Err(format!(
+                "Package digest mismatch:\n  expected: {}\n  actual:   {}",
+                normalized, actual
+            ));
+        }
+    }
+
+    // ORIGINAL SOURCE: root_package.rs:251 - save_lockfile_to_disk()
+    // Generate V4 lockfile using DependencyGraph JSON from TypeScript
+    let lockfile = match graph_json {
+        Some(graph) => generate_lockfile_v4_internal(graph),
+        None => String::new(),  // No graph provided, skip lockfile
+    };
+
+    let cost_estimate = match options.estimate_publish_cost {
+        Some(prices) => Some(estimate_publish_cost(
+            &module_infos,
+            &module_bytes,
+            &compilation_to_output,
+            prices,
+            &protocol_config,
+        )?),
+        None => None,
+    };
+
+    let address_mapping: BTreeMap<String, String> = compilation_to_output
+        .iter()
+        .map(|(compiled, output)| (*compiled, *output))
+        .chain(root_address_mapping)
+        .map(|(compiled, output)| (compiled.to_canonical_string(true), output.to_canonical_string(true)))
+        .collect();
+
+    // Builder warnings follow the compiler's in the same `warning: ` form, and are hidden
+    // and counted the same way by `silenceWarnings`.
+    let warnings = if options.silence_warnings {
+        diagnostics.suppressed_warnings += builder_warnings.len() as u32;
+        warnings
+    } else {
+        diagnostics.warnings += builder_warnings.len() as u32;
+        builder_warnings
+            .iter()
+            .fold(warnings, |text, warning| Some(format!("{}warning: {}\n", text.unwrap_or_default(), warning)))
+    };
+
+    let output_data = CompilationOutput {
+        modules: EncodedModules::Base64(module_bytes),
+        module_names,
+        module_sizes,
+        module_versions,
+        module_count,
+        total_bytecode_bytes,
+        interface,
+        entry_points,
+        hints,
+        constants,
+        debug_bundle,
+        friends,
+        tree_shake_warnings,
+        dependencies: dependency_ids_vec
+            .iter()
+            .map(|bytes| AccountAddress::new(*bytes).to_canonical_string(true))
+            .collect(),
+        digest: package_digest.to_vec(),
+        address_mapping,
+        cost_estimate,
+        lockfile,
+        warnings,
+        pruned_dependencies,
+        unused_dependencies: unused_dependencies.into_iter().collect(),
+        test_only_dependencies: test_only_dependencies.into_iter().collect(),
+        notes,
+        build_info,
+        diagnostics,
+    };
+
+    Ok(output_data)
 }
 
+/// Check serialized modules against the publish limits a validator enforces.
+///
+/// ORIGINAL SOURCE: sui-types/src/transaction.rs - check_publish_size / MovePackage::new
+/// (`max_modules_in_publish`, `max_move_package_size`).
+fn check_package_limits(
+    module_names: &[String],
+    module_sizes: &[usize],
+    protocol_config: &ProtocolConfig,
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    let max_modules = protocol_config.max_modules_in_publish() as usize;
+    if module_sizes.len() > max_modules {
+        violations.push(format!(
+            "Package has {} modules, exceeding max_modules_in_publish ({})",
+            module_sizes.len(),
+            max_modules
+        ));
+    }
+    let max_size = protocol_config.max_move_package_size();
+    let total: u64 = module_sizes.iter().map(|size| *size as u64).sum();
+    if total > max_size {
+        let mut largest: Vec<(&String, &usize)> = module_names.iter().zip(module_sizes).collect();
+        largest.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let largest: Vec<String> = largest
+            .iter()
+            .take(5)
+            .map(|(name, size)| format!("{} ({} bytes)", name, size))
+            .collect();
+        violations.push(format!(
+            "Package is {} bytes, exceeding max_move_package_size ({} bytes); largest modules: {}",
+            total,
+            max_size,
+            largest.join(", ")
+        ));
+    }
+    violations
+}
 
-#[wasm_bindgen]
+/// Gas prices for `estimatePublishCost`, in MIST per unit.
+#[derive(Deserialize, Clone, Copy)]
+struct PublishPrices {
+    #[serde(rename = "storagePrice")]
+    storage_price: u64,
+    #[serde(rename = "computationPrice")]
+    computation_price: u64,
+}
+
+/// Estimated cost of publishing the output package.
+#[derive(Serialize)]
+struct CostEstimate {
+    /// Size the package object is charged for: the `MovePackage` plus object metadata.
+    #[serde(rename = "storageBytes")]
+    storage_bytes: u64,
+    /// `storageBytes` in storage units, times the storage price.
+    #[serde(rename = "storageCost")]
+    storage_cost: u64,
+    /// Publish computation units before bucketing; a lower bound on what is charged.
+    #[serde(rename = "computationUnits")]
+    computation_units: u64,
+    #[serde(rename = "computationCost")]
+    computation_cost: u64,
+    #[serde(rename = "totalCost")]
+    total_cost: u64,
+}
+
+/// Estimate the cost of publishing `modules` against the dependencies in `linkage`
+/// (original address -> output address).
+///
+/// The package object is built the way publish builds it (`MovePackage::new` with a type
+/// origin per datatype and a linkage entry per dependency), so the storage size is exact
+/// for a fresh package. Computation only covers the publish fee itself, not execution
+/// of `init` functions.
+///
+/// ORIGINAL SOURCE: sui-types/src/move_package.rs - MovePackage::new_initial / size
+/// ORIGINAL SOURCE: sui-types/src/gas_model/gas_v2.rs - storage cost per byte
+fn estimate_publish_cost(
+    modules: &[(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)],
+    module_bytes: &[Vec<u8>],
+    linkage: &BTreeMap<AccountAddress, AccountAddress>,
+    prices: PublishPrices,
+    protocol_config: &ProtocolConfig,
+) -> Result<CostEstimate, String> {
+    use sui_types::base_types::{ObjectID, SequenceNumber};
+    use sui_types::move_package::{MovePackage, TypeOrigin, UpgradeInfo};
+    use sui_types::object::{Data, Object};
+
+    let module_map: BTreeMap<String, Vec<u8>> = modules
+        .iter()
+        .zip(module_bytes)
+        .map(|((id, _), bytes)| (id.name().to_string(), bytes.clone()))
+        .collect();
+    let type_origin_table: Vec<TypeOrigin> = modules
+        .iter()
+        .flat_map(|(id, named)| {
+            let module = &named.module;
+            let structs = module.struct_defs().iter().map(|def| def.struct_handle);
+            let enums = module.enum_defs().iter().map(|def| def.enum_handle);
+            structs
+                .chain(enums)
+                .map(|handle| TypeOrigin {
+                    module_name: id.name().to_string(),
+                    datatype_name: module.identifier_at(module.datatype_handle_at(handle).name).to_string(),
+                    package: ObjectID::ZERO,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let linkage_table: BTreeMap<ObjectID, UpgradeInfo> = linkage
+        .iter()
+        .map(|(original, output)| {
+            (
+                ObjectID::from(*original),
+                UpgradeInfo { upgraded_id: ObjectID::from(*output), upgraded_version: SequenceNumber::from_u64(1) },
+            )
+        })
+        .collect();
+
+    let package = MovePackage::new(
+        ObjectID::ZERO,
+        SequenceNumber::from_u64(1),
+        module_map,
+        protocol_config.max_move_package_size(),
+        type_origin_table,
+        linkage_table,
+    )
+    .map_err(|e| format!("Failed to build package for cost estimate: {}", e))?;
+    let object = Object::new_package_from_data(Data::Package(package), TransactionDigest::genesis_marker());
+    let storage_bytes = object.object_size_for_gas_metering() as u64;
+
+    let storage_cost = storage_bytes
+        .saturating_mul(protocol_config.obj_data_cost_refundable())
+        .saturating_mul(prices.storage_price);
+    let total_module_bytes: u64 = module_bytes.iter().map(|bytes| bytes.len() as u64).sum();
+    let computation_units = protocol_config
+        .package_publish_cost_fixed()
+        .saturating_add(total_module_bytes.saturating_mul(protocol_config.package_publish_cost_per_byte()));
+    let computation_cost = computation_units.saturating_mul(prices.computation_price);
+    Ok(CostEstimate {
+        storage_bytes,
+        storage_cost,
+        computation_units,
+        computation_cost,
+        total_cost: storage_cost.saturating_add(computation_cost),
+    })
+}
+
+/// Names of the packages whose modules are reachable from `roots` through imports.
+fn reachable_dependency_packages<'a>(
+    units: &'a [AnnotatedCompiledModule],
+    roots: Vec<&'a AnnotatedCompiledModule>,
+) -> std::collections::BTreeSet<String> {
+    let by_id: BTreeMap<ModuleId, &AnnotatedCompiledModule> = units
+        .iter()
+        .map(|unit| (unit.named_module.module.self_id(), unit))
+        .collect();
+    let mut visited = std::collections::BTreeSet::new();
+    let mut reached = std::collections::BTreeSet::new();
+    let mut worklist = roots;
+    while let Some(unit) = worklist.pop() {
+        for dep_id in unit.named_module.module.immediate_dependencies() {
+            if !visited.insert(dep_id.clone()) {
+                continue;
+            }
+            if let Some(dep_unit) = by_id.get(&dep_id) {
+                if let Some(name) = dep_unit.named_module.package_name {
+                    reached.insert(name.to_string());
+                }
+                worklist.push(dep_unit);
+            }
+        }
+    }
+    reached
+}
+
+/// Compile a package. `on_log` optionally receives `{ level, phase, message }` build events
+/// (phases: "setup", "parsing", "compiling", "verifying", "tree-shaking", "serializing").
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn compile(
     files_json: &str,
     dependencies_json: &str,
     options_json: Option<String>,
     graph_json: Option<String>,  // DependencyGraph JSON for lockfile generation
+    on_log: Option<LogCallback>,
 ) -> MoveCompilerResult {
-    compile_impl(files_json, dependencies_json, options_json, graph_json)
+    let logger = BuildLogger::new(on_log);
+    compile_impl(files_json, dependencies_json, options_json, graph_json, &logger)
 }
 
+/// Async variant of `compile` that yields to the event loop between phases, so a page
+/// stays responsive while a large package builds. `progress` receives
+/// `{ phase, packagesDone, packagesTotal }` before each phase (phases: "setup", "parsing",
+/// "typing", "bytecode", "verifying", "serializing"). Every pass runs over all packages at
+/// once, so `packagesDone` moves from 0 to `packagesTotal` when bytecode generation finishes.
+/// `on_log` receives the same build events as `compile`'s. The promise resolves to the same
+/// `MoveCompilerResult` `compile` returns.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn compile_async(
+    files_json: String,
+    dependencies_json: String,
+    options_json: Option<String>,
+    progress: &js_sys::Function,
+    on_log: Option<LogCallback>,
+) -> js_sys::Promise {
+    let progress = Progress { callback: progress.clone() };
+    let logger = BuildLogger::new(on_log);
+    wasm_bindgen_futures::future_to_promise(async move {
+        let options: CompileOptions = options_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let result = compile_package_async(&files_json, &dependencies_json, &options, &progress, &logger).await;
+        Ok(JsValue::from(MoveCompilerResult::compiled(result)))
+    })
+}
 
-#[cfg(feature = "testing")]
-fn test_impl(
+/// The `compile_package` flow, split at the same stage boundaries `build_and_verify` uses.
+///
+/// `colored`'s override is process-wide, so it is only held inside each synchronous stage:
+/// another build may run while this one is suspended at an `.await`.
+#[cfg(feature = "wasm")]
+async fn compile_package_async(
     files_json: &str,
     dependencies_json: &str,
-) -> MoveTestResult {
+    options: &CompileOptions,
+    progress: &Progress,
+    logger: &BuildLogger,
+) -> Result<CompilationOutput, BuildFailure> {
+    let graph_json = options.dependency_graph.clone();
+
+    progress.report("setup", 0, 0).await;
+    let (files, dep_packages) = parse_package_inputs(files_json, dependencies_json)?;
+    let (root, targets) = prepare_package(&files, dep_packages, options, logger)?;
+    let (prepared, all_targets) = PreparedRoot::new(targets, options)?;
+    let total = all_targets.len();
+
+    progress.report("parsing", 0, total).await;
+    let parsed = colored(options, || ParsedBuild::start(root, all_targets, options, None))?;
+    progress.report("typing", 0, total).await;
+    let typed = colored(options, || parsed.check_types(options))?;
+    progress.report("bytecode", 0, total).await;
+    let compiled = colored(options, || typed.generate_bytecode(options))?;
+    progress.report("verifying", total, total).await;
+    let build = colored(options, || compiled.verify(options, &prepared.protocol_config, prepared.metered, logger))?;
+
+    progress.report("serializing", total, total).await;
+    colored(options, || prepared.finish(build, options, graph_json.as_deref(), logger).map_err(BuildFailure::from))
+}
+
+/// Run one synchronous build stage under the build's `ansiColor` setting.
+#[cfg(feature = "wasm")]
+fn colored<T>(options: &CompileOptions, stage: impl FnOnce() -> Result<T, BuildFailure>) -> Result<T, BuildFailure> {
+    let _color = ColorOverride::set(options.ansi_color());
+    stage()
+}
+
+/// Progress reporting for `compile_async`.
+#[cfg(feature = "wasm")]
+struct Progress {
+    callback: js_sys::Function,
+}
+
+#[cfg(feature = "wasm")]
+impl Progress {
+    /// Report `phase`, then give the event loop a turn before it starts.
+    async fn report(&self, phase: &str, done: usize, total: usize) {
+        let event = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&event, &"phase".into(), &phase.into());
+        let _ = js_sys::Reflect::set(&event, &"packagesDone".into(), &(done as u32).into());
+        let _ = js_sys::Reflect::set(&event, &"packagesTotal".into(), &(total as u32).into());
+        // A throwing callback must not abort the build.
+        let _ = self.callback.call1(&JsValue::NULL, &event);
+        yield_to_event_loop().await;
+    }
+}
+
+/// Resolve on a `setTimeout(0)` macrotask, so pending rendering and input run first.
+/// Falls back to a resolved promise where `setTimeout` is unavailable.
+#[cfg(feature = "wasm")]
+async fn yield_to_event_loop() {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let global = js_sys::global();
+        let set_timeout = js_sys::Reflect::get(&global, &"setTimeout".into())
+            .ok()
+            .and_then(|f| f.dyn_into::<js_sys::Function>().ok());
+        match set_timeout {
+            Some(set_timeout) => {
+                let _ = set_timeout.call2(&global, &resolve, &JsValue::from(0));
+            }
+            None => {
+                let _ = resolve.call0(&JsValue::NULL);
+            }
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Named addresses bound in a snippet's synthesized manifest.
+const SNIPPET_ADDRESSES: [(&str, &str); 3] = [("playground", "0x0"), ("std", "0x1"), ("sui", "0x2")];
+
+/// Compile a single `.move` source without a manifest.
+///
+/// The source becomes `sources/playground.move` of a package named `playground`
+/// (edition `2024.beta`) with `playground`, `std` and `sui` bound to `0x0`, `0x1` and
+/// `0x2`. A snippet that uses `std` or `sui` modules needs their sources: pass the
+/// framework packages in `dependencies_json`, an array shaped like the dependencies of
+/// `compile` (e.g. from `resolveDependencies`). Options and output are those of `compile`;
+/// invalid options JSON is an error.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compile_snippet(
+    source: &str,
+    dependencies_json: Option<String>,
+    options_json: Option<String>,
+) -> MoveCompilerResult {
+    let options: CompileOptions = match options_json.map(|json| serde_json::from_str(&json)).transpose() {
+        Ok(options) => options.unwrap_or_default(),
+        Err(e) => return MoveCompilerResult::err(format!("Failed to parse options: {}", e)),
+    };
+    let result = parse_package_inputs("{}", dependencies_json.as_deref().unwrap_or("[]"))
+        .map_err(BuildFailure::from)
+        .and_then(|(_, dep_packages)| {
+            let mut move_toml =
+                String::from("[package]\nname = \"playground\"\nedition = \"2024.beta\"\n\n[addresses]\n");
+            for (name, address) in SNIPPET_ADDRESSES {
+                move_toml.push_str(&format!("{} = \"{}\"\n", name, address));
+            }
+            let files = BTreeMap::from([
+                ("Move.toml".to_string(), move_toml),
+                ("sources/playground.move".to_string(), source.to_string()),
+            ]);
+            compile_package(&files, dep_packages, &options, None, &BuildLogger::new(None))
+        })
+        .map_err(|mut failure| {
+            // The compiler reports the unbound address; say which ones a snippet has.
+            if !failure.unbound_addresses.is_empty() {
+                failure.message.push_str(&format!(
+                    "\nnote: a snippet binds only {} and the addresses of its dependencies; \
+                     declare modules at one of them or at a numeric address",
+                    SNIPPET_ADDRESSES.map(|(name, _)| name).join(", ")
+                ));
+            }
+            failure
+        });
+    MoveCompilerResult::compiled(result)
+}
+
+/// Compile several root packages that share one dependency set.
+///
+/// `packages_json` is a JSON array of `{ name, files }` groups, each `files` map laid out
+/// like the `files` argument of `compile`. All roots go through a single compiler run
+/// together with the dependencies, so shared dependencies are parsed, checked and
+/// verified once. The output is a JSON object mapping each package name to the same
+/// output `compile` produces. Compiler warnings cover the whole run and are attached to
+/// every package; no lockfile is generated. Inputs go through the same checks as
+/// `compile`; root files are keyed `<name>/<path>` there, including in `fileModes`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compile_workspace(
+    packages_json: &str,
+    dependencies_json: &str,
+    options_json: Option<String>,
+) -> MoveCompilerResult {
+    let options: CompileOptions = options_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    let logger = BuildLogger::new(None);
+
+    let result = serde_json::from_str::<Vec<WorkspacePackage>>(packages_json)
+        .map_err(|e| BuildFailure::from(format!("Failed to parse packages JSON: {}", e)))
+        .and_then(|packages| {
+            let (_, dep_packages) = parse_package_inputs("{}", dependencies_json)?;
+            compile_workspace_packages(packages, dep_packages, &options, &logger)
+        });
+    match result {
+        Ok((outputs, counts)) => MoveCompilerResult {
+            counts,
+            ..MoveCompilerResult::ok(serde_json::to_string(&outputs).unwrap_or_default())
+        },
+        Err(failure) => MoveCompilerResult::err(failure),
+    }
+}
+
+/// One root package of `compile_workspace`.
+#[derive(Deserialize)]
+struct WorkspacePackage {
+    name: String,
+    files: BTreeMap<String, String>,
+}
+
+fn compile_workspace_packages(
+    packages: Vec<WorkspacePackage>,
+    dep_packages: Vec<PackageGroup>,
+    options: &CompileOptions,
+    logger: &BuildLogger,
+) -> Result<(BTreeMap<String, CompilationOutput>, DiagnosticCounts), BuildFailure> {
+    let _color = ColorOverride::set(options.ansi_color());
+    let protocol_config = resolve_protocol_config(options.protocol_version, options.chain.as_deref())?;
+    if packages.is_empty() {
+        return Err("No packages to compile".to_string().into());
+    }
+
+    // Each root's files live under `<name>/` so the roots can share one filesystem.
+    let mut ingestion = Ingestion::default();
+    for (index, pkg) in packages.iter().enumerate() {
+        if packages[..index].iter().any(|other| other.name == pkg.name) {
+            return Err(format!("Duplicate workspace package name '{}'", pkg.name).into());
+        }
+        for (path, contents) in &pkg.files {
+            ingestion.add_file(None, &format!("{}/{}", pkg.name, path), contents)?;
+        }
+    }
+    for group in dep_packages {
+        ingestion.add_dependency(group)?;
+    }
+    let (root, files, dep_packages) = ingestion.prepare(options, logger)?;
+
+    let mut roots = Vec::with_capacity(packages.len());
+    let mut root_targets = Vec::with_capacity(packages.len());
+    let mut dependency_targets = None;
+    for pkg in &packages {
+        // The root's files that the active mode selected, keyed as in its own tree.
+        let prefix = format!("{}/", pkg.name);
+        let pkg_files: BTreeMap<String, String> = files
+            .iter()
+            .filter_map(|(path, contents)| Some((path.strip_prefix(&prefix)?.to_string(), contents.clone())))
+            .collect();
+        // `prepare` sees one root manifest; every root takes part in the cycle check.
+        check_dependency_cycles(&pkg_files, &dep_packages).map_err(|e| format!("{}: {}", pkg.name, e))?;
+        logger.info("parsing", &format!("Reading manifest of '{}'", pkg.name));
+        let PackageTargets {
+            root_package_name,
+            targets,
+            dependency_ids,
+            compilation_to_output,
+            known_compilation_addresses,
+            notes,
+            warnings,
+            address_order,
+            declared_dependencies,
+            unpublished_dependencies,
+            root_address_mapping,
+        } = build_package_targets(&pkg_files, &dep_packages, options, logger)
+            .map_err(|e| format!("{}: {}", pkg.name, e))?;
+
+        let mut targets = targets.into_iter();
+        let Some(mut root_paths) = targets.next() else {
+            return Err(format!("{}: no root package target", pkg.name).into());
+        };
+        // Dependency targets only depend on `dep_packages`, so every root yields the same set.
+        if dependency_targets.is_none() {
+            dependency_targets = Some(targets.collect::<Vec<_>>());
+        }
+
+        let build_info = BuildInfo::new(&root_package_name, &root_paths, address_order, options);
+        // Name each root after its workspace entry: roots may share a manifest name.
+        if let Some((name, _)) = root_paths.name.as_mut() {
+            *name = Symbol::from(pkg.name.as_str());
+        }
+        root_paths.paths = root_paths
+            .paths
+            .iter()
+            .map(|path| Symbol::from(format!("{}/{}", pkg.name, path)))
+            .collect();
+        roots.push(RootPackage {
+            name: pkg.name.clone(),
+            target: root_targets.len(),
+            dependency_ids,
+            compilation_to_output,
+            known_compilation_addresses,
+            notes,
+            warnings,
+            build_info,
+            declared_dependencies,
+            unpublished_dependencies,
+            root_address_mapping,
+        });
+        root_targets.push(root_paths);
+    }
+
+    let mut all_targets = root_targets;
+    all_targets.extend(dependency_targets.unwrap_or_default());
+
+    logger.info(
+        "compiling",
+        &format!("Compiling {} root packages with {} dependencies", roots.len(), dep_packages.len()),
+    );
+    let metered = options.metered_verification.unwrap_or(!options.test_mode);
+    let build = build_and_verify(root, all_targets, options, &protocol_config, metered, logger, None)?;
+    let verified = VerifiedUnits {
+        files: &build.files,
+        units: &build.units,
+        hints: &build.hints,
+        compiler_notes: &build.compiler_notes,
+        fn_info: &build.fn_info,
+        protocol_config: &protocol_config,
+        metered,
+        core_packages: &build.core_packages,
+        file_targets: &build.file_targets,
+        diagnostics: build.diagnostics,
+    };
+
+    // The shared run's diagnostics once, plus the builder warnings each root adds.
+    let mut counts = build.diagnostics;
+    let mut outputs = BTreeMap::new();
+    for root_package in roots {
+        let name = root_package.name.clone();
+        let output = assemble_root_output(verified, root_package, build.warnings.clone(), options, None, logger)
+            .map_err(|e| format!("{}: {}", name, e))?;
+        counts.add(DiagnosticCounts {
+            errors: output.diagnostics.errors - build.diagnostics.errors,
+            warnings: output.diagnostics.warnings - build.diagnostics.warnings,
+            suppressed_warnings: output.diagnostics.suppressed_warnings - build.diagnostics.suppressed_warnings,
+        });
+        outputs.insert(name, output);
+    }
+    Ok((outputs, counts))
+}
+
+/// Type-check a package without generating bytecode.
+///
+/// Runs the compiler only up to `PASS_CFGIR` (parsing, expansion, typing, borrow checking),
+/// skipping bytecode generation, verification, tree-shaking and serialization. Returns
+/// success with an empty output, or the rendered diagnostics on failure. Intended for
+/// fast editor feedback.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn check(
+    files_json: &str,
+    dependencies_json: &str,
+    options_json: Option<String>,
+) -> MoveCompilerResult {
     #[cfg(debug_assertions)]
+    #[cfg(feature = "wasm")]
     console_error_panic_hook::set_once();
-    
-    // START ANSI SUPPORT
-    colored::control::set_override(true);
-    let ansi_color = true;
-    // END ANSI SUPPORT
-    
-    let (root, files, dep_packages) = match setup_vfs(files_json, dependencies_json) {
-        Ok(res) => {
-            res
-        },
-        Err(e) => {
-            return MoveTestResult { passed: false, output: e };
+
+    let options: CompileOptions = options_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let result = parse_package_inputs(files_json, dependencies_json)
+        .map_err(BuildFailure::from)
+        .and_then(|(files, dep_packages)| check_package(&files, dep_packages, &options));
+    match result {
+        Ok(()) => MoveCompilerResult::ok(String::new()),
+        Err(failure) => MoveCompilerResult::err(failure),
+    }
+}
+
+fn check_package(
+    files: &BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    options: &CompileOptions,
+) -> Result<(), BuildFailure> {
+    let _color = ColorOverride::set(options.ansi_color());
+
+    let (root, PackageTargets { targets, .. }) =
+        prepare_package(files, dep_packages, options, &BuildLogger::new(None))?;
+
+    let compiler = Compiler::from_package_paths(Some(root), targets, Vec::new())
+        .map_err(|e| format!("Failed to create compiler: {}", e))?;
+    let flags = if options.test_mode {
+        Flags::testing()
+    } else {
+        Flags::empty()
+    };
+
+    let (files_info, res) = compiler
+        .set_flags(flags)
+        .run::<{ move_compiler::PASS_CFGIR }>()
+        .map_err(|e| format!("Compiler error: {}", e))?;
+    match res {
+        Ok(_) => Ok(()),
+        Err((_severity, diags)) => Err(render_errors(&files_info, diags, options)),
+    }
+}
+
+/// Extract documentation for the root package's modules.
+///
+/// Output is a JSON array with one entry per root module: `{ address, name, doc, structs,
+/// functions, constants }`. Structs and functions carry the same fields as `emitInterface`
+/// (all functions, private ones included) plus `doc`; constants are
+/// `{ name, signature, doc }`. `doc` is the attached doc comment, or null.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn build_docs(
+    files_json: &str,
+    dependencies_json: &str,
+    options_json: Option<String>,
+) -> MoveCompilerResult {
+    #[cfg(debug_assertions)]
+    #[cfg(feature = "wasm")]
+    console_error_panic_hook::set_once();
+
+    let options: CompileOptions = options_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+
+    let result = parse_package_inputs(files_json, dependencies_json)
+        .map_err(BuildFailure::from)
+        .and_then(|(files, dep_packages)| package_docs(&files, dep_packages, &options));
+    match result {
+        Ok(docs) => MoveCompilerResult::ok(serde_json::to_string(&docs).unwrap_or_default()),
+        Err(failure) => MoveCompilerResult::err(failure),
+    }
+}
+
+fn package_docs(
+    files: &BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    options: &CompileOptions,
+) -> Result<Vec<docs::ModuleDocs>, BuildFailure> {
+    let _color = ColorOverride::set(options.ansi_color());
+
+    let (root, PackageTargets { targets, .. }) =
+        prepare_package(files, dep_packages, options, &BuildLogger::new(None))?;
+
+    // Print addresses as the root's named addresses (the first name bound to each).
+    let mut address_names = BTreeMap::<AccountAddress, String>::new();
+    for (name, address) in &targets[0].named_address_map {
+        address_names.entry(address.into_inner()).or_insert_with(|| name.clone());
+    }
+
+    // Doc comments only survive in the parser AST, signatures are read from bytecode: take
+    // the comments from the parsed program, then hand it back to the same compiler run.
+    let root_package = targets[0].name.as_ref().map(|(name, _)| *name);
+    let named_addresses = targets[0].named_address_map.clone();
+    let mut parsed = ParsedBuild::start(root, targets, options, None)?;
+    let (compiler, program) = parsed.compiler.into_ast();
+    let mut source_docs = docs::collect_source_docs(&program, root_package, &named_addresses, files);
+    parsed.compiler = compiler.at_parser(program);
+
+    let compiled = parsed.check_types(options)?.generate_bytecode(options)?;
+    Ok(compiled
+        .units
+        .iter()
+        .filter(|unit| unit_target(&compiled.files, &compiled.file_targets, unit) == Some(0))
+        .map(|unit| {
+            let module = &unit.named_module.module;
+            let id = module.self_id();
+            let source = source_docs.remove(&(*id.address(), id.name().to_string())).unwrap_or_default();
+            docs::module_docs(interface::module_interface(module, &address_names, true), source)
+        })
+        .collect())
+}
+
+#[cfg(feature = "wasm")]
+#[derive(Serialize)]
+struct BinaryCompileResult {
+    success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<CompilationOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(rename = "errorsByFile", skip_serializing_if = "Option::is_none")]
+    errors_by_file: Option<BTreeMap<String, String>>,
+    #[serde(rename = "editionHints", skip_serializing_if = "Vec::is_empty")]
+    edition_hints: Vec<String>,
+    #[serde(rename = "warningCount")]
+    warning_count: u32,
+    #[serde(rename = "errorCount")]
+    error_count: u32,
+    #[serde(rename = "suppressedWarningCount")]
+    suppressed_warning_count: u32,
+}
+
+/// Binary variant of `compile`.
+///
+/// Takes the files map, dependency groups, and options as plain JS values instead of JSON
+/// strings, and returns `{ success, output | error, errorsByFile?, editionHints?,
+/// warningCount, errorCount, suppressedWarningCount }` where `output.modules` is an array of
+/// `Uint8Array`s rather than base64 strings. This skips a JSON parse of every source file and
+/// the base64 round-trip of every module, which dominates marshalling time for framework-sized
+/// inputs. The output otherwise has the same shape as `compile`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn compile_bytes(files: JsValue, dependencies: JsValue, options: JsValue) -> JsValue {
+    #[cfg(debug_assertions)]
+    console_error_panic_hook::set_once();
+
+    let result = (|| -> Result<CompilationOutput, BuildFailure> {
+        let files: BTreeMap<String, String> = serde_wasm_bindgen::from_value(files)
+            .map_err(|e| format!("Failed to parse files: {}", e))?;
+        let dep_packages: Vec<PackageGroup> = if dependencies.is_undefined() || dependencies.is_null() {
+            vec![]
+        } else {
+            serde_wasm_bindgen::from_value(dependencies)
+                .map_err(|e| format!("Failed to parse dependencies: {}", e))?
+        };
+        let dep_packages = dedupe_dependency_groups(dep_packages)?;
+        let options: CompileOptions = if options.is_undefined() || options.is_null() {
+            CompileOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)
+                .map_err(|e| format!("Failed to parse options: {}", e))?
+        };
+        let graph_json = options.dependency_graph.clone();
+        let logger = BuildLogger::new(None);
+        compile_package(&files, dep_packages, &options, graph_json.as_deref(), &logger)
+    })();
+
+    let result = match result {
+        Ok(mut output) => {
+            output.modules = output.modules.into_raw();
+            BinaryCompileResult {
+                success: true,
+                warning_count: output.diagnostics.warnings,
+                error_count: output.diagnostics.errors,
+                suppressed_warning_count: output.diagnostics.suppressed_warnings,
+                output: Some(output),
+                error: None,
+                errors_by_file: None,
+                edition_hints: Vec::new(),
+            }
         }
+        Err(failure) => BinaryCompileResult {
+            success: false,
+            output: None,
+            error: Some(failure.message),
+            errors_by_file: failure.errors_by_file,
+            edition_hints: failure.edition_hints,
+            warning_count: failure.counts.warnings,
+            error_count: failure.counts.errors,
+            suppressed_warning_count: failure.counts.suppressed_warnings,
+        },
     };
 
-    // 1. Build PackagePaths for targets (root package)
-    let mut root_named_address_map = BTreeMap::<String, NumericalAddress>::new();
-    let mut root_edition = Edition::LEGACY;
+    let serializer = serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+    result.serialize(&serializer).unwrap_or(JsValue::NULL)
+}
+
+
+/// Run the Move and Sui bytecode verifiers over already-compiled modules.
+///
+/// `modules_json` is a JSON array of base64-encoded modules. The output is a JSON array
+/// with one `{ module, passed, error? }` entry per module; `success` is set only when
+/// every module passed. Modules are verified independently, so one failure does not
+/// hide the results for the rest.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn verify_modules(modules_json: &str, options_json: Option<String>) -> MoveCompilerResult {
+    let options: VerifyOptions = options_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    match verify_encoded_modules(modules_json, &options) {
+        Ok(results) => MoveCompilerResult {
+            success: results.iter().all(|r| r.passed),
+            ..MoveCompilerResult::ok(serde_json::to_string(&results).unwrap_or_default())
+        },
+        Err(e) => MoveCompilerResult::err(e),
+    }
+}
 
+/// Verification outcome for one module of `verify_modules`.
+#[derive(Serialize)]
+struct ModuleVerification {
+    /// Canonical module ID (`address::name`).
+    module: String,
+    passed: bool,
+    /// Which verifier rejected the module, and why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
 
-    if let Some(move_toml_content) = files.get("Move.toml") {
-        if let Ok(manifest) = toml::from_str::<SourceManifest>(move_toml_content) {
-            // Extract Edition
-            if let Some(edition) = manifest.package.edition {
-                root_edition = parse_edition(&edition);
-            }
-            // Extract Addresses
-            if let Some(addresses) = manifest.addresses {
-                for (name, addr_opt) in addresses {
-                    if let Some(addr) = addr_opt {
-                        let name_str = name.as_str().to_string();
-                        if let Some(bytes) = parse_hex_address_to_bytes(&addr) {
-                            root_named_address_map.insert(
-                                name_str,
-                                NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex)
-                            );
-                        }
-                    }
-                }
+/// Decode a JSON array of base64 modules.
+fn decode_base64_modules(modules_json: &str) -> Result<Vec<CompiledModule>, String> {
+    let encoded: Vec<String> = serde_json::from_str(modules_json)
+        .map_err(|e| format!("Failed to parse modules JSON: {}", e))?;
+
+    let mut modules = Vec::with_capacity(encoded.len());
+    for (index, module_b64) in encoded.iter().enumerate() {
+        let bytes = general_purpose::STANDARD
+            .decode(module_b64)
+            .map_err(|e| format!("Module #{} is not valid base64: {}", index, e))?;
+        let module = CompiledModule::deserialize_with_defaults(&bytes)
+            .map_err(|e| format!("Module #{} failed to deserialize: {}", index, e))?;
+        modules.push(module);
+    }
+    Ok(modules)
+}
+
+fn verify_encoded_modules(modules_json: &str, options: &VerifyOptions) -> Result<Vec<ModuleVerification>, String> {
+    let protocol_config = resolve_protocol_config(options.protocol_version, options.chain.as_deref())?;
+    let modules = decode_base64_modules(modules_json)?;
+
+    let fn_info = fn_info_from_bytecode(&modules, options.may_contain_test_functions);
+    let verifier_config = protocol_config.verifier_config(None);
+    // Unmetered: the meter is only threaded through to satisfy the signature.
+    let mut meter = SuiVerifierMeter::new(signing_meter_config());
+
+    Ok(modules
+        .iter()
+        .map(|m| {
+            let error = verify_module(m, &fn_info, &verifier_config, &mut meter, true, false).err();
+            ModuleVerification {
+                module: m.self_id().to_canonical_string(true),
+                passed: error.is_none(),
+                error,
             }
+        })
+        .collect())
+}
+
+/// Diff the public interface of two builds of a package.
+///
+/// Both inputs are JSON arrays of base64-encoded modules (e.g. the published modules and a
+/// fresh build's `modules`). The output lists, per changed module, added, removed and
+/// changed public/entry functions and structs, plus `compatibleUnderCompatiblePolicy` and
+/// the `incompatibilities` behind it; `success` is false only when the inputs are invalid.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn diff_interfaces(old_modules_json: &str, new_modules_json: &str) -> MoveCompilerResult {
+    let decoded = decode_base64_modules(old_modules_json)
+        .map_err(|e| format!("Old modules: {}", e))
+        .and_then(|old| Ok((old, decode_base64_modules(new_modules_json).map_err(|e| format!("New modules: {}", e))?)));
+    match decoded {
+        Ok((old, new)) => {
+            MoveCompilerResult::ok(serde_json::to_string(&interface_diff::diff_interfaces(old, new)).unwrap_or_default())
         }
+        Err(e) => MoveCompilerResult::err(e),
     }
+}
 
-    let root_targets: Vec<Symbol> = files
-        .keys()
-        .filter(|name| !name.ends_with("Move.toml") && name.ends_with(".move"))
-        .map(|s| Symbol::from(s.as_str()))
-        .collect();
+/// Disassemble every module of a build in one call.
+///
+/// Input is a JSON array of base64-encoded modules (a compile result's `modules`); the
+/// output is a JSON object of module name -> disassembly, as `sui move disassemble`
+/// prints it. Stops at the first module that does not decode or disassemble, naming it.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn disassemble_all(modules_json: &str) -> MoveCompilerResult {
+    let disassembled = decode_base64_modules(modules_json).and_then(|modules| {
+        modules
+            .iter()
+            .map(|module| {
+                let name = module.self_id().name().to_string();
+                let text = Disassembler::from_module(module, Spanned::unsafe_no_loc(()).loc)
+                    .and_then(|disassembler| disassembler.disassemble())
+                    .map_err(|e| format!("Module '{}' failed to disassemble: {}", name, e))?;
+                Ok((name, text))
+            })
+            .collect::<Result<BTreeMap<String, String>, String>>()
+    });
+    match disassembled {
+        Ok(texts) => MoveCompilerResult::ok(serde_json::to_string(&texts).unwrap_or_default()),
+        Err(e) => MoveCompilerResult::err(e),
+    }
+}
+
+/// Compare locally built modules against the modules of a published package.
+///
+/// Both inputs are JSON arrays of base64-encoded modules. Local modules are compiled
+/// against a placeholder (usually 0x0) or the original package address, so before
+/// comparing, every occurrence of the local self-address is replaced with `original_id`,
+/// as source validation does. On-chain modules carry the package's original ID at every
+/// version; an upgraded package's published-at (storage) ID does not appear in them.
+/// The output is a JSON array of
+/// `{ module, status, firstDifferingOffset?, difference? }` entries, where `status` is
+/// `match`, `mismatch`, `missingOnChain` or `missingLocally`; `success` is set only
+/// when every module matches.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn verify_against_onchain(
+    local_modules_json: &str,
+    onchain_modules_json: &str,
+    original_id: &str,
+) -> MoveCompilerResult {
+    match compare_with_onchain(local_modules_json, onchain_modules_json, original_id) {
+        Ok(results) => MoveCompilerResult {
+            success: results.iter().all(|r| r.status == ModuleMatchStatus::Match),
+            ..MoveCompilerResult::ok(serde_json::to_string(&results).unwrap_or_default())
+        },
+        Err(e) => MoveCompilerResult::err(e),
+    }
+}
 
+#[derive(Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+enum ModuleMatchStatus {
+    Match,
+    Mismatch,
+    MissingOnChain,
+    MissingLocally,
+}
 
-    // 2. Build PackagePaths for dependencies
-    let mut dep_package_paths = Vec::new();
-    for pkg_group in &dep_packages {
-        let mut named_address_map = BTreeMap::<String, NumericalAddress>::new();
-        let mut edition = Edition::LEGACY;
+#[derive(Serialize)]
+struct ModuleComparison {
+    module: String,
+    status: ModuleMatchStatus,
+    /// First byte at which the serialized modules differ.
+    #[serde(rename = "firstDifferingOffset", skip_serializing_if = "Option::is_none")]
+    first_differing_offset: Option<usize>,
+    /// First module table whose contents differ.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    difference: Option<String>,
+}
 
-        if let Some(ref addr_map) = pkg_group.address_mapping {
-            for (name, addr_str) in addr_map {
-                if let Some(bytes) = parse_hex_address_to_bytes(addr_str) {
-                    named_address_map.insert(
-                        name.clone(),
-                        NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex)
-                    );
-                }
-            }
+fn compare_with_onchain(
+    local_modules_json: &str,
+    onchain_modules_json: &str,
+    original_id: &str,
+) -> Result<Vec<ModuleComparison>, String> {
+    let original = AccountAddress::new(
+        parse_hex_address_to_bytes(original_id)
+            .map_err(|e| format!("Invalid original ID \"{}\": {}", original_id, e))?,
+    );
+    let local = decode_base64_modules(local_modules_json)
+        .map_err(|e| format!("Local modules: {}", e))?;
+    let onchain = decode_base64_modules(onchain_modules_json)
+        .map_err(|e| format!("On-chain modules: {}", e))?;
+
+    let mut onchain_by_name: BTreeMap<String, CompiledModule> = onchain
+        .into_iter()
+        .map(|m| (m.self_id().name().to_string(), m))
+        .collect();
+
+    let mut results = Vec::new();
+    for mut module in local {
+        let name = module.self_id().name().to_string();
+        substitute_self_address(&mut module, original);
+        let Some(onchain_module) = onchain_by_name.remove(&name) else {
+            results.push(ModuleComparison {
+                module: name,
+                status: ModuleMatchStatus::MissingOnChain,
+                first_differing_offset: None,
+                difference: None,
+            });
+            continue;
+        };
+
+        let local_bytes = serialize_module(&module)?;
+        let onchain_bytes = serialize_module(&onchain_module)?;
+        if local_bytes == onchain_bytes {
+            results.push(ModuleComparison {
+                module: name,
+                status: ModuleMatchStatus::Match,
+                first_differing_offset: None,
+                difference: None,
+            });
+        } else {
+            let offset = local_bytes
+                .iter()
+                .zip(&onchain_bytes)
+                .position(|(a, b)| a != b)
+                .unwrap_or_else(|| local_bytes.len().min(onchain_bytes.len()));
+            results.push(ModuleComparison {
+                module: name,
+                status: ModuleMatchStatus::Mismatch,
+                first_differing_offset: Some(offset),
+                difference: describe_module_difference(&module, &onchain_module),
+            });
         }
+    }
+    results.extend(onchain_by_name.into_keys().map(|name| ModuleComparison {
+        module: name,
+        status: ModuleMatchStatus::MissingLocally,
+        first_differing_offset: None,
+        difference: None,
+    }));
+    Ok(results)
+}
 
-        if let Some(ref edition_str) = pkg_group.edition {
-            edition = parse_edition(edition_str);
+/// Move the root modules from 0x0 to `address`, as the adapter does at publish, and
+/// re-run the verifiers on the result so the emitted bytecode is checked as published.
+fn substitute_root_address(
+    modules: Vec<(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)>,
+    address: &str,
+    fn_info: &FnInfoMap,
+    protocol_config: &ProtocolConfig,
+    test_mode: bool,
+    metered: bool,
+    core_packages: &BTreeSet<Symbol>,
+) -> Result<Vec<(ModuleId, move_compiler::compiled_unit::NamedCompiledModule)>, String> {
+    let bytes = parse_hex_address_to_bytes(address)
+        .map_err(|e| format!("Invalid substituteRootAddress \"{}\": {}", address, e))?;
+    let target = AccountAddress::new(bytes);
+
+    let mut substituted = Vec::with_capacity(modules.len());
+    for (id, mut named) in modules {
+        if *id.address() != AccountAddress::ZERO {
+            return Err(format!(
+                "Cannot substitute root address: module '{}' is at {}, not 0x0",
+                id.name(),
+                id.address().to_canonical_string(true)
+            ));
         }
+        substitute_self_address(&mut named.module, target);
+        named.address = NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex);
+        substituted.push((named.module.self_id(), named));
+    }
 
-        let dep_files: Vec<Symbol> = pkg_group.files
-            .keys()
-            .filter(|name| !name.ends_with("Move.toml") && name.ends_with(".move"))
-            .map(|s| Symbol::from(s.as_str()))
-            .collect();
+    // Function info is keyed by module address, so follow the modules to their new home.
+    let fn_info: FnInfoMap = fn_info
+        .iter()
+        .map(|(key, info)| {
+            let mod_addr = if key.mod_addr == AccountAddress::ZERO { target } else { key.mod_addr };
+            (FnInfoKey { fn_name: key.fn_name.clone(), mod_addr }, FnInfo { is_test: info.is_test })
+        })
+        .collect();
+    let module_refs: Vec<&NamedCompiledModule> = substituted.iter().map(|(_, named)| named).collect();
+    verify_bytecode(&module_refs, &fn_info, protocol_config, test_mode, metered, core_packages, &BTreeSet::new())
+        .map_err(|e| format!("Bytecode Verification Failed after address substitution: {}", e))?;
+
+    Ok(substituted)
+}
 
-        // Merge dependency addresses into root map
-        for (name, addr) in &named_address_map {
-             if !root_named_address_map.contains_key(name) {
-                 root_named_address_map.insert(name.clone(), *addr);
-             }
+/// Rewrite every occurrence of the module's own address to `address`.
+///
+/// ORIGINAL SOURCE: sui-source-validation substitutes the on-chain address for the
+/// placeholder the package was compiled with.
+fn substitute_self_address(module: &mut CompiledModule, address: AccountAddress) {
+    let self_address = *module.address();
+    for addr in module.address_identifiers.iter_mut() {
+        if *addr == self_address {
+            *addr = address;
         }
+    }
+}
 
-        dep_package_paths.push(PackagePaths {
-            name: Some((
-                Symbol::from(pkg_group.name.as_str()),
-                PackageConfig {
-                    is_dependency: true,
-                    edition,
-                    flavor: Flavor::Sui,
-                    ..PackageConfig::default()
-                },
-            )),
-            paths: dep_files,
-            named_address_map,
-        });
+fn serialize_module(module: &CompiledModule) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+    module
+        .serialize_with_version(module.version, &mut bytes)
+        .map_err(|e| format!("Failed to serialize module '{}': {}", module.self_id().name(), e))?;
+    Ok(bytes)
+}
+
+/// Name the first table that differs between two modules.
+fn describe_module_difference(local: &CompiledModule, onchain: &CompiledModule) -> Option<String> {
+    if local.version != onchain.version {
+        return Some(format!(
+            "bytecode version differs (local {}, on-chain {})",
+            local.version, onchain.version
+        ));
     }
+    let tables = [
+        ("module handles", local.module_handles() == onchain.module_handles()),
+        ("datatype handles", local.datatype_handles() == onchain.datatype_handles()),
+        ("function handles", local.function_handles() == onchain.function_handles()),
+        ("friend declarations", local.friend_decls() == onchain.friend_decls()),
+        ("signatures", local.signatures() == onchain.signatures()),
+        ("identifiers", local.identifiers() == onchain.identifiers()),
+        ("address identifiers", local.address_identifiers() == onchain.address_identifiers()),
+        ("constant pool", local.constant_pool() == onchain.constant_pool()),
+        ("metadata", local.metadata == onchain.metadata),
+        ("struct definitions", local.struct_defs() == onchain.struct_defs()),
+        ("enum definitions", local.enum_defs() == onchain.enum_defs()),
+        ("function definitions", local.function_defs() == onchain.function_defs()),
+    ];
+    tables
+        .iter()
+        .find(|(_, equal)| !equal)
+        .map(|(table, _)| format!("{} differ", table))
+}
 
-    // FALLBACK: Ensure std and sui are always defined
-    if !root_named_address_map.contains_key("std") {
-        if let Some(bytes) = parse_hex_address_to_bytes("0x1") {
-            root_named_address_map.insert("std".to_string(), NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex));
-        }
+/// Run the root package's tests. Shared by the wasm `test` export and `api::test`.
+#[cfg(feature = "testing")]
+fn test_impl(
+    files: &BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    options: &TestOptions,
+) -> MoveTestResult {
+    // Test reports have always been colored; `ansiColor: false` opts out.
+    let ansi_color = options.ansi_color.unwrap_or(true);
+    let _color = ColorOverride::set(ansi_color);
+
+    // Coverage is read from execution traces, which only a VM built with tracing records.
+    #[cfg(not(feature = "coverage"))]
+    if options.with_coverage {
+        return MoveTestResult::failed("withCoverage requires a build with the `coverage` feature".to_string());
     }
-    if !root_named_address_map.contains_key("sui") {
-        if let Some(bytes) = parse_hex_address_to_bytes("0x2") {
-            root_named_address_map.insert("sui".to_string(), NumericalAddress::new(bytes, move_compiler::shared::NumberFormat::Hex));
+
+    let protocol_config = match resolve_protocol_config(options.protocol_version, options.chain.as_deref()) {
+        Ok(config) => config,
+        Err(e) => return MoveTestResult::failed(e),
+    };
+    TEST_PROTOCOL_CONFIG.with(|config| *config.borrow_mut() = Some(protocol_config.clone()));
+    let cost_table = match options.gas_schedule.as_deref().unwrap_or("unitTest") {
+        "unitTest" => initial_cost_schedule_for_unit_tests(),
+        "protocol" => cost_table_for_version(protocol_config.gas_model_version()),
+        other => {
+            return MoveTestResult::failed(format!(
+                "Unsupported gasSchedule \"{}\" (expected \"unitTest\" or \"protocol\")",
+                other
+            ))
         }
+    };
+    match TestTxContextConfig::from_options(options) {
+        Ok(tx_config) => TEST_TX_CONTEXT.with(|config| *config.borrow_mut() = tx_config),
+        Err(e) => return MoveTestResult::failed(e),
     }
-
-    let target_package = PackagePaths {
-        name: Some((
-            Symbol::from("root"),
-            PackageConfig {
-                is_dependency: false,
-                edition: root_edition,
-                flavor: Flavor::Sui,
-                ..PackageConfig::default()
-                },
-        )),
-        paths: root_targets,
-        named_address_map: root_named_address_map,
+    
+    // The package is read exactly as `compile` reads it in test mode, so manifests,
+    // addresses and dependency groups behave (and fail) the same way in both.
+    let logger = BuildLogger::new(None);
+    let (root, targets) = match prepare_package(files, dep_packages, &options.compile_options(), &logger) {
+        Ok(res) => res,
+        Err(e) => return MoveTestResult::failed(e),
     };
-
-    // PATCHED: Treat all dependencies as targets to ensure their bytecode is emitted.
-    // This is necessary for the test runner to find them in the linking phase.
-    let mut all_targets = vec![target_package];
-    all_targets.extend(dep_package_paths);
+    // PATCHED: Dependencies are targets too, so their bytecode is emitted for the test
+    // runner to link against.
+    let PackageTargets { targets: all_targets, notes, .. } = targets;
 
     // 3. Construct TestPlan
     // 3. Construct TestPlan
@@ -1130,7 +5513,7 @@ fn test_impl(
         },
         Err(e) => {
 
-            return MoveTestResult { passed: false, output: format!("Failed to create compiler: {}", e) }
+            return MoveTestResult::failed(format!("Failed to create compiler: {}", e))
         },
     };
 
@@ -1142,7 +5525,7 @@ fn test_impl(
         },
         Err(e) => {
 
-             return MoveTestResult { passed: false, output: format!("Compiler error: {}", e) }
+             return MoveTestResult::failed(format!("Compiler error: {}", e))
         },
     };
 
@@ -1151,89 +5534,174 @@ fn test_impl(
             c
         },
         Err((_severity, diags)) => {
-            let buffer = move_compiler::diagnostics::report_diagnostics_to_buffer(&files_info, diags, ansi_color);
-            return MoveTestResult { passed: false, output: String::from_utf8_lossy(&buffer).to_string() };
+            return MoveTestResult::compile_failed(&files_info, diags, ansi_color);
         }
     };
 
     let (compiler, cfgir) = compiler.into_ast();
     let compilation_env = compiler.compilation_env();
     let mut test_tests = move_compiler::unit_test::plan_builder::construct_test_plan(compilation_env, None, &cfgir);
-    
-    // PATCHED: Filter out dependency tests. We only want to run tests for the root package.
-    // test_tests is Option<Vec<ModuleTestPlan>>
-    if let Some(plans) = &mut test_tests {
-         plans.retain(|plan| {
-             // Heuristic: Filter out frameworks (0x1, 0x2).
-             let s = format!("{:?}", plan.module_id.address()); 
-             !s.ends_with("0000000000000000000000000000000000000000000000000000000000000001") &&
-             !s.ends_with("0000000000000000000000000000000000000000000000000000000000000002")
-         });
-    }
     let mapped_files = compilation_env.mapped_files().clone();
 
     // Reconstruct/continue compilation to get units
     let compilation_result = compiler.at_cfgir(cfgir).build();
-    let (units, _) = match compilation_result {
+    let (units, warnings) = match compilation_result {
         Ok(res) => res,
         Err((_severity, diags)) => {
-             let buffer = move_compiler::diagnostics::report_diagnostics_to_buffer(&files_info, diags, ansi_color);
-             return MoveTestResult { passed: false, output: String::from_utf8_lossy(&buffer).to_string() };
+             return MoveTestResult::compile_failed(&files_info, diags, ansi_color);
         }
     };
+    let diagnostics = DiagnosticCounts {
+        suppressed_warnings: DiagnosticCounts::of(&warnings.into_vec()).warnings,
+        ..DiagnosticCounts::default()
+    };
+    if let Err(e) = check_module_cycles(units.iter().map(|unit| &unit.named_module)) {
+        return MoveTestResult::failed(e);
+    }
+
+    let package_of: BTreeMap<ModuleId, Option<Symbol>> = units
+        .iter()
+        .map(|unit| (unit.named_module.module.self_id(), unit.named_module.package_name))
+        .collect();
+    // PATCHED: Only run tests for the root package and the dependencies named in
+    // `includePackages`. Plans are matched to packages through the compiled units, never by
+    // address: a root assigned 0x2 keeps its tests and a dependency at any address loses them.
+    // Units without a package name belong to the root, as in `assemble_root_output`.
+    let count_tests = |plans: &Option<Vec<move_compiler::unit_test::ModuleTestPlan>>| {
+        plans.iter().flatten().map(|plan| plan.tests.len() as u32).sum::<u32>()
+    };
+    let discovered = count_tests(&test_tests);
+    if let Some(plans) = &mut test_tests {
+        plans.retain(|plan| match package_of.get(&plan.module_id).copied().flatten() {
+            Some(name) => {
+                name.as_str() == "root"
+                    || options.include_packages.iter().any(|pkg| pkg.as_str() == name.as_str())
+            }
+            None => true,
+        });
+    }
+
+    let filtered = discovered - count_tests(&test_tests);
 
     let units: Vec<_> = units.into_iter().map(|unit| unit.named_module).collect();
+    // Kept for decoding abort codes after the plan (which owns `units`) is consumed.
+    let compiled_modules: BTreeMap<ModuleId, CompiledModule> = units
+        .iter()
+        .map(|unit| (unit.module.self_id(), unit.module.clone()))
+        .collect();
 
     let test_plan = match test_tests {
         Some(tests) => {
             move_compiler::unit_test::TestPlan::new(tests, mapped_files, units, vec![])
         },
         None => {
-            return MoveTestResult { passed: true, output: "No tests found".to_string() }
+            return MoveTestResult {
+                passed: true,
+                output: "No tests found".to_string(),
+                gas: TestGasReport::default(),
+                notes,
+                failures: Vec::new(),
+                coverage: None,
+                counts: TestCounts { discovered, filtered, ..TestCounts::default() },
+                diagnostics,
+            }
         },
     };
 
     // 4. Run tests and capture output
     Lazy::force(&SET_EXTENSION_HOOK);
+    // The store outlives a `test()` call; start each run from an empty one so objects
+    // left behind by a previous run (e.g. shared objects from test_scenario) don't leak in.
+    TEST_STORE_INNER.with(|store| *store.borrow_mut() = InMemoryStorage::default());
 
+    // Wasm has no preemption, so a runaway test is stopped by its gas budget.
     let config = UnitTestingConfig {
         num_threads: 1, // Crucial for Wasm
-        gas_limit: Some(1_000_000),
+        gas_limit: Some(options.gas_limit.unwrap_or(DEFAULT_TEST_GAS_LIMIT)),
         report_stacktrace_on_abort: true,
+        seed: options.rng_seed,
+        // Traces are never written out; the runner only folds them into coverage.
+        trace_execution: options.with_coverage.then(String::new),
         ..UnitTestingConfig::default_with_bound(None)
     };
 
     let natives = sui_move_natives::all_natives(
         false,
-        &ProtocolConfig::get_for_max_version_UNSAFE(),
+        &protocol_config,
     );
 
+    // Drop records left behind by an earlier run that bailed out mid-way.
+    move_unit_test::test_runner::take_test_run_records();
+    move_unit_test::test_runner::take_test_coverage();
+
     let output_buffer = std::io::Cursor::new(Vec::new());
     let (output_buffer, passed) = match config.run_and_report_unit_tests(
         test_plan,
         Some(natives),
-        Some(initial_cost_schedule_for_unit_tests()),
+        Some(cost_table),
         output_buffer,
     ) {
         Ok(res) => res,
-        Err(e) => return MoveTestResult { passed: false, output: format!("Test runner error: {}", e) },
+        Err(e) => return MoveTestResult::failed(format!("Test runner error: {}", e)),
     };
 
-    let output_str = String::from_utf8_lossy(output_buffer.get_ref()).to_string();
+    let mut output_str = String::from_utf8_lossy(output_buffer.get_ref()).to_string();
+    let records = move_unit_test::test_runner::take_test_run_records();
+    let counts = TestCounts::from_records(&records, discovered, filtered);
+    if counts.out_of_gas > 0 {
+        let out_of_gas: Vec<String> = records
+            .iter()
+            .filter(|record| is_out_of_gas(record))
+            .map(|record| format!("{}::{}", record.module, record.function))
+            .collect();
+        output_str.push_str(&format!(
+            "\n{} test(s) ran out of gas (raise gasLimit if the test is expected to run longer):\n  {}\n",
+            counts.out_of_gas,
+            out_of_gas.join("\n  ")
+        ));
+    }
+    let gas = TestGasReport::from_records(&records);
+    let source_index = SourceIndex::new(&files_info);
+    let failures = source_index.failures(&records, &compiled_modules);
+    output_str.push_str(&format_failure_locations(&failures));
+    let coverage = options.with_coverage.then(|| {
+        let root_modules = compiled_modules.iter().filter_map(|(id, module)| {
+            let package = package_of.get(id).copied().flatten();
+            package.is_some_and(|name| name.as_str() == "root").then_some(module)
+        });
+        coverage_report(root_modules, move_unit_test::test_runner::take_test_coverage())
+    });
 
     MoveTestResult {
         passed,
         output: output_str,
+        gas,
+        notes,
+        failures,
+        coverage,
+        counts,
+        diagnostics,
     }
 }
 
 #[cfg(feature = "testing")]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn test(
     files_json: &str,
     dependencies_json: &str,
+    options_json: Option<String>,
 ) -> MoveTestResult {
-    test_impl(files_json, dependencies_json)
+    #[cfg(debug_assertions)]
+    #[cfg(feature = "wasm")]
+    console_error_panic_hook::set_once();
+
+    let options: TestOptions = options_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    match parse_package_inputs(files_json, dependencies_json) {
+        Ok((files, dep_packages)) => test_impl(&files, dep_packages, &options),
+        Err(e) => MoveTestResult::failed(e),
+    }
 }
 
 /// Compute manifest digest for Move.lock V4 generation.
@@ -1243,9 +5711,27 @@ pub fn test(
 /// - Returns uppercase hex SHA256 hash
 /// 
 /// Input format: `{ "deps": [ { "name": "Dep1", "git": "...", "subdir": "...", "rev": "..." }, ... ] }`
+/// (other per-dep fields: `local`, `system`, `on-chain`/`id`, `resolver` + `external`,
+/// `is_override`, `use_environment`, `modes`)
 /// Output format: `"E3A1B2C4...\"`  (64-char uppercase hex)
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub fn compute_manifest_digest(deps_json: &str) -> String {
+    manifest_digest(deps_json).unwrap_or_default()
+}
+
+/// Like `compute_manifest_digest`, but reports failures instead of returning `""`.
+///
+/// Output format: `{ "ok": true, "digest": "E3A1..." }` or `{ "ok": false, "error": "..." }`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_manifest_digest_checked(deps_json: &str) -> String {
+    let result = match manifest_digest(deps_json) {
+        Ok(digest) => serde_json::json!({ "ok": true, "digest": digest }),
+        Err(error) => serde_json::json!({ "ok": false, "error": error }),
+    };
+    result.to_string()
+}
+
+fn manifest_digest(deps_json: &str) -> Result<String, String> {
     use std::path::PathBuf;
     use std::collections::BTreeMap as StdBTreeMap;
     use sha2::{Digest, Sha256};
@@ -1273,6 +5759,19 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
     struct SystemDependency {
         system: String,
     }
+
+    // `{ on-chain = true }`; the package ID comes from the environment, not the manifest.
+    #[derive(Serialize)]
+    struct OnChainDepInfo {
+        #[serde(rename = "on-chain")]
+        on_chain: bool,
+    }
+
+    // `{ r.<resolver> = <data> }`
+    #[derive(Serialize)]
+    struct ExternalDependency {
+        r: StdBTreeMap<String, toml::Value>,
+    }
     
     // ManifestDependencyInfo enum - matches CLI's ManifestDependencyInfo
     // CLI has: Git, External, Local, OnChain, System
@@ -1280,7 +5779,9 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
     #[derive(Serialize)]
     enum ManifestDependencyInfo {
         Git(ManifestGitDependency),
+        External(ExternalDependency),
         Local(LocalDepInfo),
+        OnChain(OnChainDepInfo),
         System(SystemDependency),
     }
     
@@ -1331,10 +5832,20 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
         local: Option<String>,
         #[serde(default)]
         system: Option<String>,  // For system dependencies: { system = "name" }
+        #[serde(default, rename = "on-chain")]
+        on_chain: Option<bool>,  // For on-chain dependencies: { on-chain = true }
+        #[serde(default)]
+        id: Option<String>,  // On-chain package ID; implies on-chain, not part of the digest
+        #[serde(default)]
+        resolver: Option<String>,  // For external dependencies: { r.<resolver> = <external> }
+        #[serde(default)]
+        external: Option<serde_json::Value>,
         #[serde(default)]
         is_override: Option<bool>, // Allows specifying override=true (default false)
         #[serde(default)]
         use_environment: Option<String>,
+        #[serde(default)]
+        modes: Option<Vec<String>>, // e.g. ["test"] for mode-gated dependencies
     }
     
     #[derive(Deserialize)]
@@ -1344,11 +5855,11 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
     
     let input: Input = match serde_json::from_str(deps_json) {
         Ok(i) => i,
-        Err(_) => {
+        Err(input_err) => {
             // Fallback: try parsing as simple string array (backward compat)
             let simple: Vec<String> = match serde_json::from_str(deps_json) {
                 Ok(s) => s,
-                Err(_) => return String::new(),
+                Err(_) => return Err(format!("Failed to parse dependencies JSON: {}", input_err)),
             };
             // Build simple deps map
             let mut deps_map: BTreeMap<String, ReplacementDependency> = BTreeMap::new();
@@ -1360,12 +5871,10 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
                 });
             }
             let triggers = RepinTriggers { deps: deps_map };
-            let serialized = match toml_edit::ser::to_string(&triggers) {
-                Ok(s) => s,
-                Err(_) => return String::new(),
-            };
+            let serialized = toml_edit::ser::to_string(&triggers)
+                .map_err(|e| format!("Failed to serialize dependencies: {}", e))?;
             let hash = Sha256::digest(serialized.as_bytes());
-            return format!("{:X}", hash);
+            return Ok(format!("{:X}", hash));
         }
     };
     
@@ -1383,7 +5892,7 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
                 }),
                 is_override: dep.is_override.unwrap_or(false),
                 rename_from: None,
-                modes: None,
+                modes: dep.modes,
             })
         } else if let Some(local_path) = dep.local {
             // Local dependency
@@ -1393,7 +5902,28 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
                 }),
                 is_override: dep.is_override.unwrap_or(false),
                 rename_from: None,
-                modes: None,
+                modes: dep.modes,
+            })
+        } else if let Some(resolver) = dep.resolver {
+            // External (resolver) dependency
+            let data = dep.external.unwrap_or(serde_json::Value::Null);
+            let data = toml::Value::try_from(&data)
+                .map_err(|e| format!("Dependency '{}': invalid external resolver data: {}", dep.name, e))?;
+            Some(DefaultDependency {
+                dependency_info: ManifestDependencyInfo::External(ExternalDependency {
+                    r: StdBTreeMap::from([(resolver, data)]),
+                }),
+                is_override: dep.is_override.unwrap_or(false),
+                rename_from: None,
+                modes: dep.modes,
+            })
+        } else if dep.on_chain.unwrap_or(false) || dep.id.is_some() {
+            // On-chain dependency
+            Some(DefaultDependency {
+                dependency_info: ManifestDependencyInfo::OnChain(OnChainDepInfo { on_chain: true }),
+                is_override: dep.is_override.unwrap_or(false),
+                rename_from: None,
+                modes: dep.modes,
             })
         } else if let Some(system_name) = dep.system {
             // System dependency
@@ -1403,7 +5933,7 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
                 }),
                 is_override: dep.is_override.unwrap_or(true), // Implicit deps usually have override=true
                 rename_from: None,
-                modes: None,
+                modes: dep.modes,
             })
         } else {
             None
@@ -1419,34 +5949,398 @@ pub fn compute_manifest_digest(deps_json: &str) -> String {
     let triggers = RepinTriggers { deps: deps_map };
     
     // Serialize to TOML using `toml_edit` to match CLI behavior (Inline Tables)
-    let serialized = match toml_edit::ser::to_string(&triggers) {
-        Ok(s) => s,
-        Err(_) => return String::new(),
-    };
+    let serialized = toml_edit::ser::to_string(&triggers)
+        .map_err(|e| format!("Failed to serialize dependencies: {}", e))?;
     
     // Compute SHA256 hash
     let hash = Sha256::digest(serialized.as_bytes());
     
     // Format as uppercase hex
-    format!("{:X}", hash)
+    Ok(format!("{:X}", hash))
 }
 
-#[derive(Deserialize, Default)]
-struct CompileOptions {
+/// Compute the dependency digest (`deps_digest`) over a resolved dependency graph.
+/// This is a builder-defined fingerprint, not a CLI-compatible value:
+/// - Each pinned package is serialized to TOML in the lock file's `Pin` shape
+///   (`source`, `deps`), and hashed individually
+/// - The per-package hashes are sorted and hashed together, so input order does not matter
+/// - Returns uppercase hex SHA256 hash
+///
+/// Input format:
+/// `{ "packages": [ { "name": "Dep1", "git": "...", "rev": "<sha>", "subdir": "...",
+///   "deps": { "Alias": "PkgId", ... } }, { "name": "Dep2", "local": "../dep2" }, ... ] }`
+/// Output format: `"E3A1B2C4..."` (64-char uppercase hex), or `""` if the input is invalid.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_deps_digest(resolved_graph_json: &str) -> String {
+    deps_digest(resolved_graph_json).unwrap_or_default()
+}
+
+/// Like `compute_deps_digest`, but reports failures instead of returning `""`.
+///
+/// Output format: `{ "ok": true, "digest": "E3A1..." }` or `{ "ok": false, "error": "..." }`
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub fn compute_deps_digest_checked(resolved_graph_json: &str) -> String {
+    let result = match deps_digest(resolved_graph_json) {
+        Ok(digest) => serde_json::json!({ "ok": true, "digest": digest }),
+        Err(error) => serde_json::json!({ "ok": false, "error": error }),
+    };
+    result.to_string()
+}
+
+fn deps_digest(resolved_graph_json: &str) -> Result<String, String> {
+    use std::path::PathBuf;
+    use sha2::{Digest, Sha256};
+    use serde::{Serialize, Deserialize};
+
+    // Same field order as the lock file's `LockfileDependencyInfo` / `Pin`
+    #[derive(Serialize)]
+    struct PinnedGitDependency {
+        #[serde(rename = "git")]
+        repo: String,
+        subdir: PathBuf,
+        rev: String,
+    }
+
+    #[derive(Serialize)]
+    struct PinnedLocalDependency {
+        local: PathBuf,
+    }
+
+    #[derive(Serialize)]
+    #[serde(untagged)]
+    enum LockfileDependencyInfo {
+        Local(PinnedLocalDependency),
+        Git(PinnedGitDependency),
+    }
+
+    #[derive(Serialize)]
+    struct Pin {
+        source: LockfileDependencyInfo,
+        deps: BTreeMap<String, String>,
+    }
+
+    #[derive(Deserialize)]
+    struct PinnedInfo {
+        name: String,
+        #[serde(default)]
+        git: Option<String>,
+        #[serde(default)]
+        rev: Option<String>,
+        #[serde(default)]
+        subdir: Option<String>,
+        #[serde(default)]
+        local: Option<String>,
+        #[serde(default)]
+        deps: BTreeMap<String, String>,
+    }
+
+    #[derive(Deserialize)]
+    struct Input {
+        packages: Vec<PinnedInfo>,
+    }
+
+    let input: Input = serde_json::from_str(resolved_graph_json)
+        .map_err(|e| format!("Failed to parse resolved graph JSON: {}", e))?;
+
+    let mut hashed_pins = Vec::with_capacity(input.packages.len());
+    for pkg in input.packages {
+        let source = if let Some(repo) = pkg.git {
+            // A pinned git dependency must carry its resolved revision
+            let Some(rev) = pkg.rev else {
+                return Err(format!("Package '{}': git dependency has no pinned `rev`", pkg.name));
+            };
+            LockfileDependencyInfo::Git(PinnedGitDependency {
+                repo,
+                subdir: PathBuf::from(pkg.subdir.unwrap_or_default()),
+                rev,
+            })
+        } else if let Some(local_path) = pkg.local {
+            LockfileDependencyInfo::Local(PinnedLocalDependency {
+                local: PathBuf::from(local_path),
+            })
+        } else {
+            return Err(format!("Package '{}': expected a `git` or `local` source", pkg.name));
+        };
+
+        let mut pinned = BTreeMap::new();
+        pinned.insert(pkg.name, Pin { source, deps: pkg.deps });
+        let serialized = toml_edit::ser::to_string(&pinned)
+            .map_err(|e| format!("Failed to serialize pinned package: {}", e))?;
+        hashed_pins.push(format!("{:X}", Sha256::digest(serialized.as_bytes())));
+    }
+
+    // Sort so the digest is independent of the order packages were resolved in
+    hashed_pins.sort();
+    let mut hasher = Sha256::new();
+    for pin_hash in &hashed_pins {
+        hasher.update(pin_hash.as_bytes());
+    }
+    Ok(format!("{:X}", hasher.finalize()))
+}
+
+/// Options of `compile` and the other build entry points, deserialized from the camelCase
+/// JSON object the JS API takes.
+#[derive(Deserialize, Default, Clone)]
+pub struct CompileOptions {
     #[serde(default, rename = "silenceWarnings")]
     silence_warnings: bool,
+    /// Leave the compiler's note-level diagnostics (e.g. deprecations) out of `notes`.
+    #[serde(default, rename = "silenceNotes")]
+    silence_notes: bool,
+    /// Warnings to drop, by code (`W09001`) or message (`unused variable`).
+    #[serde(default, rename = "suppressWarnings")]
+    suppress_warnings: Vec<String>,
+    /// On compiler errors, also report the diagnostics per file key in `errorsByFile`
+    /// (diagnostics without a file under ""). The failure output stays the plain report.
+    #[serde(default, rename = "perModuleErrors")]
+    per_module_errors: bool,
     #[serde(default, rename = "testMode")]
     test_mode: bool,
+    /// Largest accepted input file in bytes (default 8 MiB).
+    #[serde(default, rename = "maxFileBytes")]
+    max_file_bytes: Option<usize>,
+    /// Largest accepted total of all input files in bytes (default 256 MiB).
+    #[serde(default, rename = "maxTotalBytes")]
+    max_total_bytes: Option<usize>,
+    /// In test mode, allow root code outside tests to use dependencies' `#[test_only]` functions and types.
+    #[serde(default, rename = "keepTestOnlyDeps")]
+    keep_test_only_deps: bool,
+    /// Fail the build when it produces (unsilenced) warnings.
+    #[serde(default, rename = "warningsAsErrors")]
+    warnings_as_errors: bool,
+    /// Include `[dev-dependencies]` packages without enabling test mode.
+    #[serde(default, rename = "devMode")]
+    dev_mode: bool,
+    /// Compile root `tests/` files. Defaults to `testMode`.
+    #[serde(default, rename = "includeTests")]
+    include_tests: Option<bool>,
+    /// Compile root `examples/` files. Defaults to `testMode`.
+    #[serde(default, rename = "includeExamples")]
+    include_examples: Option<bool>,
+    /// Protocol version to verify against. Defaults to the latest bundled version.
+    #[serde(default, rename = "protocolVersion")]
+    protocol_version: Option<u64>,
+    /// Chain whose protocol config applies: "mainnet", "testnet" or "unknown" (default).
+    #[serde(default)]
+    chain: Option<String>,
+    /// Run the metered verifier with validator signing limits.
+    /// Defaults to on for non-test builds so "too complex to publish" errors surface here.
+    #[serde(default, rename = "meteredVerification")]
+    metered_verification: Option<bool>,
+    /// Publish size limits (`max_move_package_size`, `max_modules_in_publish`):
+    /// "error", "warn" or "off". Defaults to "error" for non-test builds, "off" in test mode.
+    #[serde(default, rename = "packageLimits")]
+    package_limits: Option<String>,
     #[serde(default, rename = "lintFlag")]
     lint_flag: Option<String>,
+    /// Fall back to defaults (package "root", legacy edition, no addresses) when a
+    /// Move.toml fails to parse, instead of failing the build.
+    #[serde(default, rename = "ignoreManifestErrors")]
+    ignore_manifest_errors: bool,
+    /// Assume `std = 0x1` / `sui = 0x2` when they are not defined (default). When off, nothing
+    /// is assumed and an unbound framework address fails compilation, with a note naming the
+    /// dependency group expected to define it.
+    #[serde(default, rename = "implicitFrameworkAddresses")]
+    implicit_framework_addresses: Option<bool>,
+    /// Fail instead of warning when a dependency's own `[addresses]` entry and the
+    /// original ID its Move.lock records (with `lockEnvironment`) disagree.
+    #[serde(default, rename = "strictPublishedAt")]
+    strict_published_at: bool,
+    /// Warn instead of failing when the root uses modules of a dependency that has no
+    /// published address. The output then cannot be published as is.
+    #[serde(default, rename = "allowUnpublishedDependencies")]
+    allow_unpublished_dependencies: bool,
+    /// Add `interface` (structs, enums and callable functions of each root module) to the output.
+    #[serde(default, rename = "emitInterface")]
+    emit_interface: bool,
+    /// Add `entryPoints` (callable root functions with parameter kinds and BCS layouts).
+    #[serde(default, rename = "emitEntryPoints")]
+    emit_entry_points: bool,
+    /// Add `friends` (each root module's friend declarations) to the output.
+    #[serde(default, rename = "withFriends")]
+    with_friends: bool,
+    /// Add `hints` (unused `use`s and constants, unreachable private functions).
+    #[serde(default, rename = "emitHints")]
+    emit_hints: bool,
+    /// Add `constants` (constant pool of each root module with names and decoded values).
+    #[serde(default, rename = "emitConstants")]
+    emit_constants: bool,
+    /// Add `debugBundle` (source map and compiled source text of each root module).
+    #[serde(default, rename = "emitDebugBundle")]
+    emit_debug_bundle: bool,
+    /// Environment (e.g. "mainnet" or a chain ID) whose lock publication overrides the
+    /// addresses of dependencies without an `addressMapping`: `[published.<env>]` of their
+    /// Published.toml or Move.lock, `[env.<env>]` of a legacy Move.lock, or the root Move.lock's
+    /// `[pinned.<env>.<name>]` entry.
+    #[serde(default, rename = "lockEnvironment")]
+    lock_environment: Option<String>,
+    /// Move.lock (version 3 or 4) whose pinned packages the dependency groups must match.
+    #[serde(default, rename = "moveLock")]
+    move_lock: Option<String>,
+    /// Environment whose `[pinned.<env>]` table of a version 4 `moveLock` is checked; may be
+    /// left out when the lock pins a single environment.
+    #[serde(default, rename = "moveLockEnvironment")]
+    move_lock_environment: Option<String>,
+    /// Log `moveLock` mismatches instead of failing.
+    #[serde(default, rename = "ignoreLockMismatch")]
+    ignore_lock_mismatch: bool,
+    /// Fail instead of reporting `treeShakeWarnings`.
+    #[serde(default, rename = "strictTreeShaking")]
+    strict_tree_shaking: bool,
+    /// Active build mode; files and dependency groups restricted to other modes are left out.
+    #[serde(default)]
+    mode: Option<String>,
+    /// Root file key -> modes the file belongs to. Unlisted files belong to every mode.
+    #[serde(default, rename = "fileModes")]
+    file_modes: BTreeMap<String, Vec<String>>,
+    /// Fail unless the package digest equals this hex string (`0x` prefix optional).
+    #[serde(default, rename = "expectedDigest")]
+    expected_digest: Option<String>,
+    /// Dependency package name -> edition, overriding both the package's Move.toml and
+    /// its dependency entry (for third-party packages with a mislabeled edition).
+    #[serde(default, rename = "editionOverrides")]
+    edition_overrides: BTreeMap<String, String>,
+    /// Gas prices (`{ storagePrice, computationPrice }`) to estimate the publish cost with.
+    #[serde(default, rename = "estimatePublishCost")]
+    estimate_publish_cost: Option<PublishPrices>,
+    /// Binary format version to serialize modules at. Defaults to the newest version the
+    /// selected protocol config accepts; a newer version than it accepts is an error.
+    #[serde(default, rename = "bytecodeVersion")]
+    bytecode_version: Option<u32>,
+    /// Keep the root `[addresses]` declaration order: the builder's own "Invalid address"
+    /// errors list entries in that order (alphabetical otherwise), and
+    /// `buildInfo.declaredAddressOrder` reports it. Compiler diagnostics are unaffected: the
+    /// compiler keys named addresses by name, so they stay alphabetical.
+    #[serde(default, rename = "preserveAddressOrder")]
+    preserve_address_order: bool,
+    /// Emit the root modules "as if published" at this address instead of 0x0.
+    #[serde(default, rename = "substituteRootAddress")]
+    substitute_root_address: Option<String>,
+    /// Emit ANSI color codes in diagnostics. Defaults to off.
     #[serde(default, rename = "ansiColor")]
-    ansi_color: bool,
+    ansi_color: Option<bool>,
     /// DependencyGraph JSON for V4 lockfile generation
     /// Passed from TypeScript resolver
     #[serde(default, rename = "dependencyGraph")]
     dependency_graph: Option<String>,
 }
 
+#[derive(Deserialize, Default)]
+struct VerifyOptions {
+    /// Treat every function as possibly `#[test]`, relaxing the test-exempt checks.
+    /// Bytecode carries no test attributes, so by default every function is non-test.
+    #[serde(default, rename = "mayContainTestFunctions")]
+    may_contain_test_functions: bool,
+    #[serde(default, rename = "protocolVersion")]
+    protocol_version: Option<u64>,
+    #[serde(default)]
+    chain: Option<String>,
+}
+
+impl CompileOptions {
+    fn ansi_color(&self) -> bool {
+        self.ansi_color.unwrap_or(false)
+    }
+}
+
+/// Applies `colored`'s process-wide override for the duration of one call and removes it
+/// on drop, so the setting of one build does not leak into the host or the next build.
+struct ColorOverride;
+
+impl ColorOverride {
+    fn set(enabled: bool) -> Self {
+        colored::control::set_override(enabled);
+        ColorOverride
+    }
+}
+
+impl Drop for ColorOverride {
+    fn drop(&mut self) {
+        colored::control::unset_override();
+    }
+}
+
+/// Options of `test`.
+#[cfg(feature = "testing")]
+#[derive(Deserialize, Default, Clone)]
+pub struct TestOptions {
+    /// Protocol version whose native cost table the tests run under.
+    #[serde(default, rename = "protocolVersion")]
+    protocol_version: Option<u64>,
+    #[serde(default)]
+    chain: Option<String>,
+    #[serde(default, rename = "ignoreManifestErrors")]
+    ignore_manifest_errors: bool,
+    #[serde(default, rename = "implicitFrameworkAddresses")]
+    implicit_framework_addresses: Option<bool>,
+    /// Dependency packages (by name) whose tests run alongside the root package's.
+    #[serde(default, rename = "includePackages")]
+    include_packages: Vec<String>,
+    /// Emit ANSI color codes in diagnostics and the test report. Defaults to on.
+    #[serde(default, rename = "ansiColor")]
+    ansi_color: Option<bool>,
+    /// Transaction sender seen by `tx_context::sender`. Defaults to 0x0.
+    #[serde(default)]
+    sender: Option<String>,
+    #[serde(default)]
+    epoch: Option<u64>,
+    #[serde(default, rename = "epochTimestampMs")]
+    epoch_timestamp_ms: Option<u64>,
+    /// Seeds the transaction digest (and so fresh object IDs) and `#[random_test]` inputs.
+    #[serde(default, rename = "rngSeed")]
+    rng_seed: Option<u64>,
+    /// Trace test execution and report which root module instructions ran. Needs the
+    /// `coverage` feature (part of the npm `full` build).
+    #[serde(default, rename = "withCoverage")]
+    with_coverage: bool,
+    /// Gas budget of each test (default 1,000,000, as the CLI's `--gas-limit`). A runaway
+    /// loop fails with out-of-gas instead of hanging.
+    #[serde(default, rename = "gasLimit")]
+    gas_limit: Option<u64>,
+    /// Cost table for test execution: "unitTest" (default), or "protocol" for the table of
+    /// the selected protocol version's gas model, so gas figures match production.
+    #[serde(default, rename = "gasSchedule")]
+    gas_schedule: Option<String>,
+    /// Input size limits, as for `compile`.
+    #[serde(default, rename = "maxFileBytes")]
+    max_file_bytes: Option<usize>,
+    #[serde(default, rename = "maxTotalBytes")]
+    max_total_bytes: Option<usize>,
+    /// Dependency package name -> edition, as for `compile`.
+    #[serde(default, rename = "editionOverrides")]
+    edition_overrides: BTreeMap<String, String>,
+    /// Active build mode and root file modes, as for `compile`.
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default, rename = "fileModes")]
+    file_modes: BTreeMap<String, Vec<String>>,
+    /// Environment whose lock publication overrides dependency addresses, as for `compile`.
+    #[serde(default, rename = "lockEnvironment")]
+    lock_environment: Option<String>,
+}
+
+#[cfg(feature = "testing")]
+impl TestOptions {
+    /// The options `compile` would read the package with: `sui move test` builds in test
+    /// mode, with dev-dependencies and the `tests/` and `examples/` directories.
+    fn compile_options(&self) -> CompileOptions {
+        CompileOptions {
+            test_mode: true,
+            protocol_version: self.protocol_version,
+            chain: self.chain.clone(),
+            ignore_manifest_errors: self.ignore_manifest_errors,
+            implicit_framework_addresses: self.implicit_framework_addresses,
+            max_file_bytes: self.max_file_bytes,
+            max_total_bytes: self.max_total_bytes,
+            edition_overrides: self.edition_overrides.clone(),
+            mode: self.mode.clone(),
+            file_modes: self.file_modes.clone(),
+            lock_environment: self.lock_environment.clone(),
+            ..CompileOptions::default()
+        }
+    }
+}
+
 /// Generate a Move.lock V4 lockfile from dependency information.
 ///
 /// ORIGINAL SOURCE REFERENCES:
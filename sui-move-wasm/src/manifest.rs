@@ -29,7 +29,32 @@ pub type Substitution = BTreeMap<NamedAddress, SubstOrRename>;
 pub struct SourceManifest {
     pub package: PackageInfo,
     pub addresses: Option<AddressDeclarations>,
-    // Removed unused fields to avoid strict parsing issues with 'deps'
+    // Dependency entries are read leniently (every field optional) to avoid strict parsing
+    // issues with 'deps'; `parse_manifest` classifies them.
+    #[serde(default)]
+    pub dependencies: BTreeMap<PackageName, ManifestDependency>,
+    #[serde(default, rename = "dev-dependencies")]
+    pub dev_dependencies: BTreeMap<PackageName, ManifestDependency>,
+}
+
+/// A `[dependencies]` entry; one of `git`, `local`, `system`, `on-chain` or `r` selects its kind.
+///
+/// ORIGINAL SOURCE: move-package-alt/src/schema/manifest.rs - ManifestDependencyInfo
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ManifestDependency {
+    pub git: Option<String>,
+    pub rev: Option<String>,
+    pub subdir: Option<String>,
+    pub local: Option<String>,
+    pub system: Option<String>,
+    #[serde(rename = "on-chain")]
+    pub on_chain: Option<bool>,
+    /// External resolver name -> its data.
+    pub r: Option<BTreeMap<String, serde_json::Value>>,
+    #[serde(rename = "override")]
+    pub dep_override: Option<bool>,
+    #[serde(rename = "rename-from")]
+    pub rename_from: Option<String>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -0,0 +1,204 @@
+// Stateful compilation for editor integrations.
+//
+// A session keeps the in-memory filesystem (with every dependency file already written),
+// the parsed dependency groups, the compiler targets derived from the manifests, and the
+// dependencies compiled once (their parser through CFGIR ASTs and their units), so an edit
+// to a root `.move` file only re-runs the compiler over the root package.
+//
+// Cache invalidation:
+// - editing an existing root `.move` file: only the file contents change; targets and the
+//   compiled dependencies are reused
+// - adding or removing a root `.move` file: the root target list is rebuilt
+// - editing `Move.toml`: targets are rebuilt and the dependencies recompiled (named addresses
+//   and `addr_subst` change what they are compiled at)
+// - any edit drops the cached result of the previous `compile()`
+// - dependency files are fixed for the lifetime of the session; create a new session to change them
+
+use crate::ingest::Ingestion;
+use crate::{
+    BuildLogger, CompileOptions, MoveCompilerResult, PackageGroup, PackageTargets,
+    build_package_targets, check_input_contents, check_input_sizes, compile_targets, ensure_vfs_parents,
+    parse_package_inputs, precompile_dependencies, validate_input_path,
+};
+use move_compiler::FullyCompiledProgram;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use vfs::VfsPath;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct CompilerSession {
+    files: BTreeMap<String, String>,
+    dep_packages: Vec<PackageGroup>,
+    /// (package, path, size in bytes) of every selected dependency file, for the size limits;
+    /// the groups keep only the paths of dependency sources.
+    dependency_sizes: Vec<(String, String, usize)>,
+    options: CompileOptions,
+    root: VfsPath,
+    targets: Option<PackageTargets>,
+    dependencies: Option<Arc<FullyCompiledProgram>>,
+    last_result: Option<MoveCompilerResult>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl CompilerSession {
+    /// Create a session from the same inputs `compile()` takes.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        files_json: &str,
+        dependencies_json: &str,
+        options_json: Option<String>,
+    ) -> Result<CompilerSession, String> {
+        let options: CompileOptions = options_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        let (files, dep_packages) = parse_package_inputs(files_json, dependencies_json)?;
+        let mut dependency_sizes: Vec<(String, String, usize)> = dep_packages
+            .iter()
+            .flat_map(|pkg| {
+                pkg.files
+                    .iter()
+                    .map(move |(path, contents)| (pkg.name.clone(), path.clone(), contents.len()))
+            })
+            .collect();
+        let (root, files, dep_packages) = Ingestion::of(&files, dep_packages)
+            .and_then(|ingestion| ingestion.prepare(&options, &BuildLogger::new(None)))?;
+        dependency_sizes.retain(|(name, _, _)| dep_packages.iter().any(|pkg| &pkg.name == name));
+
+        Ok(CompilerSession {
+            files,
+            dep_packages,
+            dependency_sizes,
+            options,
+            root,
+            targets: None,
+            dependencies: None,
+            last_result: None,
+        })
+    }
+
+    /// Add or replace a root package file. The file goes through the same checks as
+    /// `compile()`'s inputs (path, contents, size limits).
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = updateFile))]
+    pub fn update_file(&mut self, path: &str, content: &str) -> Result<(), String> {
+        validate_input_path(path)?;
+        check_input_contents(None, path, content)?;
+        self.ensure_root_path(path)?;
+        let root_sizes = self
+            .files
+            .iter()
+            .filter(|(other, _)| other.as_str() != path)
+            .map(|(other, contents)| (None, other.as_str(), contents.len()))
+            .chain(std::iter::once((None, path, content.len())));
+        let dependency_sizes =
+            self.dependency_sizes.iter().map(|(name, other, size)| (Some(name.as_str()), other.as_str(), *size));
+        check_input_sizes(
+            root_sizes.chain(dependency_sizes),
+            self.options.max_file_bytes,
+            self.options.max_total_bytes,
+        )?;
+        let vfs_path = self
+            .root
+            .join(path)
+            .map_err(|e| format!("Invalid path {}: {}", path, e))?;
+        ensure_vfs_parents(&vfs_path)?;
+        vfs_path
+            .create_file()
+            .and_then(|mut f| {
+                use std::io::Write;
+                write!(f, "{}", content)?;
+                Ok(())
+            })
+            .map_err(|e| format!("Failed to create file {}: {}", path, e))?;
+
+        let is_new = self.files.insert(path.to_string(), content.to_string()).is_none();
+        if is_new || path.ends_with("Move.toml") {
+            self.targets = None;
+        }
+        if path.ends_with("Move.toml") {
+            self.dependencies = None;
+        }
+        self.last_result = None;
+        Ok(())
+    }
+
+    /// Remove a root package file.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(js_name = removeFile))]
+    pub fn remove_file(&mut self, path: &str) -> Result<(), String> {
+        self.ensure_root_path(path)?;
+        if self.files.remove(path).is_none() {
+            return Err(format!("File {} is not part of the session", path));
+        }
+        self.root
+            .join(path)
+            .and_then(|p| p.remove_file())
+            .map_err(|e| format!("Failed to remove file {}: {}", path, e))?;
+
+        self.targets = None;
+        if path.ends_with("Move.toml") {
+            self.dependencies = None;
+        }
+        self.last_result = None;
+        Ok(())
+    }
+
+    /// Compile the current state. Output has the same shape as `compile()`.
+    pub fn compile(&mut self) -> MoveCompilerResult {
+        if let Some(result) = &self.last_result {
+            return result.clone();
+        }
+
+        let logger = BuildLogger::new(None);
+        let targets = match &self.targets {
+            Some(targets) => targets.clone(),
+            None => match build_package_targets(&self.files, &self.dep_packages, &self.options, &logger) {
+                Ok(targets) => {
+                    self.targets = Some(targets.clone());
+                    targets
+                }
+                Err(e) => return MoveCompilerResult::err(e),
+            },
+        };
+
+        let dependencies = match &self.dependencies {
+            _ if targets.targets.len() < 2 => None,
+            Some(dependencies) => Some(dependencies.clone()),
+            None => match precompile_dependencies(self.root.clone(), &targets.targets, &self.options) {
+                Ok(dependencies) => {
+                    self.dependencies = Some(dependencies.clone());
+                    Some(dependencies)
+                }
+                Err(failure) => return MoveCompilerResult::err(failure),
+            },
+        };
+
+        let graph_json = self.options.dependency_graph.clone();
+        let result = MoveCompilerResult::compiled(compile_targets(
+            self.root.clone(),
+            targets,
+            &self.options,
+            graph_json.as_deref(),
+            &logger,
+            dependencies,
+        ));
+        self.last_result = Some(result.clone());
+        result
+    }
+}
+
+impl CompilerSession {
+    fn ensure_root_path(&self, path: &str) -> Result<(), String> {
+        let owner = self
+            .dep_packages
+            .iter()
+            .find(|pkg| pkg.files.contains_key(path));
+        match owner {
+            Some(pkg) => Err(format!(
+                "File {} belongs to dependency '{}'; dependencies are fixed for a session",
+                path, pkg.name
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// Interface stubs for dependencies known only through RPC.
+//
+// `sui_getNormalizedMoveModule` / `sui_getNormalizedMoveModulesByPackage` return the public
+// surface of an on-chain module but no source. A `PackageGroup` carrying `normalizedModules`
+// gets one generated `.move` file per module that declares the same structs, enums and public
+// functions, so callers type-check against it:
+// - structs and enums keep their abilities, phantom/constrained type parameters and fields
+//   (positional and empty declarations are restored from the `pos<N>` / `dummy_field` encoding)
+// - `public` functions keep their signature (including `entry`) and get an `abort 0` body
+// - `public(package)` and private functions are left out; other packages cannot call them
+// Types are written with fully qualified addresses, so stubs need no named addresses.
+//
+// ORIGINAL SOURCE: sui-json-rpc-types/src/sui_move.rs - SuiMoveNormalizedModule
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Deserialize)]
+pub(crate) struct NormalizedModule {
+    pub(crate) address: String,
+    pub(crate) name: String,
+    #[serde(default)]
+    structs: BTreeMap<String, NormalizedStruct>,
+    #[serde(default)]
+    enums: BTreeMap<String, NormalizedEnum>,
+    #[serde(default, rename = "exposedFunctions")]
+    exposed_functions: BTreeMap<String, NormalizedFunction>,
+}
+
+#[derive(Deserialize)]
+struct AbilitySet {
+    abilities: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct StructTypeParameter {
+    constraints: AbilitySet,
+    #[serde(rename = "isPhantom")]
+    is_phantom: bool,
+}
+
+#[derive(Deserialize)]
+struct NormalizedField {
+    name: String,
+    #[serde(rename = "type")]
+    type_: NormalizedType,
+}
+
+#[derive(Deserialize)]
+struct NormalizedStruct {
+    abilities: AbilitySet,
+    #[serde(rename = "typeParameters")]
+    type_parameters: Vec<StructTypeParameter>,
+    fields: Vec<NormalizedField>,
+}
+
+#[derive(Deserialize)]
+struct NormalizedEnum {
+    abilities: AbilitySet,
+    #[serde(rename = "typeParameters")]
+    type_parameters: Vec<StructTypeParameter>,
+    variants: BTreeMap<String, Vec<NormalizedField>>,
+    #[serde(default, rename = "variantDeclarationOrder")]
+    variant_declaration_order: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct NormalizedFunction {
+    visibility: String,
+    #[serde(rename = "isEntry")]
+    is_entry: bool,
+    #[serde(rename = "typeParameters")]
+    type_parameters: Vec<AbilitySet>,
+    parameters: Vec<NormalizedType>,
+    #[serde(rename = "return")]
+    return_: Vec<NormalizedType>,
+}
+
+#[derive(Deserialize)]
+enum NormalizedType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    Address,
+    Signer,
+    Struct {
+        address: String,
+        module: String,
+        name: String,
+        #[serde(rename = "typeArguments")]
+        type_arguments: Vec<NormalizedType>,
+    },
+    Vector(Box<NormalizedType>),
+    TypeParameter(u16),
+    Reference(Box<NormalizedType>),
+    MutableReference(Box<NormalizedType>),
+}
+
+/// Identifiers that are keywords in Move 2024 and must be escaped to be used as names.
+const RESERVED: [&str; 28] = [
+    "abort", "acquires", "as", "break", "const", "continue", "copy", "else", "enum", "false",
+    "for", "fun", "friend", "if", "let", "loop", "match", "module", "move", "mut", "native",
+    "public", "return", "struct", "true", "type", "use", "while",
+];
+
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("`{}`", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn render_type(ty: &NormalizedType, type_params: &[String]) -> String {
+    match ty {
+        NormalizedType::Bool => "bool".to_string(),
+        NormalizedType::U8 => "u8".to_string(),
+        NormalizedType::U16 => "u16".to_string(),
+        NormalizedType::U32 => "u32".to_string(),
+        NormalizedType::U64 => "u64".to_string(),
+        NormalizedType::U128 => "u128".to_string(),
+        NormalizedType::U256 => "u256".to_string(),
+        NormalizedType::Address => "address".to_string(),
+        NormalizedType::Signer => "signer".to_string(),
+        NormalizedType::Struct { address, module, name, type_arguments } => {
+            let mut out = format!("{}::{}::{}", address, ident(module), ident(name));
+            if !type_arguments.is_empty() {
+                let args: Vec<String> = type_arguments.iter().map(|t| render_type(t, type_params)).collect();
+                let _ = write!(out, "<{}>", args.join(", "));
+            }
+            out
+        }
+        NormalizedType::Vector(inner) => format!("vector<{}>", render_type(inner, type_params)),
+        NormalizedType::TypeParameter(idx) => type_params
+            .get(*idx as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("T{}", idx)),
+        NormalizedType::Reference(inner) => format!("&{}", render_type(inner, type_params)),
+        NormalizedType::MutableReference(inner) => format!("&mut {}", render_type(inner, type_params)),
+    }
+}
+
+fn render_abilities(set: &AbilitySet) -> Vec<String> {
+    set.abilities.iter().map(|ability| ability.to_lowercase()).collect()
+}
+
+fn render_datatype_params(params: &[StructTypeParameter]) -> (Vec<String>, String) {
+    let names: Vec<String> = (0..params.len()).map(|idx| format!("T{}", idx)).collect();
+    if params.is_empty() {
+        return (names, String::new());
+    }
+    let decls: Vec<String> = params
+        .iter()
+        .zip(&names)
+        .map(|(param, name)| {
+            let mut decl = if param.is_phantom { format!("phantom {}", name) } else { name.clone() };
+            let constraints = render_abilities(&param.constraints);
+            if !constraints.is_empty() {
+                let _ = write!(decl, ": {}", constraints.join(" + "));
+            }
+            decl
+        })
+        .collect();
+    (names, format!("<{}>", decls.join(", ")))
+}
+
+/// Field list in declaration syntax: `{ a: u64 }`, `(u64, bool)` or `{}`.
+fn render_fields(fields: &[NormalizedField], type_params: &[String]) -> String {
+    // Source `{}` compiles to a single `dummy_field: bool`.
+    if fields.len() == 1 && fields[0].name == "dummy_field" && matches!(fields[0].type_, NormalizedType::Bool) {
+        return "{}".to_string();
+    }
+    let positional = !fields.is_empty()
+        && fields.iter().enumerate().all(|(idx, field)| field.name == format!("pos{}", idx));
+    if positional {
+        let types: Vec<String> = fields.iter().map(|f| render_type(&f.type_, type_params)).collect();
+        return format!("({})", types.join(", "));
+    }
+    let decls: Vec<String> = fields
+        .iter()
+        .map(|f| format!("{}: {}", ident(&f.name), render_type(&f.type_, type_params)))
+        .collect();
+    format!("{{ {} }}", decls.join(", "))
+}
+
+fn render_has(abilities: &AbilitySet) -> String {
+    let abilities = render_abilities(abilities);
+    if abilities.is_empty() {
+        String::new()
+    } else {
+        format!(" has {}", abilities.join(", "))
+    }
+}
+
+/// Generate the stub source for one normalized module.
+pub(crate) fn module_stub(module: &NormalizedModule) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "// Generated from normalized module JSON; declarations only.");
+    let _ = writeln!(out, "module {}::{} {{", module.address, ident(&module.name));
+
+    for (name, def) in &module.structs {
+        let (params, decl) = render_datatype_params(&def.type_parameters);
+        let fields = render_fields(&def.fields, &params);
+        let has = render_has(&def.abilities);
+        // Positional fields precede the ability list; named fields follow it.
+        if fields.starts_with('(') {
+            let _ = writeln!(out, "    public struct {}{}{}{};", ident(name), decl, fields, has);
+        } else {
+            let _ = writeln!(out, "    public struct {}{}{} {}", ident(name), decl, has, fields);
+        }
+    }
+
+    for (name, def) in &module.enums {
+        let (params, decl) = render_datatype_params(&def.type_parameters);
+        let order: Vec<&String> = match &def.variant_declaration_order {
+            Some(order) => order.iter().collect(),
+            None => def.variants.keys().collect(),
+        };
+        let variants: Vec<String> = order
+            .into_iter()
+            .filter_map(|variant| {
+                let fields = def.variants.get(variant)?;
+                let fields = if fields.is_empty() { String::new() } else { render_fields(fields, &params) };
+                Some(if fields.is_empty() || fields.starts_with('(') {
+                    format!("{}{}", ident(variant), fields)
+                } else {
+                    format!("{} {}", ident(variant), fields)
+                })
+            })
+            .collect();
+        let _ = writeln!(
+            out,
+            "    public enum {}{}{} {{ {} }}",
+            ident(name),
+            decl,
+            render_has(&def.abilities),
+            variants.join(", ")
+        );
+    }
+
+    for (name, def) in &module.exposed_functions {
+        if def.visibility != "Public" {
+            continue;
+        }
+        let params: Vec<String> = (0..def.type_parameters.len()).map(|idx| format!("T{}", idx)).collect();
+        let mut signature = format!("    public {}fun {}", if def.is_entry { "entry " } else { "" }, ident(name));
+        if !params.is_empty() {
+            let decls: Vec<String> = def
+                .type_parameters
+                .iter()
+                .zip(&params)
+                .map(|(set, param)| {
+                    let constraints = render_abilities(set);
+                    if constraints.is_empty() {
+                        param.clone()
+                    } else {
+                        format!("{}: {}", param, constraints.join(" + "))
+                    }
+                })
+                .collect();
+            let _ = write!(signature, "<{}>", decls.join(", "));
+        }
+        let args: Vec<String> = def
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(idx, ty)| format!("_a{}: {}", idx, render_type(ty, &params)))
+            .collect();
+        let _ = write!(signature, "({})", args.join(", "));
+        match def.return_.as_slice() {
+            [] => {}
+            [single] => {
+                let _ = write!(signature, ": {}", render_type(single, &params));
+            }
+            many => {
+                let types: Vec<String> = many.iter().map(|ty| render_type(ty, &params)).collect();
+                let _ = write!(signature, ": ({})", types.join(", "));
+            }
+        }
+        let _ = writeln!(out, "{} {{ abort 0 }}", signature);
+    }
+
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,1600 @@
+import path from "path";
+import { fileURLToPath } from "url";
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+// usage: node features_test.mjs [filter]
+// Behavior tests for the wasm exports over small in-memory packages. Only cases that need
+// the real Sui framework go to the network (set GITHUB_TOKEN to avoid rate limits).
+// Runs against the full build (`npm run build`). With `filter`, only cases whose name
+// contains it run.
+const DIST_DIR = path.resolve(__dirname, "../../dist/full");
+const FILTER = process.argv[2];
+
+const { initMoveCompiler, getWasmBindings, resolveDependencies } = await import(
+  path.join(DIST_DIR, "index.js")
+);
+
+await initMoveCompiler();
+const wasm = await getWasmBindings();
+
+// ---------------------------------------------------------------------------
+// Fixtures
+// ---------------------------------------------------------------------------
+
+// Just enough of MoveStdlib at 0x1 for the cases below: test mode needs
+// `std::unit_test::poison`.
+const STDLIB = {
+  name: "MoveStdlib",
+  addressMapping: { std: "0x1" },
+  files: {
+    "dependencies/MoveStdlib/Move.toml": `[package]
+name = "MoveStdlib"
+edition = "2024.beta"
+
+[addresses]
+std = "0x1"
+`,
+    "dependencies/MoveStdlib/sources/unit_test.move": `#[test_only]
+module std::unit_test {
+    public native fun poison();
+}
+`,
+  },
+};
+
+/**
+ * Root package files: a Move.toml for `name` at 0x0 (plus `addresses` and any extra
+ * `manifest` sections) and `sources` by path.
+ */
+function rootPackage(
+  sources,
+  { name = "demo", manifest = "", addresses = {} } = {}
+) {
+  const extra = Object.entries(addresses)
+    .map(([named, address]) => `${named} = "${address}"\n`)
+    .join("");
+  return {
+    "Move.toml": `[package]
+name = "${name}"
+edition = "2024.beta"
+
+[addresses]
+${name} = "0x0"
+${extra}${manifest}`,
+    ...sources,
+  };
+}
+
+/** A dependency group `name` at `address` with `modules` (file name -> source). */
+function dependency(name, address, modules, fields = {}) {
+  const files = {
+    [`dependencies/${name}/Move.toml`]: `[package]
+name = "${name}"
+edition = "2024.beta"
+
+[addresses]
+${name.toLowerCase()} = "${address}"
+`,
+  };
+  for (const [file, source] of Object.entries(modules)) {
+    files[`dependencies/${name}/sources/${file}`] = source;
+  }
+  return {
+    name,
+    files,
+    addressMapping: { [name.toLowerCase()]: address, std: "0x1" },
+    ...fields,
+  };
+}
+
+function compile(files, dependencies = [STDLIB], options = {}) {
+  const result = wasm.compile(
+    JSON.stringify(files),
+    JSON.stringify(dependencies),
+    JSON.stringify(options)
+  );
+  return {
+    success: result.success,
+    output: result.success ? JSON.parse(result.output) : undefined,
+    error: result.success ? undefined : result.output,
+    raw: result,
+  };
+}
+
+function runTests(files, dependencies = [STDLIB], options = {}) {
+  return wasm.test(
+    JSON.stringify(files),
+    JSON.stringify(dependencies),
+    JSON.stringify({ ansiColor: false, ...options })
+  );
+}
+
+/** `compile()` through `PackageBuilder`: one call per root file, dependency and dependency file. */
+function buildIncrementally(files, dependencies = [STDLIB], options = {}) {
+  const builder = new wasm.PackageBuilder();
+  for (const [file, contents] of Object.entries(files)) {
+    builder.addFile(file, contents);
+  }
+  for (const { name, files: depFiles = {}, ...info } of dependencies) {
+    builder.addDependency(name, JSON.stringify(info));
+    for (const [file, contents] of Object.entries(depFiles)) {
+      builder.addDependencyFile(name, file, contents);
+    }
+  }
+  return builder.compile(JSON.stringify(options));
+}
+
+/** `files` with their dependencies resolved as a user build would, Sui framework included. */
+async function withFramework(files) {
+  const resolved = await resolveDependencies({
+    files,
+    network: "mainnet",
+    githubToken: process.env.GITHUB_TOKEN,
+  });
+  return {
+    files: JSON.parse(resolved.files),
+    dependencies: JSON.parse(resolved.dependencies),
+  };
+}
+
+const canonical = (address) =>
+  "0x" + address.replace(/^0x/, "").padStart(64, "0");
+
+// ---------------------------------------------------------------------------
+// Assertions
+// ---------------------------------------------------------------------------
+
+function assert(condition, message) {
+  if (!condition) throw new Error(message);
+}
+
+function assertEqual(actual, expected, what) {
+  const a = JSON.stringify(actual);
+  const e = JSON.stringify(expected);
+  if (a !== e) throw new Error(`${what}: expected ${e}, got ${a}`);
+}
+
+function assertIncludes(text, fragment, what) {
+  if (typeof text !== "string" || !text.includes(fragment)) {
+    throw new Error(
+      `${what}: expected to contain ${JSON.stringify(fragment)}, got ${JSON.stringify(text)}`
+    );
+  }
+}
+
+function expectSuccess(result, what) {
+  if (!result.success) throw new Error(`${what} failed:\n${result.error}`);
+  return result.output;
+}
+
+function expectFailure(result, what) {
+  if (result.success) throw new Error(`${what} unexpectedly succeeded`);
+  return result.error;
+}
+
+// ---------------------------------------------------------------------------
+// Cases
+// ---------------------------------------------------------------------------
+
+const cases = [];
+function test(name, fn) {
+  cases.push({ name, fn });
+}
+
+test("tree-shaking reports declared but unused dependencies as pruned", () => {
+  const used = dependency("Used", "0xa", {
+    "used.move": "module used::used { public fun one(): u64 { 1 } }\n",
+  });
+  const unused = dependency("Unused", "0xb", {
+    "unused.move": "module unused::unused { public fun two(): u64 { 2 } }\n",
+  });
+  const files = rootPackage({
+    "sources/main.move":
+      "module demo::main { public fun f(): u64 { used::used::one() } }\n",
+  });
+  const output = expectSuccess(
+    compile(files, [STDLIB, used, unused]),
+    "compile"
+  );
+  assert(
+    output.dependencies.some((id) => canonical(id) === canonical("0xa")),
+    `dependencies should list 0xa: ${output.dependencies}`
+  );
+  assert(
+    output.dependencies.some((id) => canonical(id) === canonical("0xb")),
+    `dependencies should keep 0xb for the digest: ${output.dependencies}`
+  );
+  assert(
+    output.prunedDependencies.includes(canonical("0xb")),
+    `prunedDependencies should list 0xb: ${output.prunedDependencies}`
+  );
+});
+
+test("a malformed root Move.toml fails with the TOML error", () => {
+  const files = {
+    "Move.toml": '[package]\nname = "demo"\nedition = "2024.beta\n',
+    "sources/main.move": "module demo::main {}\n",
+  };
+  const error = expectFailure(compile(files), "compile");
+  assertIncludes(error, "Failed to parse Move.toml", "error");
+});
+
+test("short and full-length addresses are accepted, malformed ones named", () => {
+  const full = "0x" + "0".repeat(63) + "3";
+  const files = rootPackage(
+    {
+      "sources/main.move": `module demo::main {
+    const SHORT: address = @short;
+    const FULL: address = @full;
+    public fun short(): address { SHORT }
+    public fun full(): address { FULL }
+}
+`,
+    },
+    { addresses: { short: "0x2", full } }
+  );
+  const output = expectSuccess(
+    compile(files, [STDLIB], { emitConstants: true }),
+    "compile"
+  );
+  const values = output.constants.main.map((constant) => constant.value);
+  assert(values.includes(canonical("0x2")), `0x2 padded: ${values}`);
+  assert(values.includes(full), `full-length address kept: ${values}`);
+
+  const malformed = rootPackage(
+    { "sources/main.move": "module demo::main {}\n" },
+    { addresses: { long: "0x" + "1".repeat(66), bad: "0xZZ" } }
+  );
+  const error = expectFailure(compile(malformed), "compile");
+  assertIncludes(error, "'long'", "error");
+  assertIncludes(error, "longer than 32 bytes", "error");
+  assertIncludes(error, "'bad'", "error");
+  assertIncludes(error, "not valid hex", "error");
+});
+
+test("an unknown edition fails instead of compiling as legacy", () => {
+  const files = rootPackage({ "sources/main.move": "module demo::main {}\n" });
+  files["Move.toml"] = files["Move.toml"].replace("2024.beta", "2024.betaa");
+  const error = expectFailure(compile(files), "compile");
+  assertIncludes(error, 'Unsupported edition "2024.betaa"', "error");
+  assertIncludes(error, "2024.beta", "supported editions");
+});
+
+test("compile_workspace builds two roots against one shared dependency", () => {
+  const shared = dependency("Shared", "0xa", {
+    "shared.move": "module shared::shared { public fun one(): u64 { 1 } }\n",
+  });
+  const packages = [
+    {
+      name: "alpha",
+      files: rootPackage(
+        {
+          "sources/a.move":
+            "module alpha::a { public fun f(): u64 { shared::shared::one() } }\n",
+        },
+        { name: "alpha" }
+      ),
+    },
+    {
+      name: "beta",
+      files: rootPackage(
+        {
+          "sources/b.move":
+            "module beta::b { public fun g(): u64 { shared::shared::one() + 1 } }\n",
+        },
+        { name: "beta" }
+      ),
+    },
+  ];
+  const result = wasm.compile_workspace(
+    JSON.stringify(packages),
+    JSON.stringify([STDLIB, shared]),
+    JSON.stringify({})
+  );
+  if (!result.success) throw new Error(`compile_workspace failed:\n${result.output}`);
+  const outputs = JSON.parse(result.output);
+  assertEqual(Object.keys(outputs).sort(), ["alpha", "beta"], "packages");
+  assertEqual(outputs.alpha.moduleNames, ["a"], "alpha modules");
+  assertEqual(outputs.beta.moduleNames, ["b"], "beta modules");
+  for (const name of ["alpha", "beta"]) {
+    assert(
+      outputs[name].dependencies.some((id) => canonical(id) === canonical("0xa")),
+      `${name} links the shared dependency: ${outputs[name].dependencies}`
+    );
+  }
+  assert(
+    JSON.stringify(outputs.alpha.digest) !== JSON.stringify(outputs.beta.digest),
+    "each root has its own digest"
+  );
+});
+
+test("warningsAsErrors fails on shown warnings only", () => {
+  const files = rootPackage({
+    "sources/main.move":
+      "module demo::main { public fun f() { let unused = 1; } }\n",
+  });
+  const plain = expectSuccess(compile(files), "compile");
+  assertIncludes(plain.warnings, "unused", "warnings without the flag");
+
+  const strict = compile(files, [STDLIB], { warningsAsErrors: true });
+  assertIncludes(expectFailure(strict, "compile"), "unused", "error");
+  assert(strict.raw.warningCount >= 1, "the failure counts the warning");
+
+  expectSuccess(
+    compile(files, [STDLIB], { warningsAsErrors: true, silenceWarnings: true }),
+    "compile with silenced warnings"
+  );
+});
+
+test("compute_deps_digest is order independent and tracks pinned revisions", () => {
+  const stdlib = {
+    name: "MoveStdlib",
+    git: "https://github.com/MystenLabs/sui.git",
+    rev: "a1b2c3d4e5f6a1b2c3d4e5f6a1b2c3d4e5f6a1b2",
+    subdir: "crates/sui-framework/packages/move-stdlib",
+  };
+  const sui = {
+    name: "Sui",
+    git: stdlib.git,
+    rev: stdlib.rev,
+    subdir: "crates/sui-framework/packages/sui-framework",
+    deps: { MoveStdlib: "MoveStdlib" },
+  };
+  const local = { name: "Local", local: "../local" };
+  const digest = wasm.compute_deps_digest(
+    JSON.stringify({ packages: [stdlib, sui, local] })
+  );
+  assert(/^[0-9A-F]{64}$/.test(digest), `uppercase SHA-256 hex: ${digest}`);
+  assertEqual(
+    wasm.compute_deps_digest(JSON.stringify({ packages: [local, sui, stdlib] })),
+    digest,
+    "digest of the same packages in another order"
+  );
+  const moved = { ...sui, rev: "0".repeat(40) };
+  assert(
+    wasm.compute_deps_digest(JSON.stringify({ packages: [stdlib, moved, local] })) !==
+      digest,
+    "a different pinned revision changes the digest"
+  );
+  const unpinned = { name: "Sui", git: stdlib.git };
+  assertEqual(
+    wasm.compute_deps_digest(JSON.stringify({ packages: [unpinned] })),
+    "",
+    "a git package without a revision"
+  );
+});
+
+test("compute_deps_digest_checked reports malformed input", () => {
+  const local = { name: "Local", local: "../local" };
+  const graph = JSON.stringify({ packages: [local] });
+  const ok = JSON.parse(wasm.compute_deps_digest_checked(graph));
+  assertEqual(ok.ok, true, "valid input");
+  assertEqual(ok.digest, wasm.compute_deps_digest(graph), "checked digest");
+
+  const garbage = JSON.parse(wasm.compute_deps_digest_checked("{not json"));
+  assertEqual(garbage.ok, false, "malformed JSON");
+  assertIncludes(garbage.error, "Failed to parse resolved graph JSON", "malformed JSON error");
+
+  const unpinned = JSON.parse(
+    wasm.compute_deps_digest_checked(
+      JSON.stringify({ packages: [{ name: "Sui", git: "https://github.com/MystenLabs/sui.git" }] })
+    )
+  );
+  assertEqual(unpinned.ok, false, "git package without a revision");
+  assertIncludes(unpinned.error, "Package 'Sui': git dependency has no pinned `rev`", "rev error");
+
+  const sourceless = JSON.parse(
+    wasm.compute_deps_digest_checked(JSON.stringify({ packages: [{ name: "Bare" }] }))
+  );
+  assertEqual(sourceless.ok, false, "package without a source");
+  assertIncludes(sourceless.error, "Package 'Bare'", "source error");
+});
+
+test("moduleNames align with modules", () => {
+  const files = rootPackage({
+    "sources/b.move": "module demo::b { public fun f(): u64 { 1 } }\n",
+    "sources/a.move":
+      "module demo::a { public fun g(): u64 { demo::b::f() } }\n",
+    "sources/c.move": "module demo::c {}\n",
+  });
+  const output = expectSuccess(compile(files), "compile");
+  assertEqual(output.moduleNames.length, output.modules.length, "lengths");
+  assertEqual(output.moduleCount, output.modules.length, "moduleCount");
+  // `a` uses `b`, so `b` comes first; names follow the topological order.
+  assert(
+    output.moduleNames.indexOf("b") < output.moduleNames.indexOf("a"),
+    `b before a: ${output.moduleNames}`
+  );
+  output.moduleNames.forEach((name, i) => {
+    const single = wasm.disassemble_all(JSON.stringify([output.modules[i]]));
+    assert(single.success, `module ${i} disassembles: ${single.output}`);
+    assertEqual(Object.keys(JSON.parse(single.output)), [name], `module ${i}`);
+    assertEqual(
+      Buffer.from(output.modules[i], "base64").length,
+      output.moduleSizes[i],
+      `size of ${name}`
+    );
+  });
+});
+
+test("includePackages runs the tests of named source dependencies", () => {
+  const mylib = dependency("MyLib", "0xa", {
+    "lib.move": `module mylib::lib {
+    public fun one(): u64 { 1 }
+    #[test]
+    fun lib_test() { assert!(one() == 1, 0) }
+}
+`,
+  });
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    #[test]
+    fun root_test() { assert!(mylib::lib::one() == 1, 0) }
+}
+`,
+  });
+  const rootOnly = runTests(files, [STDLIB, mylib]);
+  assert(rootOnly.passed, `root tests pass:\n${rootOnly.output}`);
+  assertEqual(rootOnly.numRun, 1, "tests run without includePackages");
+  assertEqual(rootOnly.numFiltered, 1, "dependency tests filtered");
+
+  const withLib = runTests(files, [STDLIB, mylib], {
+    includePackages: ["MyLib"],
+  });
+  assert(withLib.passed, `all tests pass:\n${withLib.output}`);
+  assertEqual(withLib.numRun, 2, "tests run with includePackages");
+  assertIncludes(withLib.output, "lib_test", "runner output");
+});
+
+test("test failures distinguish native aborts from Move aborts", () => {
+  // `sui::address::from_bytes` is backed by the Sui natives the runner links at 0x2; it
+  // aborts natively on input that is not 32 bytes.
+  const sui = dependency("Sui", "0x2", {
+    "address.move": `module sui::address {
+    public native fun from_bytes(bytes: vector<u8>): address;
+}
+`,
+  });
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    #[test]
+    fun native_abort() { sui::address::from_bytes(vector[1u8]); }
+    #[test]
+    fun move_abort() { abort 7 }
+}
+`,
+  });
+  const result = runTests(files, [STDLIB, sui]);
+  assert(!result.passed, "both tests fail");
+  const byTest = Object.fromEntries(
+    result.failures.map((failure) => [failure.test.split("::").pop(), failure])
+  );
+
+  const native = byTest.native_abort;
+  assert(native, `native_abort is reported:\n${result.output}`);
+  assertEqual(native.kind, "native", "native_abort kind");
+  assertIncludes(native.nativeFunction, "address::from_bytes", "nativeFunction");
+  assertEqual(typeof native.abortCode, "number", "native abort code");
+
+  const move = byTest.move_abort;
+  assert(move, `move_abort is reported:\n${result.output}`);
+  assertEqual(move.kind, "abort", "move_abort kind");
+  assertEqual(move.abortCode, 7, "move_abort code");
+  assertEqual(move.nativeFunction, undefined, "move_abort nativeFunction");
+});
+
+test("root and dependency files on one path with different contents are rejected", () => {
+  const stray = dependency("Stray", "0xa", {});
+  stray.files["sources/main.move"] = `module stray::main {}
+`;
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {}
+`,
+  });
+  const result = compile(files, [STDLIB, stray]);
+  expectFailure(result, "colliding path");
+  assertIncludes(
+    result.error,
+    "sources/main.move (root and dependency 'Stray')",
+    "collision error"
+  );
+});
+
+test("objects a test run leaves in the test store do not leak into the next run", async () => {
+  const counter = `module demo::counter {
+    public struct Counter has key { id: UID }
+
+    public fun share(ctx: &mut TxContext) {
+        transfer::share_object(Counter { id: object::new(ctx) })
+    }
+}
+`;
+  const sharing = await withFramework(
+    rootPackage({
+      "sources/counter.move": counter,
+      "tests/counter_tests.move": `#[test_only]
+module demo::counter_tests {
+    use sui::test_scenario;
+    use demo::counter::{Self, Counter};
+
+    #[test]
+    fun shares_counter() {
+        let mut scenario = test_scenario::begin(@0xA);
+        counter::share(scenario.ctx());
+        scenario.next_tx(@0xA);
+        assert!(test_scenario::has_most_recent_shared<Counter>(), 0);
+        scenario.end();
+    }
+}
+`,
+    })
+  );
+  const checking = await withFramework(
+    rootPackage({
+      "sources/counter.move": counter,
+      "tests/counter_tests.move": `#[test_only]
+module demo::counter_tests {
+    use sui::test_scenario;
+    use demo::counter::Counter;
+
+    #[test]
+    fun starts_without_counter() {
+        let mut scenario = test_scenario::begin(@0xA);
+        scenario.next_tx(@0xA);
+        assert!(!test_scenario::has_most_recent_shared<Counter>(), 0);
+        scenario.end();
+    }
+}
+`,
+    })
+  );
+
+  const fresh = runTests(checking.files, checking.dependencies);
+  assert(fresh.passed, `fresh run passes:\n${fresh.output}`);
+  const first = runTests(sharing.files, sharing.dependencies);
+  assert(first.passed, `sharing run passes:\n${first.output}`);
+  const second = runTests(checking.files, checking.dependencies);
+  assert(second.passed, `run after sharing passes:\n${second.output}`);
+  assertEqual(second.output, fresh.output, "output after a sharing run");
+  const again = runTests(sharing.files, sharing.dependencies);
+  assertEqual(again.output, first.output, "output of a repeated run");
+});
+
+test("input paths that escape the package root are rejected", () => {
+  const source = `module demo::evil {}
+`;
+  const parent = compile(rootPackage({ "../evil.move": source }));
+  expectFailure(parent, "'..' path");
+  assertIncludes(
+    parent.error,
+    "Invalid path ../evil.move: '..' components are not allowed",
+    "'..' path error"
+  );
+
+  const absolute = compile(rootPackage({ "/abs/path.move": source }));
+  expectFailure(absolute, "absolute path");
+  assertIncludes(
+    absolute.error,
+    "Invalid path /abs/path.move: absolute paths are not allowed",
+    "absolute path error"
+  );
+
+  const nested = compile(rootPackage({ "sources/a/../../../evil.move": source }));
+  expectFailure(nested, "nested '..' path");
+});
+
+test("format_source is idempotent and reports parse errors", () => {
+  const messy = `module demo::fmt {
+  use std::vector;
+      public fun sum(v: &vector<u64>): u64 {
+  let mut total = 0;   let mut i = 0;
+     while (i < vector::length(v)) { total = total + *vector::borrow(v, i); i = i + 1; };
+  total
+ }
+}
+`;
+  for (const options of [undefined, JSON.stringify({ indent: 2, maxWidth: 60 })]) {
+    const first = wasm.format_source(messy, options);
+    assert(first.success, `formatting succeeds:\n${first.output}`);
+    const second = wasm.format_source(first.output, options);
+    assert(second.success, `reformatting succeeds:\n${second.output}`);
+    assertEqual(second.output, first.output, `reformatted output (${options})`);
+  }
+
+  const broken = wasm.format_source("module demo::fmt { fun f( }\n");
+  assert(!broken.success, "unparsable source is rejected");
+});
+
+test("unusedDependencies lists declared dependencies the root never uses", () => {
+  const used = dependency("Used", "0xa", {
+    "lib.move": `module used::lib {
+    public fun one(): u64 { 1 }
+}
+`,
+  });
+  const unused = dependency("Unused", "0xb", {
+    "lib.move": `module unused::lib {
+    public fun two(): u64 { 2 }
+}
+`,
+  });
+  // Provided, unreachable, but not declared by the root: a transitive dependency.
+  const transitive = dependency("Transitive", "0xc", {
+    "lib.move": `module transitive::lib {
+    public fun three(): u64 { 3 }
+}
+`,
+  });
+  const files = rootPackage(
+    {
+      "sources/main.move": `module demo::main {
+    public fun one(): u64 { used::lib::one() }
+}
+`,
+    },
+    {
+      manifest: `
+[dependencies]
+Used = { local = "dependencies/Used" }
+Unused = { local = "dependencies/Unused" }
+`,
+    }
+  );
+  const output = expectSuccess(
+    compile(files, [STDLIB, used, unused, transitive]),
+    "build with an unused dependency"
+  );
+  assertEqual(output.unusedDependencies, ["Unused"], "unusedDependencies");
+});
+
+
+test("dev dependencies are resolvable in test() but not in compile()", () => {
+  const helpers = dependency(
+    "Helpers",
+    "0xa",
+    {
+      "fixtures.move": `module helpers::fixtures {
+    public fun seed(): u64 { 7 }
+}
+`,
+    },
+    { scope: "dev" }
+  );
+  const main = `module demo::main {
+    public fun one(): u64 { 1 }
+}
+`;
+  const files = rootPackage({
+    "sources/main.move": main,
+    "tests/main_tests.move": `#[test_only]
+module demo::main_tests {
+    #[test]
+    fun uses_fixture() { assert!(helpers::fixtures::seed() + demo::main::one() == 8, 0) }
+}
+`,
+  });
+
+  const tested = runTests(files, [STDLIB, helpers]);
+  assert(tested.passed, `tests resolve the dev dependency:\n${tested.output}`);
+  assertEqual(tested.numPassed, 1, "tests passed");
+
+  const built = expectSuccess(compile(files, [STDLIB, helpers]), "build");
+  assertEqual(built.unusedDependencies, [], "dev dependency is not part of the build");
+
+  const leaking = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { helpers::fixtures::seed() }
+}
+`,
+  });
+  expectFailure(compile(leaking, [STDLIB, helpers]), "build using a dev dependency");
+});
+
+test("expectedDigest fails the build when the digest differs", () => {
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { 1 }
+}
+`,
+  });
+  const output = expectSuccess(compile(files), "build");
+  const hex = Buffer.from(output.digest).toString("hex");
+
+  for (const expectedDigest of [hex, hex.toUpperCase(), `0x${hex}`]) {
+    expectSuccess(compile(files, [STDLIB], { expectedDigest }), `digest ${expectedDigest}`);
+  }
+
+  const wrong = "00".repeat(32);
+  const mismatch = compile(files, [STDLIB], { expectedDigest: wrong });
+  expectFailure(mismatch, "wrong expected digest");
+  assertIncludes(mismatch.error, "Package digest mismatch", "mismatch error");
+  assertIncludes(mismatch.error, `expected: ${wrong}`, "expected digest");
+  assertIncludes(mismatch.error, `actual:   ${hex}`, "actual digest");
+});
+
+test("withFriends lists the friends of each module", () => {
+  // `public(package)` compiles to friend visibility, with its callers as friends.
+  const files = rootPackage({
+    "sources/vault.move": `module demo::vault {
+    public(package) fun secret(): u64 { 42 }
+}
+`,
+    "sources/teller.move": `module demo::teller {
+    public fun reveal(): u64 { demo::vault::secret() }
+}
+`,
+  });
+  const output = expectSuccess(compile(files, [STDLIB], { withFriends: true }), "build");
+  assertEqual(output.friends.vault, [`${canonical("0x0")}::teller`], "vault friends");
+  assertEqual(output.friends.teller, [], "teller friends");
+
+  const plain = expectSuccess(compile(files), "build without withFriends");
+  assertEqual(plain.friends, undefined, "friends without withFriends");
+});
+
+test("a dependency without publishedIdForOutput is linked at its own address", () => {
+  // Without an output address a dependency maps to its compilation address, so tree
+  // shaking keeps it and has nothing to report.
+  const mylib = dependency("MyLib", "0xa", {
+    "lib.move": `module mylib::lib {
+    public fun one(): u64 { 1 }
+}
+`,
+  });
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { mylib::lib::one() }
+}
+`,
+  });
+  const output = expectSuccess(
+    compile(files, [STDLIB, mylib], { strictTreeShaking: true }),
+    "strict build"
+  );
+  assertEqual(output.treeShakeWarnings, undefined, "treeShakeWarnings");
+  assertEqual(output.addressMapping[canonical("0xa")], canonical("0xa"), "mapping");
+  assert(
+    output.dependencies.some((id) => canonical(id) === canonical("0xa")),
+    `dependencies keep 0xa: ${JSON.stringify(output.dependencies)}`
+  );
+});
+
+test("lockEnvironment links dependencies at the IDs the root Move.lock pins", () => {
+  const mylib = dependency("mylib", "0xa", {
+    "lib.move": `module mylib::lib {
+    public fun one(): u64 { 1 }
+}
+`,
+  });
+  delete mylib.addressMapping;
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { mylib::lib::one() }
+    #[test]
+    fun linked_at_lock_address() { assert!(@mylib == @0xb, 0) }
+}
+`,
+    "Move.lock": `[move]
+version = 4
+
+[pinned.mainnet.mylib]
+source = { local = "dependencies/mylib" }
+address-override = "0xb"
+published-at = "0xc"
+`,
+  });
+
+  const manifest = expectSuccess(compile(files, [STDLIB, mylib]), "build without lockEnvironment");
+  assertEqual(manifest.addressMapping[canonical("0xa")], canonical("0xa"), "manifest address");
+
+  const locked = expectSuccess(
+    compile(files, [STDLIB, mylib], { lockEnvironment: "mainnet" }),
+    "build with lockEnvironment"
+  );
+  assertEqual(locked.addressMapping[canonical("0xb")], canonical("0xc"), "locked address");
+  const dependencies = locked.dependencies.map(canonical);
+  assert(
+    dependencies.includes(canonical("0xc")) && !dependencies.includes(canonical("0xa")),
+    `dependencies link the lock's ID: ${JSON.stringify(locked.dependencies)}`
+  );
+
+  const tested = runTests(files, [STDLIB, mylib], { lockEnvironment: "mainnet" });
+  assert(tested.passed, `tests see the lock's address:\n${tested.output}`);
+});
+
+test("parse_manifest reads a full manifest", () => {
+  const manifest = JSON.parse(
+    wasm.parse_manifest(`[package]
+name = "demo"
+edition = "2024"
+published-at = "0x5"
+
+[dependencies]
+Sui = { git = "https://github.com/MystenLabs/sui.git", subdir = "crates/sui-framework/packages/sui-framework", rev = "framework/mainnet", override = true }
+Local = { local = "../local" }
+Chain = { on-chain = true }
+Named = { r.mvr = "@pkg/named" }
+Renamed = { local = "../renamed", rename-from = "Original" }
+
+[dev-dependencies]
+Fixtures = { local = "../fixtures" }
+
+[addresses]
+demo = "0x0"
+unset = "_"
+`)
+  );
+  assertEqual(manifest.error, undefined, "parse error");
+  assertEqual(manifest.name, "demo", "name");
+  assertEqual(manifest.edition, "2024", "edition");
+  assertEqual(manifest.publishedAt, "0x5", "publishedAt");
+  assertEqual(manifest.addresses.demo, "0x0", "assigned address");
+  assertEqual(manifest.addresses.unset, null, "unassigned address");
+
+  const deps = Object.fromEntries(manifest.dependencies.map((dep) => [dep.name, dep]));
+  assertEqual(Object.keys(deps).sort(), ["Chain", "Local", "Named", "Renamed", "Sui"], "names");
+  assertEqual(deps.Sui.kind, "git", "git kind");
+  assertEqual(deps.Sui.rev, "framework/mainnet", "git rev");
+  assertEqual(deps.Sui.subdir, "crates/sui-framework/packages/sui-framework", "git subdir");
+  assertEqual(deps.Sui.override, true, "override");
+  assertEqual(deps.Local.kind, "local", "local kind");
+  assertEqual(deps.Local.local, "../local", "local path");
+  assertEqual(deps.Chain.kind, "onChain", "on-chain kind");
+  assertEqual(deps.Named.kind, "external", "external kind");
+  assertEqual(deps.Named.resolver, "mvr", "resolver");
+  assertEqual(deps.Named.data, "@pkg/named", "resolver data");
+  assertEqual(deps.Renamed.renameFrom, "Original", "renameFrom");
+
+  assertEqual(manifest.devDependencies.length, 1, "dev dependencies");
+  assertEqual(manifest.devDependencies[0].name, "Fixtures", "dev dependency name");
+  assertEqual(manifest.devDependencies[0].local, "../fixtures", "dev dependency path");
+
+  const broken = JSON.parse(wasm.parse_manifest("[package\nname = 1"));
+  assertIncludes(broken.error, "Failed to parse Move.toml", "parse error");
+});
+
+test("a root syntax error is reported before dependencies are compiled", () => {
+  // The dependency has a type error; it is only found if the dependency gets compiled.
+  const mylib = dependency("MyLib", "0xa", {
+    "lib.move": `module mylib::lib {
+    public fun one(): u64 { true }
+}
+`,
+  });
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { 1
+}
+`,
+  });
+  const started = Date.now();
+  const result = compile(files, [STDLIB, mylib], { ansiColor: false });
+  const elapsed = Date.now() - started;
+  expectFailure(result, "build with a root syntax error");
+  assertIncludes(result.error, "sources/main.move", "diagnostic file");
+  assert(!result.error.includes("lib.move"), `only the root is reported:\n${result.error}`);
+  assertEqual(result.raw.errorCount, 1, "errorCount");
+  assert(elapsed < 10000, `syntax error reported in ${elapsed}ms`);
+});
+
+test("test-only code stays out of a regular build", () => {
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun value(): u64 { 1 }
+
+    #[test_only]
+    public fun test_helper(): u64 { value() + 1 }
+
+    #[test]
+    fun value_test() { assert!(test_helper() == 2, 0) }
+}
+`,
+    "sources/fixtures.move": `#[test_only]
+module demo::fixtures {
+    public fun seed(): u64 { 7 }
+}
+`,
+  });
+  const output = expectSuccess(compile(files), "regular build");
+  assertEqual(output.moduleNames, ["main"], "moduleNames");
+  const disassembly = wasm.disassemble_all(JSON.stringify(output.modules));
+  assert(disassembly.success, `disassembly:\n${disassembly.output}`);
+  const main = JSON.parse(disassembly.output).main;
+  assertIncludes(main, "value", "main disassembly");
+  for (const name of ["test_helper", "value_test"]) {
+    assert(!main.includes(name), `${name} is not in the published module`);
+  }
+
+  const tested = runTests(files);
+  assert(tested.passed, `test build keeps test-only code:\n${tested.output}`);
+});
+
+test("sui_move_version and sui_version report the bundled versions", () => {
+  for (const [name, version] of [
+    ["sui_move_version", wasm.sui_move_version()],
+    ["sui_version", wasm.sui_version()],
+  ]) {
+    assert(version !== "unknown", `${name} is known`);
+    assert(/^\d+\.\d+\.\d+/.test(version), `${name} is a version: ${version}`);
+  }
+});
+
+test("build_info reports toolchain and protocol metadata", () => {
+  const info = JSON.parse(wasm.build_info());
+  assertEqual(info.sui_move_version, wasm.sui_move_version(), "sui_move_version");
+  assertEqual(info.sui_version, wasm.sui_version(), "sui_version");
+  const { min, max } = info.protocol_version;
+  assert(Number.isInteger(info.protocol_version.default), "default protocol version");
+  assert(Number.isInteger(min) && Number.isInteger(max) && min <= max, "protocol range");
+  assert(Number.isInteger(info.bytecode_version), "bytecode version");
+  for (const edition of ["legacy", "2024.beta"]) {
+    assert(info.editions.includes(edition), `editions include ${edition}`);
+  }
+  // Every listed edition is accepted by the compiler.
+  for (const edition of info.editions) {
+    const result = wasm.format_source("module demo::m {}\n", JSON.stringify({ edition }));
+    assert(result.success, `edition ${edition}: ${result.output}`);
+  }
+});
+
+test("gasSchedule protocol meters tests differently from the unit-test schedule", () => {
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    #[test]
+    fun sums() {
+        let mut total = 0;
+        let mut i = 0;
+        while (i < 1000) { total = total + i; i = i + 1; };
+        assert!(total == 499500, 0)
+    }
+}
+`,
+  });
+  const unitTest = runTests(files);
+  assert(unitTest.passed, `unit-test schedule:\n${unitTest.output}`);
+  const protocol = runTests(files, [STDLIB], { gasSchedule: "protocol" });
+  assert(protocol.passed, `protocol schedule:\n${protocol.output}`);
+  assertEqual(runTests(files, [STDLIB], { gasSchedule: "unitTest" }).gas, unitTest.gas, "default");
+  assert(unitTest.gas.totalGasUsed > 0, "gas is metered");
+  assert(
+    protocol.gas.totalGasUsed !== unitTest.gas.totalGasUsed,
+    `schedules differ: ${protocol.gas.totalGasUsed} vs ${unitTest.gas.totalGasUsed}`
+  );
+
+  const invalid = runTests(files, [STDLIB], { gasSchedule: "cheap" });
+  assert(!invalid.passed, "unknown schedule is rejected");
+  assertIncludes(invalid.output, 'Unsupported gasSchedule "cheap"', "schedule error");
+});
+
+test("silenceNotes and silenceWarnings are independent", () => {
+  const files = rootPackage({
+    "sources/old.move": `module demo::old {
+    #[deprecated(note = b"use demo::old::two")]
+    public fun one(): u64 { 1 }
+    public fun two(): u64 { 2 }
+}
+`,
+    "sources/main.move": `module demo::main {
+    public fun value(): u64 { demo::old::one() }
+}
+`,
+  });
+  const options = { ansiColor: false };
+  const all = expectSuccess(compile(files, [STDLIB], options), "build");
+  const noNotes = expectSuccess(
+    compile(files, [STDLIB], { ...options, silenceNotes: true }),
+    "build with silenceNotes"
+  );
+  const noWarnings = expectSuccess(
+    compile(files, [STDLIB], { ...options, silenceWarnings: true }),
+    "build with silenceWarnings"
+  );
+  const notes = all.notes || [];
+  const remaining = noNotes.notes || [];
+
+  const guidance = [...notes, all.warnings || ""].join("\n");
+  assertIncludes(guidance, "use demo::old::two", "deprecation guidance");
+  assertEqual(noNotes.warnings, all.warnings, "warnings under silenceNotes");
+  assertEqual(noWarnings.notes, all.notes, "notes under silenceWarnings");
+  for (const note of remaining) {
+    assert(notes.includes(note), `silenceNotes only removes notes: ${note}`);
+  }
+  // Compiler notes are reported once: in `notes`, never in `warnings` as well.
+  for (const note of notes.filter((note) => !remaining.includes(note))) {
+    assert(!(all.warnings || "").includes(note), `note also in warnings: ${note}`);
+  }
+});
+
+test("diagnostics echo a decimal manifest address in decimal", () => {
+  // Both names bind 42, so `first::m` and `second::m` are the same module.
+  const files = rootPackage(
+    {
+      "sources/first.move": `module first::m {}
+`,
+      "sources/second.move": `module second::m {}
+`,
+    },
+    { addresses: { first: "42", second: "42" } }
+  );
+  const result = compile(files, [STDLIB], { ansiColor: false });
+  expectFailure(result, "build with a duplicate module");
+  assert(/\b42\b/.test(result.error), `decimal address in:\n${result.error}`);
+  assert(!/0x0*2a\b/i.test(result.error), `no hex address in:\n${result.error}`);
+});
+
+test("disassemble_all disassembles a package in one call", () => {
+  const files = rootPackage({
+    "sources/a.move": `module demo::a {
+    public fun one(): u64 { 1 }
+}
+`,
+    "sources/b.move": `module demo::b {
+    public fun two(): u64 { demo::a::one() + 1 }
+}
+`,
+  });
+  const output = expectSuccess(compile(files), "build");
+  const result = wasm.disassemble_all(JSON.stringify(output.modules));
+  assert(result.success, `disassembly:\n${result.output}`);
+  const texts = JSON.parse(result.output);
+  assertEqual(Object.keys(texts).sort(), ["a", "b"], "disassembled modules");
+  assertIncludes(texts.a, "public one(): u64", "a disassembly");
+  assertIncludes(texts.b, "public two(): u64", "b disassembly");
+
+  const corrupt = [output.modules[0], Buffer.from("not a module").toString("base64")];
+  const failed = wasm.disassemble_all(JSON.stringify(corrupt));
+  assert(!failed.success, "a corrupt module fails the call");
+  assertIncludes(failed.output, "Module #1 failed to deserialize", "failing module");
+});
+
+test("emitConstants recovers constant names and values", () => {
+  const files = rootPackage({
+    "sources/errors.move": `module demo::errors {
+    const E_FOO: u64 = 7;
+    const OWNER: address = @0xcafe;
+
+    public fun check(x: u64) {
+        assert!(x > 0, E_FOO);
+        assert!(@0xcafe == OWNER, E_FOO);
+    }
+}
+`,
+  });
+  const output = expectSuccess(compile(files, [STDLIB], { emitConstants: true }), "build");
+  const constants = Object.fromEntries(
+    output.constants.errors.map((constant) => [constant.name, constant])
+  );
+  const foo = constants.E_FOO;
+  assert(foo, `E_FOO listed: ${JSON.stringify(output.constants)}`);
+  assertEqual(foo.type, "u64", "E_FOO type");
+  assertEqual(foo.value, "7", "E_FOO value");
+  assertEqual(
+    foo.valueBytesB64,
+    Buffer.from([7, 0, 0, 0, 0, 0, 0, 0]).toString("base64"),
+    "E_FOO bytes"
+  );
+  assertEqual(constants.OWNER && constants.OWNER.value, canonical("0xcafe"), "OWNER value");
+
+  const plain = expectSuccess(compile(files), "build without emitConstants");
+  assertEqual(plain.constants, undefined, "constants without emitConstants");
+});
+
+test("perModuleErrors groups diagnostics by file", () => {
+  const files = rootPackage({
+    "sources/good.move": `module demo::good {
+    public fun one(): u64 { 1 }
+}
+`,
+    "sources/bad.move": `module demo::bad {
+    public fun one(): u64 { true }
+}
+`,
+    "sources/worse.move": `module demo::worse {
+    public fun one(): bool { 1 }
+}
+`,
+  });
+  const result = compile(files, [STDLIB], { perModuleErrors: true, ansiColor: false });
+  expectFailure(result, "build with broken files");
+  const byFile = JSON.parse(result.raw.errorsByFile);
+  const keys = Object.keys(byFile);
+  const keyFor = (file) => keys.find((key) => key.endsWith(file));
+  assert(keyFor("sources/bad.move"), `bad.move grouped: ${keys}`);
+  assert(keyFor("sources/worse.move"), `worse.move grouped: ${keys}`);
+  assert(!keyFor("sources/good.move"), `good.move has no diagnostics: ${keys}`);
+  assertIncludes(byFile[keyFor("sources/bad.move")], "bad.move", "bad.move diagnostics");
+  assert(
+    !byFile[keyFor("sources/bad.move")].includes("worse.move"),
+    "bad.move diagnostics stay in their file"
+  );
+
+  const plain = compile(files, [STDLIB], { ansiColor: false });
+  assertEqual(plain.raw.errorsByFile, undefined, "errorsByFile without perModuleErrors");
+});
+
+test("a dependency provided twice is compiled once, or rejected on version skew", () => {
+  const shared = () =>
+    dependency("Shared", "0xa", {
+      "lib.move": `module shared::lib {
+    public fun one(): u64 { 1 }
+}
+`,
+    });
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { shared::lib::one() }
+}
+`,
+  });
+
+  const once = expectSuccess(compile(files, [STDLIB, shared()]), "single copy");
+  const twice = expectSuccess(compile(files, [STDLIB, shared(), shared()]), "identical copies");
+  assertEqual(twice.modules, once.modules, "modules");
+  assertEqual(twice.dependencies, once.dependencies, "dependencies");
+  assertEqual(twice.digest, once.digest, "digest");
+
+  const skewed = shared();
+  skewed.files["dependencies/Shared/sources/lib.move"] = `module shared::lib {
+    public fun one(): u64 { 2 }
+}
+`;
+  const result = compile(files, [STDLIB, shared(), skewed]);
+  expectFailure(result, "differing copies");
+  assertIncludes(
+    result.error,
+    "Dependency 'Shared' is provided twice with different files (version skew?)",
+    "skew error"
+  );
+  assert(
+    /dependencies\/Shared\/sources\/lib\.move \([0-9a-f]{16} vs [0-9a-f]{16}\)/.test(result.error),
+    `differing path with hashes in:\n${result.error}`
+  );
+});
+
+test("addr_subst assigns and renames dependency addresses for the root", () => {
+  // Named after its address, so the manifest entry is also its published address.
+  const lib = dependency("lib", "0xa", {
+    "m.move": `module lib::m {
+    public fun one(): u64 { 1 }
+}
+`,
+  });
+  // Addresses come from the manifest, where `extra` is left for dependents to assign.
+  delete lib.addressMapping;
+  lib.files["dependencies/lib/Move.toml"] += `extra = "_"
+`;
+  const sources = {
+    "sources/main.move": `module demo::main {
+    public fun one(): u64 { mylib::m::one() }
+    #[test]
+    fun resolved() {
+        assert!(@mylib == @0xa, 0);
+        assert!(@extra == @0xe, 1);
+        assert!(@myextra == @0xe, 2);
+    }
+}
+`,
+  };
+  const withSubst = (subst) =>
+    rootPackage(sources, {
+      manifest: `
+[dependencies]
+lib = { local = "dependencies/lib", addr_subst = { ${subst} } }
+`,
+    });
+
+  const files = withSubst(`"extra" = "0xe", "mylib" = "lib", "myextra" = "extra"`);
+  expectSuccess(compile(files, [STDLIB, lib]), "build with addr_subst");
+  const tested = runTests(files, [STDLIB, lib]);
+  assert(tested.passed, `renamed addresses resolve:\n${tested.output}`);
+
+  const reassigned = compile(
+    withSubst(`"lib" = "0xf", "extra" = "0xe", "mylib" = "lib", "myextra" = "extra"`),
+    [STDLIB, lib]
+  );
+  expectFailure(reassigned, "assigning an assigned address");
+  assertIncludes(
+    reassigned.error,
+    "addr_subst assigns 'lib' of dependency 'lib', which already assigns it 0xa",
+    "reassignment error"
+  );
+
+  const unknown = compile(withSubst(`"mylib" = "nope"`), [STDLIB, lib]);
+  expectFailure(unknown, "renaming an unknown address");
+  assertIncludes(
+    unknown.error,
+    "addr_subst renames 'mylib' from 'nope' of dependency 'lib', which does not define that address",
+    "unknown rename error"
+  );
+});
+
+test("addressMapping and publishedIdForOutput read unprefixed digits as hex", () => {
+  const lib = dependency(
+    "lib",
+    "0x10",
+    { "lib.move": "module lib::lib { public fun one(): u64 { 1 } }\n" },
+    { addressMapping: { lib: "10", std: "0x1" }, publishedIdForOutput: "20" }
+  );
+  const files = rootPackage({
+    "sources/main.move": "module demo::main { public fun f(): u64 { lib::lib::one() } }\n",
+  });
+  const output = expectSuccess(compile(files, [STDLIB, lib]), "compile");
+  assertEqual(output.addressMapping.lib, canonical("0x10"), "lib address");
+  assert(
+    output.dependencies.some((id) => canonical(id) === canonical("0x20")),
+    `dependencies should list 0x20: ${output.dependencies}`
+  );
+});
+
+test("compile_workspace applies compile's input checks and counts warnings once", () => {
+  const packages = [
+    {
+      name: "alpha",
+      files: rootPackage(
+        { "sources/a.move": "module alpha::a { fun f() { let x = 1; } }\n" },
+        { name: "alpha" }
+      ),
+    },
+    {
+      name: "beta",
+      files: rootPackage({ "sources/b.move": "module beta::b {}\n" }, { name: "beta" }),
+    },
+  ];
+  const result = wasm.compile_workspace(
+    JSON.stringify(packages),
+    JSON.stringify([STDLIB]),
+    JSON.stringify({})
+  );
+  if (!result.success) throw new Error(`compile_workspace failed:\n${result.output}`);
+  assertEqual(result.warningCount, 1, "warnings of the shared run");
+
+  const limited = wasm.compile_workspace(
+    JSON.stringify(packages),
+    JSON.stringify([STDLIB]),
+    JSON.stringify({ maxFileBytes: 16 })
+  );
+  assertEqual(limited.success, false, "file over maxFileBytes");
+  assertIncludes(limited.output, "maxFileBytes", "size limit error");
+
+  const bytecode = [
+    { name: "alpha", files: { ...packages[0].files, "build/a.mv": "bytes" } },
+  ];
+  const rejected = wasm.compile_workspace(
+    JSON.stringify(bytecode),
+    JSON.stringify([STDLIB]),
+    JSON.stringify({})
+  );
+  assertEqual(rejected.success, false, "bytecode input");
+  assertIncludes(rejected.output, "is compiled bytecode", "bytecode error");
+});
+
+test("files outside the package layout are reported as a warning, not a note", () => {
+  const files = rootPackage({
+    "sources/main.move": "module demo::main {}\n",
+    "misc/extra.move": "module demo::extra {}\n",
+  });
+  const result = compile(files);
+  const output = expectSuccess(result, "compile");
+  assertIncludes(output.warnings, "Files outside sources/", "warnings");
+  assert(
+    !(output.notes || []).some((note) => note.includes("Files outside")),
+    "the layout warning should not be a note"
+  );
+  assert(result.raw.warningCount >= 1, "warningCount counts it");
+
+  const silenced = expectSuccess(
+    compile(files, [STDLIB], { silenceWarnings: true }),
+    "compile"
+  );
+  assert(!silenced.warnings, "silenceWarnings hides builder warnings");
+});
+
+test("packageLimits: \"warn\" reports violations as warnings, not notes", () => {
+  // More modules than any protocol version's max_modules_in_publish.
+  const sources = {};
+  for (let i = 0; i < 200; i++) {
+    sources[`sources/m${i}.move`] = `module demo::m${i} {}\n`;
+  }
+  const files = rootPackage(sources);
+  const violation = "exceeding max_modules_in_publish";
+
+  const error = expectFailure(compile(files), "compile with the default limits");
+  assertIncludes(error, violation, "limit error");
+
+  const result = compile(files, [STDLIB], { packageLimits: "warn" });
+  const output = expectSuccess(result, "compile with packageLimits: warn");
+  assertIncludes(output.warnings, violation, "limit warning");
+  assert(
+    !(output.notes || []).some((note) => note.includes(violation)),
+    "the violation should not be a note"
+  );
+  assert(result.raw.warningCount >= 1, "warningCount counts it");
+});
+
+test("a bytecodeVersion the protocol does not accept fails the build", () => {
+  const files = rootPackage({ "sources/main.move": "module demo::main {}\n" });
+  const error = expectFailure(
+    compile(files, [STDLIB], { bytecodeVersion: 1000 }),
+    "compile"
+  );
+  assertIncludes(error, "Invalid bytecodeVersion 1000", "error");
+});
+
+test("PackageBuilder produces the same output as compile()", () => {
+  const lib = dependency("lib", "0xa", {
+    "lib.move": "module lib::lib { public fun one(): u64 { 1 } }\n",
+  });
+  const files = rootPackage({
+    "sources/main.move": "module demo::main { public fun f(): u64 { lib::lib::one() } }\n",
+    "tests/main_tests.move": "#[test_only]\nmodule demo::main_tests {}\n",
+  });
+  for (const options of [{}, { testMode: true }]) {
+    const expected = compile(files, [STDLIB, lib], options);
+    const actual = buildIncrementally(files, [STDLIB, lib], options);
+    assertEqual(actual.success, expected.success, `success with ${JSON.stringify(options)}`);
+    assertEqual(actual.output, expected.raw.output, `output with ${JSON.stringify(options)}`);
+  }
+
+  const broken = rootPackage({ "sources/main.move": "module demo::main { fun f( }\n" });
+  const expected = compile(broken);
+  const actual = buildIncrementally(broken);
+  assertEqual(actual.success, false, "a failing build fails");
+  assertEqual(actual.output, expected.raw.output, "failure report");
+});
+
+test("PackageBuilder keeps both copies of a path two mode variants provide", () => {
+  // Two variants of one dependency at the same paths, for different build modes.
+  const oracle = (name, modes, price) => ({
+    name,
+    modes,
+    addressMapping: { oracle: "0xa", std: "0x1" },
+    publishedIdForOutput: "0xa",
+    files: {
+      "dependencies/Oracle/Move.toml": `[package]
+name = "Oracle"
+edition = "2024.beta"
+
+[addresses]
+oracle = "0xa"
+`,
+      "dependencies/Oracle/sources/price.move": `module oracle::price { public fun get(): u64 { ${price} } }\n`,
+    },
+  });
+  const dev = oracle("OracleDev", ["dev"], 1);
+  const mainnet = oracle("OracleMainnet", ["mainnet"], 2);
+  const files = rootPackage({
+    "sources/main.move": "module demo::main { public fun f(): u64 { oracle::price::get() } }\n",
+    "tests/main_tests.move": `#[test_only]
+module demo::main_tests {
+    #[test]
+    fun dev_price() { assert!(demo::main::f() == 1, 0) }
+}
+`,
+  });
+
+  for (const mode of ["dev", "mainnet"]) {
+    for (const dependencies of [
+      [STDLIB, dev, mainnet],
+      [STDLIB, mainnet, dev],
+    ]) {
+      const expected = compile(files, dependencies, { mode });
+      const actual = buildIncrementally(files, dependencies, { mode });
+      expectSuccess(expected, `compile() in mode ${mode}`);
+      assertEqual(actual.success, true, `PackageBuilder in mode ${mode}`);
+      assertEqual(actual.output, expected.raw.output, `output in mode ${mode}`);
+    }
+  }
+
+  // The variant a mode selects is the one the build runs against.
+  const tested = runTests(files, [STDLIB, mainnet, dev], { mode: "dev" });
+  assert(tested.passed, `dev mode links the dev oracle:\n${tested.output}`);
+  const mismatched = runTests(files, [STDLIB, dev, mainnet], { mode: "mainnet" });
+  assert(!mismatched.passed, "mainnet mode links the mainnet oracle");
+});
+
+test("PackageBuilder rejects malformed options", () => {
+  const builder = new wasm.PackageBuilder();
+  builder.addFile("Move.toml", rootPackage({})["Move.toml"]);
+  builder.addFile("sources/main.move", "module demo::main {}\n");
+  const result = builder.compile("{not json");
+  assertEqual(result.success, false, "malformed options");
+  assertIncludes(result.output, "Failed to parse options", "options error");
+});
+
+test("CompilerSession results equal a fresh compile() after each edit", () => {
+  const lib = dependency("lib", "0xa", {
+    "lib.move": "module lib::lib { public fun one(): u64 { 1 } }\n",
+  });
+  const dependencies = [STDLIB, lib];
+  let files = rootPackage({
+    "sources/main.move": "module demo::main { public fun f(): u64 { lib::lib::one() } }\n",
+  });
+  const session = new wasm.CompilerSession(
+    JSON.stringify(files),
+    JSON.stringify(dependencies),
+    JSON.stringify({})
+  );
+  const check = (what) => {
+    const expected = compile(files, dependencies);
+    const actual = session.compile();
+    assertEqual(actual.success, expected.success, `${what}: success`);
+    assertEqual(actual.output, expected.raw.output, `${what}: output`);
+  };
+  check("initial state");
+
+  const edit = (path, contents) => {
+    files = { ...files, [path]: contents };
+    session.updateFile(path, contents);
+  };
+  edit("sources/main.move", "module demo::main { public fun f(): u64 { lib::lib::one() + 1 } }\n");
+  check("edited root file");
+
+  edit("sources/extra.move", "module demo::extra { public fun g(): u64 { demo::main::f() } }\n");
+  check("added root file");
+
+  edit("Move.toml", files["Move.toml"].replace('demo = "0x0"', 'demo = "0xd"'));
+  check("changed Move.toml");
+
+  edit("sources/extra.move", "module demo::extra { public fun g(): u64 { missing() } }\n");
+  check("broken root file");
+});
+
+test("CompilerSession.updateFile applies compile()'s input checks", () => {
+  const files = rootPackage({ "sources/main.move": "module demo::main {}\n" });
+  const session = new wasm.CompilerSession(
+    JSON.stringify(files),
+    JSON.stringify([STDLIB]),
+    JSON.stringify({ maxFileBytes: 200 })
+  );
+  const rejects = (path, contents, fragment) => {
+    let error;
+    try {
+      session.updateFile(path, contents);
+    } catch (e) {
+      error = String(e);
+    }
+    assertIncludes(error, fragment, `updateFile(${path})`);
+  };
+  rejects("build/main.mv", "bytes", "is compiled bytecode");
+  rejects("sources/bin.move", "module demo::bin {}\0", "contains binary data");
+  rejects("sources/big.move", `module demo::big {}\n${"// padding\n".repeat(30)}`, "maxFileBytes");
+  rejects("../escape.move", "module demo::escape {}\n", "'..' components are not allowed");
+  assert(session.compile().success, "rejected files leave the session unchanged");
+});
+
+test("preserveAddressOrder keeps a reverse-alphabetical [addresses] block in order", () => {
+  const files = rootPackage(
+    { "sources/main.move": "module demo::main {}\n" },
+    { addresses: { zeta: "0xzz", mid: "0x5", alpha: "0xqq" } }
+  );
+  const position = (error, name) => error.indexOf(`Invalid address for '${name}'`);
+
+  const declared = expectFailure(
+    compile(files, [STDLIB], { preserveAddressOrder: true }),
+    "compile with preserveAddressOrder"
+  );
+  assert(position(declared, "zeta") >= 0 && position(declared, "alpha") >= 0, declared);
+  assert(
+    position(declared, "zeta") < position(declared, "alpha"),
+    `declaration order with preserveAddressOrder:\n${declared}`
+  );
+
+  const sorted = expectFailure(compile(files), "compile");
+  assert(
+    position(sorted, "alpha") < position(sorted, "zeta"),
+    `alphabetical order by default:\n${sorted}`
+  );
+
+  const valid = rootPackage(
+    { "sources/main.move": "module demo::main {}\n" },
+    { addresses: { zeta: "0x7", mid: "0x5", alpha: "0x3" } }
+  );
+  const output = expectSuccess(
+    compile(valid, [STDLIB], { preserveAddressOrder: true }),
+    "compile a valid block"
+  );
+  assertEqual(
+    output.buildInfo.declaredAddressOrder,
+    ["demo", "zeta", "mid", "alpha"],
+    "declaredAddressOrder"
+  );
+});
+
+test("per-test gas is stable across runs and counted for an aborting test", () => {
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    fun work(n: u64): u64 {
+        let mut total = 0;
+        let mut i = 0;
+        while (i < n) { total = total + i; i = i + 1; };
+        total
+    }
+
+    #[test]
+    fun sums() { assert!(work(100) == 4950, 0) }
+
+    #[test, expected_failure(abort_code = 7)]
+    fun aborts() {
+        work(100);
+        abort 7
+    }
+}
+`,
+  });
+  const first = runTests(files);
+  assert(first.passed, `first run:\n${first.output}`);
+  const second = runTests(files);
+  assert(second.passed, `second run:\n${second.output}`);
+  assertEqual(second.gas.tests, first.gas.tests, "per-test gas across runs");
+
+  const byName = (suffix) => first.gas.tests.find((entry) => entry.name.endsWith(suffix));
+  const sums = byName("::main::sums");
+  const aborts = byName("::main::aborts");
+  assert(sums && aborts, `both tests reported: ${JSON.stringify(first.gas.tests)}`);
+  assert(sums.gasUsed > 0, "a passing test uses gas");
+  assertEqual(aborts.aborted, true, "aborted flag");
+  assert(aborts.gasUsed > 0, `an aborting test reports the gas used before the abort: ${aborts.gasUsed}`);
+  assertEqual(
+    first.gas.totalGasUsed,
+    sums.gasUsed + aborts.gasUsed,
+    "totalGasUsed sums the tests"
+  );
+});
+
+test("ansiColor: false keeps escape codes out of compile errors", () => {
+  const files = rootPackage({
+    "sources/main.move": "module demo::main { fun f(): u64 { missing() } }\n",
+  });
+  const error = expectFailure(compile(files, [STDLIB], { ansiColor: false }), "compile");
+  assertIncludes(error, "error", "error report");
+  assert(!error.includes("\x1b"), `escape codes in the error:\n${JSON.stringify(error)}`);
+});
+
+test("ansiColor: false keeps escape codes out of warnings", () => {
+  const files = rootPackage({
+    "sources/main.move": "module demo::main { fun f() { let x = 1; } }\n",
+  });
+  const output = expectSuccess(compile(files, [STDLIB], { ansiColor: false }), "compile");
+  assertIncludes(output.warnings, "warning", "warning report");
+  assert(
+    !output.warnings.includes("\x1b"),
+    `escape codes in the warnings:\n${JSON.stringify(output.warnings)}`
+  );
+});
+
+test("ansiColor: false keeps escape codes out of test reports", () => {
+  const files = rootPackage({
+    "sources/main.move": `module demo::main {
+    #[test]
+    fun fails() { assert!(1 == 2, 0) }
+}
+`,
+  });
+  const result = runTests(files, [STDLIB], { ansiColor: false });
+  assert(!result.passed, "the failing test fails");
+  assertIncludes(result.output, "fails", "test report");
+  assert(
+    !result.output.includes("\x1b"),
+    `escape codes in the test report:\n${JSON.stringify(result.output)}`
+  );
+});
+
+// ---------------------------------------------------------------------------
+
+let failed = 0;
+for (const { name, fn } of cases) {
+  if (FILTER && !name.includes(FILTER)) continue;
+  try {
+    await fn();
+    console.log(`✅ ${name}`);
+  } catch (e) {
+    failed++;
+    console.log(`❌ ${name}\n   ${e.message.replace(/\n/g, "\n   ")}`);
+  }
+}
+
+if (failed > 0) {
+  process.exit(1);
+}
@@ -0,0 +1,109 @@
+import path from "path";
+import os from "os";
+import fs from "fs";
+import { execFileSync } from "child_process";
+import { fileURLToPath } from "url";
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+// usage: node memory_compare.mjs
+// Peak memory of one build of a root package against the resolved Sui framework, through
+// `compile()` (one `files`/`dependencies` JSON string each) and through `PackageBuilder`
+// (one call per file). Each API runs in a fresh process, since wasm linear memory never
+// shrinks; the process's max RSS is its peak. Resolving the framework uses the network
+// (set GITHUB_TOKEN to avoid rate limits). Runs against the full build (`npm run build`).
+const DIST_DIR = path.resolve(__dirname, "../../dist/full");
+
+const ROOT = {
+  "Move.toml": `[package]
+name = "demo"
+edition = "2024"
+
+[addresses]
+demo = "0x0"
+`,
+  "sources/main.move": `module demo::main {
+    public fun mint(ctx: &mut TxContext): sui::coin::Coin<sui::sui::SUI> {
+        sui::coin::zero(ctx)
+    }
+}
+`,
+};
+
+async function measure(api, inputPath) {
+  const { initMoveCompiler, getWasmBindings } = await import(
+    path.join(DIST_DIR, "index.js")
+  );
+  await initMoveCompiler();
+  const wasm = await getWasmBindings();
+  const { files, dependencies } = JSON.parse(fs.readFileSync(inputPath, "utf8"));
+  const baseline = process.resourceUsage().maxRSS;
+
+  let result;
+  if (api === "compile") {
+    result = wasm.compile(
+      JSON.stringify(files),
+      JSON.stringify(dependencies),
+      JSON.stringify({})
+    );
+  } else {
+    const builder = new wasm.PackageBuilder();
+    for (const [file, contents] of Object.entries(files)) {
+      builder.addFile(file, contents);
+    }
+    for (const { name, files: depFiles, ...info } of dependencies) {
+      builder.addDependency(name, JSON.stringify(info));
+      for (const [file, contents] of Object.entries(depFiles)) {
+        builder.addDependencyFile(name, file, contents);
+      }
+    }
+    result = builder.compile(JSON.stringify({}));
+  }
+  if (!result.success) throw new Error(`${api} failed:\n${result.output}`);
+  const peak = process.resourceUsage().maxRSS;
+  console.log(JSON.stringify({ api, baselineKiB: baseline, peakKiB: peak }));
+}
+
+async function main() {
+  if (process.argv[2] === "--measure") {
+    await measure(process.argv[3], process.argv[4]);
+    return;
+  }
+
+  const { resolveDependencies } = await import(path.join(DIST_DIR, "index.js"));
+  const resolved = await resolveDependencies({
+    files: ROOT,
+    network: "mainnet",
+    githubToken: process.env.GITHUB_TOKEN,
+  });
+  const input = {
+    files: JSON.parse(resolved.files),
+    dependencies: JSON.parse(resolved.dependencies),
+  };
+  const sourceBytes = [input.files, ...input.dependencies.map((dep) => dep.files)]
+    .flatMap((files) => Object.values(files))
+    .reduce((total, contents) => total + contents.length, 0);
+  const inputPath = path.join(os.tmpdir(), `memory_compare_${process.pid}.json`);
+  fs.writeFileSync(inputPath, JSON.stringify(input));
+
+  try {
+    console.log(`Input: ${input.dependencies.length} dependencies, ${sourceBytes} source bytes`);
+    console.log("api      baseline MiB  peak MiB  build MiB");
+    for (const api of ["compile", "builder"]) {
+      const line = execFileSync(process.execPath, [__filename, "--measure", api, inputPath], {
+        encoding: "utf8",
+      })
+        .trim()
+        .split("\n")
+        .pop();
+      const { baselineKiB, peakKiB } = JSON.parse(line);
+      const mib = (kib) => (kib / 1024).toFixed(1).padStart(8);
+      console.log(`${api.padEnd(8)} ${mib(baselineKiB)}    ${mib(peakKiB)}  ${mib(peakKiB - baselineKiB)}`);
+    }
+  } finally {
+    fs.unlinkSync(inputPath);
+  }
+}
+
+await main();
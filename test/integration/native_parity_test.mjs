@@ -0,0 +1,113 @@
+import { promises as fs } from "fs";
+import path from "path";
+import { fileURLToPath } from "url";
+import { spawnSync } from "child_process";
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+// usage: node native_parity_test.mjs
+// Builds each package with the wasm `compile` and with the native `native_compile` example
+// (`npm run build:native`) from the same inputs, and requires identical outputs.
+const DIST_DIR = path.resolve(__dirname, "../../dist/full");
+const NATIVE_BIN =
+  process.env.NATIVE_COMPILE ||
+  path.resolve(__dirname, "../../sui/target/release/examples/native_compile");
+
+const { initMoveCompiler, resolveDependencies, getWasmBindings, fetchPackageFromGitHub } =
+  await import(path.join(DIST_DIR, "index.js"));
+
+const FIXTURES_DIR = path.join(__dirname, "fixtures");
+
+const REPOS = {
+  nautilus: {
+    url: "https://github.com/MystenLabs/nautilus",
+    commit: "d919402aadf15e21b3cf31515b3a46d1ca6965e4",
+    packagePath: "move/enclave",
+  },
+  deepbook: {
+    url: "https://github.com/MystenLabs/deepbookv3",
+    commit: "d3206b717c6f63593fae14d1ff9e1ec055f051bd",
+    packagePath: "packages/deepbook",
+  },
+};
+
+// Fixtures cached by fidelity_test.mjs are reused; otherwise the package is fetched.
+async function loadPackage(name, config) {
+  const packageDir = path.join(FIXTURES_DIR, name, config.packagePath);
+  if (await fs.stat(path.join(packageDir, "Move.toml")).catch(() => false)) {
+    const files = {};
+    async function readDirRecursive(currentDir) {
+      for (const entry of await fs.readdir(currentDir, { withFileTypes: true })) {
+        const fullPath = path.join(currentDir, entry.name);
+        if (entry.isDirectory()) {
+          if (entry.name === "build" || entry.name === ".git") continue;
+          await readDirRecursive(fullPath);
+        } else if (/\.(move|toml|lock)$/.test(entry.name)) {
+          files[path.relative(packageDir, fullPath)] = await fs.readFile(fullPath, "utf-8");
+        }
+      }
+    }
+    await readDirRecursive(packageDir);
+    return files;
+  }
+  return fetchPackageFromGitHub(`${config.url}/tree/${config.commit}/${config.packagePath}`, {
+    githubToken: process.env.GITHUB_TOKEN,
+    includeLock: true,
+  });
+}
+
+function compileNative(files, dependencies, options) {
+  const result = spawnSync(NATIVE_BIN, [], {
+    input: JSON.stringify({ files, dependencies, options }),
+    encoding: "utf-8",
+    maxBuffer: 200 * 1024 * 1024,
+  });
+  if (result.status !== 0) {
+    throw new Error(`native_compile failed: ${result.stderr || result.error}`);
+  }
+  return JSON.parse(result.stdout);
+}
+
+await initMoveCompiler();
+const wasm = await getWasmBindings();
+const options = { silenceWarnings: true };
+let failed = 0;
+
+for (const [name, config] of Object.entries(REPOS)) {
+  const files = await loadPackage(name, config);
+  const resolved = await resolveDependencies({ files, network: "mainnet" });
+  const dependencies = JSON.parse(resolved.dependencies);
+
+  const wasmResult = wasm.compile(
+    JSON.stringify(resolved.files),
+    resolved.dependencies,
+    JSON.stringify(options)
+  );
+  const nativeResult = compileNative(resolved.files, dependencies, options);
+
+  const problems = [];
+  if (wasmResult.success !== nativeResult.success) {
+    problems.push(`success: wasm ${wasmResult.success}, native ${nativeResult.success}`);
+  } else if (!wasmResult.success) {
+    problems.push(`both builds failed: ${wasmResult.output}`);
+  } else {
+    const wasmOutput = JSON.parse(wasmResult.output);
+    for (const field of ["modules", "dependencies", "digest", "lockfile"]) {
+      if (JSON.stringify(wasmOutput[field]) !== JSON.stringify(nativeResult.output[field])) {
+        problems.push(`${field} differs`);
+      }
+    }
+  }
+
+  if (problems.length === 0) {
+    console.log(`✅ ${name}: native output matches wasm`);
+  } else {
+    failed++;
+    console.log(`❌ ${name}: ${problems.join("; ")}`);
+  }
+}
+
+if (failed > 0) {
+  process.exit(1);
+}